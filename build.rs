@@ -1 +1,15 @@
-fn main() { embed_resource::compile("app.rc", embed_resource::NONE); }
\ No newline at end of file
+use std::path::Path;
+use std::{env, fs};
+
+/// The wordlist built into the executable, used when no words.txt is found.
+const DEFAULT_WORDLIST: &str = "wordlists/passgen-7776.txt";
+
+fn main() {
+    embed_resource::compile("app.rc", embed_resource::NONE);
+
+    println!("cargo:rerun-if-changed={}", DEFAULT_WORDLIST);
+    let words = fs::read(DEFAULT_WORDLIST).expect("Failed to read the default wordlist");
+    let compressed = miniz_oxide::deflate::compress_to_vec(&words, 10);
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is not set");
+    fs::write(Path::new(&out_dir).join("default_words.deflate"), compressed).expect("Failed to write the wordlist");
+}
@@ -0,0 +1,50 @@
+use rand::Rng;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// The most passwords one batch may hold.
+pub const MAX_BATCH: usize = 10_000;
+
+/// Generates `count` distinct passwords by calling `generate` until enough of them differ.
+///
+/// Repeats are dropped and drawn again, so the batch is a uniformly random set of
+/// distinct results. Gives up when the settings allow too few passwords to fill it.
+pub fn generate_batch<R: Rng + ?Sized>(
+    count: usize,
+    rng: &mut R,
+    mut generate: impl FnMut(&mut R) -> Result<String, String>,
+) -> Result<Vec<String>, String> {
+    if !(1..=MAX_BATCH).contains(&count) {
+        return Err(format!("A batch holds between 1 and {} passwords.", MAX_BATCH));
+    }
+
+    let attempts = count * 20 + 1000;
+    let mut seen = HashSet::with_capacity(count);
+    let mut batch = Vec::with_capacity(count);
+    for _ in 0..attempts {
+        let password = generate(rng)?;
+        if seen.insert(password.clone()) {
+            batch.push(password);
+            if batch.len() == count {
+                return Ok(batch);
+            }
+        }
+    }
+    Err(format!(
+        "Only {} distinct passwords turned up in {} tries; the settings allow too few for a batch of {}.",
+        batch.len(),
+        attempts,
+        count
+    ))
+}
+
+/// Writes a batch to a file, one password per line.
+pub fn export_batch(batch: &[String], path: &Path) -> String {
+    let mut content = batch.join("\n");
+    content.push('\n');
+    match fs::write(path, content) {
+        Ok(_) => format!("Exported {} passwords to {:?}", batch.len(), path),
+        Err(e) => format!("Error exporting passwords: {}", e),
+    }
+}
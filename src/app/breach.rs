@@ -0,0 +1,369 @@
+use crate::app::strength::L33T_TABLE;
+use crate::app::unicode::nfc;
+use memmap2::Mmap;
+use rand::Rng;
+use sha1::{Digest, Sha1};
+use std::f64::consts::LN_2;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Marks a Bloom filter file written by `build_bloom_filter`.
+const BLOOM_MAGIC: &[u8; 8] = b"PGBLOOM1";
+/// The size of a Bloom filter header: the magic, the hash count, the bit count and the entry count.
+const BLOOM_HEADER: usize = 28;
+/// The length of a SHA-1 hash in hex digits.
+const SHA1_HEX: usize = 40;
+/// How many generated passwords in a row may be rejected before generation gives up.
+pub const MAX_SCREEN_ATTEMPTS: usize = 100;
+
+/// What a breached password list knows about a password.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BreachResult {
+    NotFound,
+    /// Listed in a hash file, with the number of times it was seen in breaches.
+    Found(u64),
+    /// Matched by a Bloom filter, which has a small chance of false alarms.
+    ProbablyFound,
+}
+
+/// A local list of breached passwords, kept memory-mapped so large lists open instantly.
+pub enum BreachList {
+    /// A Have I Been Pwned SHA-1 file: one "HASH:COUNT" line per password, sorted by hash.
+    Sorted(Mmap),
+    /// A Bloom filter built from such a file by `build_bloom_filter`.
+    Bloom { data: Mmap, hashes: u32, bits: u64 },
+}
+
+/// Opens a breached password list, telling a Bloom filter from a hash file by its header.
+pub fn open_breach_list(path: &Path) -> Result<BreachList, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Could not open the breached password list {}: {}", path.display(), e))?;
+    // SAFETY: the map is only read, and the list is not expected to change while it is open.
+    let data = unsafe { Mmap::map(&file) }
+        .map_err(|e| format!("Could not read the breached password list {}: {}", path.display(), e))?;
+
+    if data.starts_with(BLOOM_MAGIC) {
+        let damaged = || format!("The Bloom filter {} is damaged.", path.display());
+        if data.len() < BLOOM_HEADER {
+            return Err(damaged());
+        }
+        let hashes = u32::from_le_bytes(data[8..12].try_into().unwrap());
+        let bits = u64::from_le_bytes(data[12..20].try_into().unwrap());
+        if hashes == 0 || bits == 0 || ((data.len() - BLOOM_HEADER) as u64) < bits.div_ceil(8) {
+            return Err(damaged());
+        }
+        return Ok(BreachList::Bloom { data, hashes, bits });
+    }
+
+    match data.iter().position(|&b| b == b':') {
+        Some(SHA1_HEX) if data[..SHA1_HEX].iter().all(u8::is_ascii_hexdigit) => Ok(BreachList::Sorted(data)),
+        Some(32) => Err(format!(
+            "{} holds NTLM hashes; download the SHA-1 version of the Have I Been Pwned list instead.",
+            path.display()
+        )),
+        _ => Err(format!(
+            "{} is neither a Have I Been Pwned SHA-1 hash file nor a Bloom filter.",
+            path.display()
+        )),
+    }
+}
+
+impl BreachList {
+    /// Looks a password up by its SHA-1 hash.
+    pub fn lookup(&self, password: &str) -> BreachResult {
+        let digest: [u8; 20] = Sha1::digest(password.as_bytes()).into();
+        match self {
+            BreachList::Sorted(data) => {
+                let hex: String = digest.iter().map(|b| format!("{:02X}", b)).collect();
+                find_hash(data, hex.as_bytes()).map_or(BreachResult::NotFound, BreachResult::Found)
+            }
+            BreachList::Bloom { data, hashes, bits } => {
+                let bitset = &data[BLOOM_HEADER..];
+                if bloom_indices(&digest, *hashes, *bits).all(|i| bitset[(i / 8) as usize] & (1 << (i % 8)) != 0) {
+                    BreachResult::ProbablyFound
+                } else {
+                    BreachResult::NotFound
+                }
+            }
+        }
+    }
+
+    /// A short description of the list for status messages.
+    pub fn describe(&self) -> &'static str {
+        match self {
+            BreachList::Sorted(_) => "a breached password hash file",
+            BreachList::Bloom { .. } => "a breached password Bloom filter",
+        }
+    }
+}
+
+/// Binary searches a sorted hash file for `target` (uppercase hex) and returns its count.
+///
+/// The search works on byte offsets, so each step finds the line around the middle
+/// of the remaining range. `low` always sits at the start of a line.
+fn find_hash(data: &[u8], target: &[u8]) -> Option<u64> {
+    let (mut low, mut high) = (0, data.len());
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let start = data[low..mid].iter().rposition(|&b| b == b'\n').map_or(low, |i| low + i + 1);
+        let end = data[start..high].iter().position(|&b| b == b'\n').map_or(high, |i| start + i);
+        let line = &data[start..end];
+        let hash = &line[..line.len().min(SHA1_HEX)];
+        match hash.iter().map(u8::to_ascii_uppercase).cmp(target.iter().copied()) {
+            std::cmp::Ordering::Equal => {
+                let count = line.get(SHA1_HEX + 1..).unwrap_or_default();
+                return Some(std::str::from_utf8(count).ok()?.trim().parse().unwrap_or(1));
+            }
+            std::cmp::Ordering::Less => low = end + 1,
+            std::cmp::Ordering::Greater => high = start,
+        }
+    }
+    None
+}
+
+/// The bits a SHA-1 digest sets in a Bloom filter, by double hashing two halves of the digest.
+fn bloom_indices(digest: &[u8; 20], hashes: u32, bits: u64) -> impl Iterator<Item = u64> {
+    let h1 = u64::from_le_bytes(digest[0..8].try_into().unwrap());
+    let h2 = u64::from_le_bytes(digest[8..16].try_into().unwrap()) | 1;
+    (0..hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % bits)
+}
+
+/// Parses 40 hex digits into a SHA-1 digest.
+fn parse_hex_digest(hex: &[u8]) -> Option<[u8; 20]> {
+    let mut digest = [0u8; 20];
+    for (byte, pair) in digest.iter_mut().zip(hex.chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(digest)
+}
+
+/// Builds a Bloom filter from a Have I Been Pwned SHA-1 hash file.
+///
+/// The filter is much smaller than the hash file and answers just as fast, at the cost
+/// of flagging about one password in `1 / false_positive_rate` that was never breached.
+/// Returns a message describing the filter written.
+pub fn build_bloom_filter(hash_file: &Path, output: &Path, false_positive_rate: f64) -> Result<String, String> {
+    if !(false_positive_rate > 0.0 && false_positive_rate < 1.0) {
+        return Err("The false positive rate must be between 0 and 1.".to_string());
+    }
+    let data = match open_breach_list(hash_file)? {
+        BreachList::Sorted(data) => data,
+        BreachList::Bloom { .. } => return Err(format!("{} is already a Bloom filter.", hash_file.display())),
+    };
+
+    let lines = || data.split(|&b| b == b'\n').filter(|line| line.len() >= SHA1_HEX);
+    let entries = lines().count() as u64;
+    let bits = ((-(entries as f64) * false_positive_rate.ln()) / (LN_2 * LN_2)).ceil().max(64.0) as u64;
+    let hashes = ((bits as f64 / entries as f64) * LN_2).round().clamp(1.0, 32.0) as u32;
+
+    let mut bitset = vec![0u8; bits.div_ceil(8) as usize];
+    for (number, line) in lines().enumerate() {
+        let digest = parse_hex_digest(&line[..SHA1_HEX])
+            .ok_or_else(|| format!("Entry {} of {} is not a SHA-1 hash.", number + 1, hash_file.display()))?;
+        for i in bloom_indices(&digest, hashes, bits) {
+            bitset[(i / 8) as usize] |= 1 << (i % 8);
+        }
+    }
+
+    let write = || -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(output)?);
+        writer.write_all(BLOOM_MAGIC)?;
+        writer.write_all(&hashes.to_le_bytes())?;
+        writer.write_all(&bits.to_le_bytes())?;
+        writer.write_all(&entries.to_le_bytes())?;
+        writer.write_all(&bitset)?;
+        writer.flush()
+    };
+    write().map_err(|e| format!("Could not write the Bloom filter {}: {}", output.display(), e))?;
+
+    Ok(format!(
+        "Built a Bloom filter of {} hashes in {} ({:.1} MB, about one false alarm in {:.0} checks).",
+        entries,
+        output.display(),
+        (BLOOM_HEADER + bitset.len()) as f64 / 1e6,
+        1.0 / false_positive_rate
+    ))
+}
+
+/// Reads an organisation's banned words, one per line; blank lines and `#` comments are skipped.
+pub fn load_banned_words(path: &Path) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read the banned words file {}: {}", path.display(), e))?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| nfc(line).to_lowercase())
+        .collect())
+}
+
+/// Replaces common l33t substitutions with the first letter they stand for.
+fn unl33t(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            let substitution = L33T_TABLE.iter().find(|(symbol, _)| *symbol == c);
+            substitution.and_then(|(_, letters)| letters.chars().next()).unwrap_or(c)
+        })
+        .collect()
+}
+
+/// The offline checks a password must pass: a breached password list and a banned words list.
+#[derive(Default)]
+pub struct PasswordScreen {
+    pub breaches: Option<BreachList>,
+    pub banned_words: Vec<String>,
+}
+
+impl PasswordScreen {
+    /// Opens the lists at the given paths; an empty path leaves that check off.
+    pub fn load(breached_file: &str, banned_words_file: &str) -> Result<Self, String> {
+        let breaches = match breached_file.trim() {
+            "" => None,
+            path => Some(open_breach_list(Path::new(path))?),
+        };
+        let banned_words = match banned_words_file.trim() {
+            "" => Vec::new(),
+            path => load_banned_words(Path::new(path))?,
+        };
+        Ok(PasswordScreen { breaches, banned_words })
+    }
+
+    /// Whether no check is turned on.
+    pub fn is_empty(&self) -> bool {
+        self.breaches.is_none() && self.banned_words.is_empty()
+    }
+
+    /// Why a password must not be used, or `None` if it passes every check.
+    pub fn rejection(&self, password: &str) -> Option<String> {
+        if !self.banned_words.is_empty() {
+            let plain = nfc(password).to_lowercase();
+            let unl33ted = unl33t(&plain);
+            if let Some(word) = self.banned_words.iter().find(|w| plain.contains(*w) || unl33ted.contains(*w)) {
+                return Some(format!("it contains the banned word '{}'", word));
+            }
+        }
+        match self.breaches.as_ref()?.lookup(password) {
+            BreachResult::NotFound => None,
+            BreachResult::Found(count) => {
+                Some(format!("it appears in the breached password list (seen {} times)", count))
+            }
+            BreachResult::ProbablyFound => Some("it is probably in the breached password list".to_string()),
+        }
+    }
+
+    /// A line for the strength report saying how the password fared, or `None` with no checks on.
+    pub fn report(&self, password: &str) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(match self.rejection(password) {
+            Some(reason) => format!("Do not use this password, whatever its score: {}.", reason),
+            None => format!("Not found in {}.", self.describe()),
+        })
+    }
+
+    /// Names the lists in use, for status messages.
+    pub fn describe(&self) -> String {
+        match (&self.breaches, self.banned_words.len()) {
+            (None, 0) => "no breached or banned password lists".to_string(),
+            (Some(list), 0) => list.describe().to_string(),
+            (None, n) => format!("the banned words list ({} words)", n),
+            (Some(list), n) => format!("{} or the banned words list ({} words)", list.describe(), n),
+        }
+    }
+}
+
+/// Draws passwords from `generate` until one passes the screen.
+pub fn generate_screened<R: Rng + ?Sized>(
+    screen: &PasswordScreen,
+    rng: &mut R,
+    mut generate: impl FnMut(&mut R) -> Result<String, String>,
+) -> Result<String, String> {
+    let mut reason = String::new();
+    for _ in 0..MAX_SCREEN_ATTEMPTS {
+        let password = generate(rng)?;
+        match screen.rejection(&password) {
+            None => return Ok(password),
+            Some(why) => reason = why,
+        }
+    }
+    Err(format!(
+        "{} generated passwords in a row were rejected, the last because {}. Loosen the settings or the banned words.",
+        MAX_SCREEN_ATTEMPTS, reason
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sha1_hex(password: &str) -> String {
+        Sha1::digest(password.as_bytes()).iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    /// Writes a sorted hash file of `password0` to `password199`, seen 1 to 200 times, and returns its entries.
+    fn write_hash_file(name: &str, line_ending: &str) -> (std::path::PathBuf, Vec<(String, u64)>) {
+        let mut entries: Vec<(String, u64)> =
+            (0..200).map(|i| (format!("password{}", i), i as u64 + 1)).collect();
+        entries.sort_by_key(|(password, _)| sha1_hex(password));
+        let text: String = entries
+            .iter()
+            .map(|(password, count)| format!("{}:{}{}", sha1_hex(password), count, line_ending))
+            .collect();
+        let path = std::env::temp_dir().join(format!("passgen-test-{}-{}", std::process::id(), name));
+        fs::write(&path, text).unwrap();
+        (path, entries)
+    }
+
+    fn check_sorted_lookups(name: &str, line_ending: &str) {
+        let (path, entries) = write_hash_file(name, line_ending);
+        let list = open_breach_list(&path).unwrap();
+        assert!(matches!(list, BreachList::Sorted(_)));
+        // The first line, the last line, and every line between them.
+        for (password, count) in &entries {
+            assert_eq!(list.lookup(password), BreachResult::Found(*count), "{}", password);
+        }
+        assert_eq!(list.lookup("not in the list"), BreachResult::NotFound);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn sorted_hash_file_lookup() {
+        check_sorted_lookups("lf.txt", "\n");
+    }
+
+    #[test]
+    fn sorted_hash_file_lookup_with_crlf_endings() {
+        check_sorted_lookups("crlf.txt", "\r\n");
+    }
+
+    #[test]
+    fn find_hash_edges() {
+        let data = b"0000000000000000000000000000000000000000:5\r\n\
+                     8888888888888888888888888888888888888888:7\r\n\
+                     ffffffffffffffffffffffffffffffffffffffff:9";
+        assert_eq!(find_hash(data, &[b'0'; 40]), Some(5));
+        assert_eq!(find_hash(data, &[b'8'; 40]), Some(7));
+        assert_eq!(find_hash(data, &[b'F'; 40]), Some(9));
+        assert_eq!(find_hash(data, &[b'1'; 40]), None);
+        assert_eq!(find_hash(data, &[b'9'; 40]), None);
+        assert_eq!(find_hash(b"", &[b'0'; 40]), None);
+    }
+
+    #[test]
+    fn bloom_filter_has_no_false_negatives() {
+        let (hash_file, entries) = write_hash_file("bloom-source.txt", "\n");
+        let filter = hash_file.with_extension("bloom");
+        build_bloom_filter(&hash_file, &filter, 0.001).unwrap();
+        let list = open_breach_list(&filter).unwrap();
+        assert!(matches!(list, BreachList::Bloom { .. }));
+        for (password, _) in &entries {
+            assert_eq!(list.lookup(password), BreachResult::ProbablyFound, "{}", password);
+        }
+        let false_alarms = (0..1000).filter(|i| list.lookup(&format!("other{}", i)) != BreachResult::NotFound).count();
+        assert!(false_alarms < 20, "{} false alarms", false_alarms);
+        fs::remove_file(hash_file).unwrap();
+        fs::remove_file(filter).unwrap();
+    }
+}
@@ -1,54 +1,681 @@
-use crate::app::config::load_config_map;
-use crate::app::password::{generate_char_password, generate_word_password};
-use crate::app::state::CharacterSets;
-use crate::app::utils::{get_app_directory, load_words_from_file};
+use crate::app::batch::generate_batch;
+use crate::app::breach::{build_bloom_filter, generate_screened, PasswordScreen};
+use crate::app::config::{
+    default_tagged_words_file, load_character_classes, load_config_map, load_profiles, load_repetition_rules,
+    load_word_filters, load_word_style, load_wordlists, profile_settings, unknown_wordlist, DEFAULT_WORDLIST,
+};
+use crate::app::derive::{derive_password, DerivationVersion, CURRENT_DERIVATION_VERSION};
+use crate::app::diceware::{check_dice_style, dice_list_error, generate_dice_passphrase};
+use crate::app::entropy::{
+    char_password_entropy, dice_passphrase_entropy, pin_entropy, pronounceable_password_entropy,
+    sentence_passphrase_entropy, template_password_entropy, word_password_entropy,
+};
+use crate::app::password::{
+    build_char_sampler, char_password_units, excluded_characters, remove_excluded_from_classes,
+    remove_excluded_from_sets, WordSampler,
+};
+use crate::app::pin::generate_pin;
+use crate::app::policy::parse_password_rules;
+use crate::app::pronounceable::PronounceableSampler;
+use crate::app::rng::system_rng;
+use crate::app::sentence::{
+    create_default_tagged_words_file, generate_sentence_passphrase, load_tagged_words, weak_sentence_warning,
+    DEFAULT_GRAMMAR,
+};
+use crate::app::state::{CharacterClass, CharacterSets, CliArgs, LengthUnit, RepetitionRules, WordFilters, WordStyle};
+use crate::app::strength::{check_password_strength, current_year};
+use crate::app::template::generate_template_password;
+use crate::app::utils::get_app_directory;
+use crate::app::wordlist::{load_default_words, load_words_from_file, Wordlist};
 use arboard::Clipboard;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+/// Printed whenever passwords are generated from a fixed seed.
+const SEEDED_WARNING: &str = "WARNING: --seed is for tests only. Anyone who knows the seed can reproduce \
+                              every password below. Do NOT use them for real accounts.";
+
+/// A deterministic ChaCha20 generator for reproducible test fixtures.
+///
+/// The same seed always produces the same passwords, so this must never be used
+/// for anything but tests. It is private to this module so the GUI cannot select it.
+fn seeded_rng(seed: u64) -> Box<dyn RngCore> {
+    Box::new(ChaCha20Rng::seed_from_u64(seed))
+}
+
+/// Picks the random number generator for a CLI run: the system CSPRNG, or the
+/// seeded test generator when `--seed` is given, with a warning on stderr.
+fn cli_rng(args: &CliArgs) -> Box<dyn RngCore> {
+    match args.seed {
+        Some(seed) => {
+            eprintln!("{}", SEEDED_WARNING);
+            seeded_rng(seed)
+        }
+        None => system_rng(),
+    }
+}
+
+/// Reads config.toml for a CLI run, with the settings of `--profile` taking the place of the top-level ones.
+fn cli_config(args: &CliArgs) -> HashMap<String, String> {
+    let mut config = load_config_map(&get_app_directory().join("config.toml"));
+    if let Some(settings) = args.profile.as_deref().and_then(|name| profile_settings(&config, name.trim())) {
+        config.extend(settings);
+    }
+    config
+}
+
+/// Checks that the profile named by `--profile` exists.
+pub fn check_cli_profile(args: &CliArgs) -> Result<(), String> {
+    let Some(name) = &args.profile else {
+        return Ok(());
+    };
+    let config = load_config_map(&get_app_directory().join("config.toml"));
+    if profile_settings(&config, name.trim()).is_some() {
+        return Ok(());
+    }
+    let names: Vec<String> = load_profiles(&config).into_keys().collect();
+    Err(match names.is_empty() {
+        true => format!("No profile named '{}'; config.toml has no profiles yet.", name),
+        false => format!("No profile named '{}'. Available: {}.", name, names.join(", ")),
+    })
+}
+
+/// Opens the breached password and banned words lists for a CLI run; the flags take precedence over config.toml.
+fn cli_password_screen(args: &CliArgs) -> Result<PasswordScreen, String> {
+    let config = cli_config(args);
+    let path = |flag: &Option<PathBuf>, key: &str| match flag {
+        Some(path) => path.to_string_lossy().to_string(),
+        None => config.get(key).cloned().unwrap_or_default(),
+    };
+    PasswordScreen::load(&path(&args.breached, "breached_passwords_file"), &path(&args.banned, "banned_words_file"))
+}
+
+/// Warns on stderr when a password that cannot simply be drawn again fails the password screen.
+fn warn_if_rejected(args: &CliArgs, password: &str, advice: &str) {
+    match cli_password_screen(args) {
+        Ok(screen) => {
+            if let Some(reason) = screen.rejection(password) {
+                eprintln!("Warning: Do not use this password: {}. {}", reason, advice);
+            }
+        }
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+/// Reads a secret without echo from the terminal, or as one line from stdin when it is piped.
+fn read_secret(prompt: &str) -> io::Result<String> {
+    if io::stdin().is_terminal() {
+        rpassword::prompt_password(prompt)
+    } else {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line).map(|_| line.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+/// Prints `--count` distinct results of `generate`, one per line. A single result
+/// is also copied to the clipboard; a batch is not, so bulk runs leave it alone.
+/// Returns what was printed, if anything.
+fn print_generated(
+    args: &CliArgs,
+    noun: &str,
+    mut generate: impl FnMut(&mut Box<dyn RngCore>) -> Result<String, String>,
+) -> Option<Vec<String>> {
+    let screen = match cli_password_screen(args) {
+        Ok(screen) => screen,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return None;
+        }
+    };
+    let screened = |rng: &mut Box<dyn RngCore>| generate_screened(&screen, rng, &mut generate);
+    match generate_batch(args.count, &mut cli_rng(args), screened) {
+        Ok(batch) => {
+            for password in &batch {
+                println!("{}", password);
+            }
+            if let [password] = &batch[..] {
+                if let Ok(mut clipboard) = Clipboard::new() {
+                    if clipboard.set_text(password.clone()).is_ok() {
+                        eprintln!("{} copied to clipboard.", noun);
+                    }
+                }
+            }
+            Some(batch)
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            None
+        }
+    }
+}
+
+/// Reads the character classes from config.toml for the CLI modes, together with
+/// the characters excluded in the config or on the command line.
+fn load_cli_character_classes(args: &CliArgs) -> (Vec<CharacterClass>, HashSet<String>) {
+    let (classes, excluded, _) = load_cli_character_settings(args);
+    (classes, excluded)
+}
+
+/// Like `load_cli_character_classes`, also reading the repetition and sequence
+/// rules, with the command-line flags taking precedence over the config.
+fn load_cli_character_settings(args: &CliArgs) -> (Vec<CharacterClass>, HashSet<String>, RepetitionRules) {
+    let config = cli_config(args);
+
+    let exclude_ambiguous =
+        args.no_ambiguous || config.get("exclude_ambiguous").and_then(|s| s.parse().ok()).unwrap_or(false);
+    let mut excluded_chars = config.get("excluded_chars").cloned().unwrap_or_default();
+    excluded_chars.push_str(args.exclude.as_deref().unwrap_or_default());
+
+    let mut rules = load_repetition_rules(&config);
+    if let Some(max) = args.max_repeats {
+        rules.max_occurrences = max;
+    }
+    rules.no_adjacent_repeats |= args.no_adjacent;
+    rules.no_sequences |= args.no_sequences;
+    rules.no_keyboard_walks |= args.no_keyboard_walks;
+    (load_character_classes(&config), excluded_characters(exclude_ambiguous, &excluded_chars), rules)
+}
+
+/// Loads the wordlist called `name`, or else the one named by `--wordlist` or config.toml,
+/// with the filters from config.toml.
+fn find_cli_wordlist(args: &CliArgs, name: Option<&str>) -> Result<Wordlist, String> {
+    let config = cli_config(args);
+    let name = name.map(String::from).or_else(|| args.wordlist.clone()).or_else(|| config.get("wordlist").cloned());
+    let mut filters = load_word_filters(&config);
+    filters.fold_ascii |= args.fold_ascii;
+    if let Some(letters) = args.unique_prefix {
+        filters.unique_prefix = letters;
+    }
+    match name.unwrap_or_default().trim() {
+        "" | DEFAULT_WORDLIST => {
+            let path = config.get("words_file_path").map(PathBuf::from);
+            load_default_words(&path.unwrap_or_else(|| get_app_directory().join("words.txt")), &filters)
+        }
+        name => {
+            let wordlists = load_wordlists(&config);
+            let path = wordlists.get(name).map(PathBuf::from).ok_or_else(|| unknown_wordlist(name, &wordlists))?;
+            load_words_from_file(&path, &filters)
+        }
+    }
+}
+
+/// Loads the wordlist for a CLI run, reporting on stderr what the filters kept and what was wrong with the list.
+fn load_cli_words(args: &CliArgs) -> Result<Wordlist, String> {
+    let wordlist = find_cli_wordlist(args, None)?;
+    let config = cli_config(args);
+    let flags = args.fold_ascii || args.unique_prefix.is_some();
+    if flags || load_word_filters(&config) != WordFilters::default() || !wordlist.report.is_clean() {
+        eprintln!("{}", wordlist.status);
+    }
+    Ok(wordlist)
+}
+
+/// Reads the passphrase style from config.toml, with the command-line flags taking precedence.
+fn load_cli_word_style(args: &CliArgs) -> Result<WordStyle, String> {
+    let config = cli_config(args);
+    let mut style = load_word_style(&config);
+    if let Some(case) = &args.case {
+        style.case = case
+            .parse()
+            .map_err(|_| format!("Unknown case '{}'; use lower, capitalized, upper, random or camel.", case))?;
+    }
+    if let Some(separator) = &args.separator {
+        style.use_separator = !separator.is_empty();
+        style.separator = separator.clone();
+    }
+    style.random_separator |= args.random_separator;
+    if let Some(insertion) = &args.insert {
+        style.insertion = insertion
+            .parse()
+            .map_err(|_| format!("Unknown insertion '{}'; use none, between or inside.", insertion))?;
+    }
+    if let Some(chars) = &args.insert_chars {
+        style.insert_chars = chars.clone();
+    }
+    if let Some(n) = args.insert_count {
+        style.insert_count = n;
+    }
+    if let Some(min) = args.min_length {
+        style.min_length = min;
+    }
+    if let Some(max) = args.max_length {
+        style.use_length_cap = true;
+        style.max_length = max;
+    }
+    style.use_length_cap &= !args.no_length_cap;
+    if args.bytes {
+        style.length_unit = LengthUnit::Bytes;
+    }
+    if let Some(letters) = args.abbreviate {
+        style.abbreviate = letters;
+    }
+    Ok(style)
+}
 
 /// Launches the command-line interface for character passwords.
-pub fn run_char_cli_mode(length: usize) {
-    let app_dir = get_app_directory();
-    let config_path = app_dir.join("config.toml");
-    let config = load_config_map(&config_path);
-    let sets = CharacterSets {
-        lowercase: config.get("lowercase_chars").cloned().unwrap_or_default(),
-        uppercase: config.get("uppercase_chars").cloned().unwrap_or_default(),
-        numbers: config.get("number_chars").cloned().unwrap_or_default(),
-        special: config.get("special_chars").cloned().unwrap_or_default(),
-    };
-
-    match generate_char_password(length, &sets, true, true, true, true) {
+pub fn run_char_cli_mode(length: usize, args: &CliArgs) {
+    let (classes, excluded, rules) = load_cli_character_settings(args);
+    let classes = remove_excluded_from_classes(&classes, &excluded);
+    let config = cli_config(args);
+    let unit = match args.bytes {
+        true => LengthUnit::Bytes,
+        false => config.get("char_length_unit").and_then(|s| s.parse().ok()).unwrap_or(LengthUnit::Characters),
+    };
+    let length = match char_password_units(length, unit, &classes) {
+        Ok(length) => length,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
+    print_char_passwords(length, &classes, &rules, args);
+}
+
+/// Prints the character passwords of a CLI run, and their entropy if asked.
+fn print_char_passwords(length: usize, classes: &[CharacterClass], rules: &RepetitionRules, args: &CliArgs) {
+    let sampler = match build_char_sampler(length, classes, rules) {
+        Ok(sampler) => sampler,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    let generated = print_generated(args, "Password", |rng| sampler.sample(rng));
+    if generated.is_some() && args.entropy {
+        match char_password_entropy(length, classes, rules) {
+            Ok(report) => eprintln!("{}", report),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+}
+
+/// Launches the command-line interface for character passwords that follow
+/// password rules given with `--rules` or `--rules-file`.
+pub fn run_rules_cli_mode(args: &CliArgs) {
+    let text = match (&args.rules, &args.rules_file) {
+        (Some(text), _) => text.clone(),
+        (None, Some(path)) => match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Error: Could not read {}: {}", path.display(), e);
+                return;
+            }
+        },
+        (None, None) => return,
+    };
+    let imported = match parse_password_rules(&text) {
+        Ok(imported) => imported,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    for note in &imported.notes {
+        eprintln!("Note: {}", note);
+    }
+
+    let (_, excluded, mut rules) = load_cli_character_settings(args);
+    imported.apply_to_rules(&mut rules);
+    let classes = remove_excluded_from_classes(&imported.classes, &excluded);
+    let length = match args.length {
+        Some(length) => match imported.check_length(length) {
+            Ok(()) => length,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        },
+        None => imported.fit_length(cli_config(args).get("char_length").and_then(|s| s.parse().ok()).unwrap_or(16)),
+    };
+    print_char_passwords(length, &classes, &rules, args);
+}
+
+/// Launches the command-line interface for word passphrases.
+pub fn run_word_cli_mode(count: usize, args: &CliArgs) {
+    if count == 0 {
+        eprintln!("Error: Word count for -w flag must be at least 1.");
+        return;
+    }
+
+    let words = match load_cli_words(args) {
+        Ok(wordlist) => wordlist.words,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    let style = match load_cli_word_style(args) {
+        Ok(style) => style,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
+    let sampler = match WordSampler::new(count, &words, &style) {
+        Ok(sampler) => sampler,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    let mut abbreviations = HashMap::new();
+    let generated = print_generated(args, "Passphrase", |rng| {
+        let (passphrase, abbreviated) = sampler.sample_abbreviated(rng)?;
+        abbreviations.extend(abbreviated.map(|abbreviated| (passphrase.clone(), abbreviated)));
+        Ok(passphrase)
+    });
+    if let Some(batch) = &generated {
+        print_abbreviations(batch, &abbreviations, args);
+    }
+    if generated.is_some() && args.entropy {
+        match word_password_entropy(count, &words, &style) {
+            Ok(report) => eprintln!("{}", report),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+}
+
+/// Prints the abbreviated form of each passphrase on stderr, warning about any the screen rejects.
+fn print_abbreviations(batch: &[String], abbreviations: &HashMap<String, String>, args: &CliArgs) {
+    let Some(abbreviated) = batch.iter().map(|passphrase| abbreviations.get(passphrase)).collect::<Option<Vec<_>>>()
+    else {
+        return;
+    };
+    let screen = cli_password_screen(args).unwrap_or_default();
+    for abbreviated in abbreviated {
+        eprintln!("Abbreviated: {}", abbreviated);
+        if let Some(reason) = screen.rejection(abbreviated) {
+            eprintln!("Warning: Do not use the abbreviated form: {}.", reason);
+        }
+    }
+}
+
+/// Launches the command-line interface for template passwords.
+pub fn run_template_cli_mode(template: &str, args: &CliArgs) {
+    let (classes, excluded) = load_cli_character_classes(args);
+    let sets = remove_excluded_from_sets(&CharacterSets::from_classes(&classes), &excluded);
+
+    let generated = print_generated(args, "Password", |rng| generate_template_password(template, &sets, rng));
+    if generated.is_some() && args.entropy {
+        match template_password_entropy(template, &sets) {
+            Ok(report) => eprintln!("{}", report),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+}
+
+/// Launches the command-line interface for pronounceable passwords.
+pub fn run_pronounceable_cli_mode(length: usize, args: &CliArgs) {
+    let sampler = match load_cli_words(args).and_then(|wordlist| PronounceableSampler::new(length, &wordlist.words)) {
+        Ok(sampler) => sampler,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
+    let generated = print_generated(args, "Password", |rng| Ok(sampler.sample(rng)));
+    if generated.is_some() && args.entropy {
+        eprintln!("{}", pronounceable_password_entropy(&sampler));
+    }
+}
+
+/// Launches the command-line interface for sentence passphrases.
+///
+/// An empty grammar means the one saved in config.toml, and the tagged wordlist
+/// is the one config.toml names, or tagged_words.txt next to the executable.
+pub fn run_sentence_cli_mode(grammar: &str, args: &CliArgs) {
+    let config = cli_config(args);
+    let grammar = match grammar.trim() {
+        "" => config.get("sentence_grammar").map(String::as_str).unwrap_or(DEFAULT_GRAMMAR),
+        grammar => grammar,
+    };
+    let separator = match &args.separator {
+        Some(separator) => separator.as_str(),
+        None => config.get("sentence_separator").map(String::as_str).unwrap_or(" "),
+    };
+    let path = PathBuf::from(config.get("tagged_words_file_path").cloned().unwrap_or_else(default_tagged_words_file));
+    create_default_tagged_words_file(&path);
+    let words = match load_tagged_words(&path) {
+        Ok((words, _)) => words,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
+    let generated =
+        print_generated(args, "Passphrase", |rng| generate_sentence_passphrase(grammar, &words, separator, rng));
+    if generated.is_some() {
+        match sentence_passphrase_entropy(grammar, &words, separator) {
+            Ok(report) => {
+                if args.entropy {
+                    eprintln!("{}", report);
+                }
+                if let Some(warning) = weak_sentence_warning(report.bits) {
+                    eprintln!("Warning: {}", warning);
+                }
+            }
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+}
+
+/// Launches the command-line interface for PINs.
+pub fn run_pin_cli_mode(length: usize, args: &CliArgs) {
+    let generated = print_generated(args, "PIN", |rng| generate_pin(length, rng));
+    if generated.is_some() && args.entropy {
+        match pin_entropy(length) {
+            Ok(report) => eprintln!("{}", report),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+}
+
+/// Launches the command-line interface for passphrases chosen with physical dice.
+pub fn run_dice_cli_mode(count: usize, args: &CliArgs) {
+    if count == 0 {
+        eprintln!("Error: Word count for -w flag must be at least 1.");
+        return;
+    }
+
+    let wordlist = match load_cli_words(args) {
+        Ok(wordlist) => wordlist,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    let Some(dice) = wordlist.dice else {
+        eprintln!("Error: {}", dice_list_error(&wordlist));
+        return;
+    };
+    let style = match load_cli_word_style(args).and_then(|style| check_dice_style(&style).map(|_| style)) {
+        Ok(style) => style,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
+    // Ask for one word's rolls at a time so typing mistakes are caught early.
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut rolls = Vec::with_capacity(count);
+    while rolls.len() < count {
+        eprint!("Word {} of {}: enter {} dice rolls (1-6): ", rolls.len() + 1, count, dice);
+        let _ = io::stderr().flush();
+        let Some(Ok(line)) = lines.next() else {
+            eprintln!("\nError: Dice input ended early.");
+            return;
+        };
+        let digits: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+        if digits.len() == dice && digits.chars().all(|c| ('1'..='6').contains(&c)) {
+            rolls.push(digits);
+        } else {
+            eprintln!("Please enter exactly {} digits between 1 and 6.", dice);
+        }
+    }
+
+    match generate_dice_passphrase(&rolls.join(" "), count, &wordlist, &style) {
         Ok(password) => {
             println!("{}", password);
+            warn_if_rejected(args, &password, "Roll the dice again.");
             if let Ok(mut clipboard) = Clipboard::new() {
                 if clipboard.set_text(password).is_ok() {
-                    eprintln!("Password copied to clipboard.");
+                    eprintln!("Passphrase copied to clipboard.");
                 }
             }
+            if args.entropy {
+                eprintln!("{}", dice_passphrase_entropy(count, dice));
+            }
         }
         Err(e) => eprintln!("Error: {}", e),
     }
 }
 
-/// Launches the command-line interface for word passphrases.
-pub fn run_word_cli_mode(count: usize) {
-    if !(3..=5).contains(&count) {
-        eprintln!("Error: Word count for -w flag must be 3, 4, or 5.");
-        return;
-    }
+/// Launches the command-line interface for site passwords derived from a master passphrase.
+///
+/// The master passphrase is read without echo from the terminal, or as one line
+/// from stdin when it is piped, and never from the command line.
+pub fn run_derive_cli_mode(site: &str, args: &CliArgs) {
+    let version = match args.derive_version.as_deref().map(str::parse::<DerivationVersion>) {
+        None => CURRENT_DERIVATION_VERSION,
+        Some(Ok(version)) => version,
+        Some(Err(e)) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
 
-    let app_dir = get_app_directory();
-    let words_file_path = app_dir.join("words.txt");
-    let (words, _) = load_words_from_file(&words_file_path);
+    // The version fixes the character policy, so only the length comes from the config.
+    let config = cli_config(args);
+    let length =
+        args.length.or_else(|| config.get("derive_length").and_then(|s| s.parse().ok())).unwrap_or(16);
+
+    let master = match read_secret("Master passphrase: ") {
+        Ok(master) => master,
+        Err(e) => {
+            eprintln!("Error: Could not read the master passphrase: {}", e);
+            return;
+        }
+    };
 
-    match generate_word_password(count, &words, true, "-", true) {
+    match derive_password(version, &master, site, args.counter, length) {
         Ok(password) => {
             println!("{}", password);
+            warn_if_rejected(args, &password, "Raise --counter to derive another.");
             if let Ok(mut clipboard) = Clipboard::new() {
                 if clipboard.set_text(password).is_ok() {
-                    eprintln!("Passphrase copied to clipboard.");
+                    eprintln!("Password copied to clipboard.");
+                }
+            }
+            eprintln!("Derived with {} for '{}', counter {}.", version, site.trim(), args.counter);
+            if args.entropy {
+                match char_password_entropy(length, &version.classes(), &version.rules()) {
+                    Ok(report) => eprintln!("{}", report),
+                    Err(e) => eprintln!("Error: {}", e),
                 }
             }
         }
         Err(e) => eprintln!("Error: {}", e),
     }
 }
+
+/// Launches the command-line interface for checking the strength of an existing password.
+///
+/// The password is read like a master passphrase, never from the command line,
+/// and only the positions of the patterns found are printed, not the password.
+pub fn run_check_cli_mode(args: &CliArgs) {
+    let screen = match cli_password_screen(args) {
+        Ok(screen) => screen,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    let password = match read_secret("Password to check: ") {
+        Ok(password) => password,
+        Err(e) => {
+            eprintln!("Error: Could not read the password: {}", e);
+            return;
+        }
+    };
+    let words = match load_cli_words(args) {
+        Ok(wordlist) => wordlist.words,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    println!("{}", check_password_strength(&password, &words, current_year()));
+    if let Some(report) = screen.report(&password) {
+        println!("{}", report);
+    }
+}
+
+/// Launches the command-line interface for building a Bloom filter from a breached password hash file.
+pub fn run_build_bloom_cli_mode(hash_file: &Path, output: &Path, false_positive_rate: f64) {
+    match build_bloom_filter(hash_file, output, false_positive_rate) {
+        Ok(message) => eprintln!("{}", message),
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+/// Launches the command-line interface for reporting on a wordlist.
+pub fn run_wordlist_info_cli_mode(name: Option<&str>, args: &CliArgs) {
+    match find_cli_wordlist(args, name) {
+        Ok(wordlist) => println!("{}", wordlist.info()),
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::wordlist::{built_in_words, load_words_from_str, BUILT_IN_WORDLIST_NAME};
+
+    /// The words of the built-in list, which never changes.
+    fn words() -> Vec<String> {
+        load_words_from_str(&built_in_words().unwrap(), BUILT_IN_WORDLIST_NAME, &WordFilters::default()).words
+    }
+
+    // Golden outputs: `--seed` promises the same output for the same seed, so any
+    // change to a generator's use of the random stream must show up here.
+
+    #[test]
+    fn seeded_char_password() {
+        let sampler = build_char_sampler(16, &CharacterClass::defaults(), &RepetitionRules::default()).unwrap();
+        assert_eq!(sampler.sample(&mut *seeded_rng(42)).unwrap(), "&bm8GT*u=zdQ>Z{;");
+    }
+
+    #[test]
+    fn seeded_word_passphrase() {
+        let (words, style) = (words(), WordStyle::default());
+        let sampler = WordSampler::new(5, &words, &style).unwrap();
+        assert_eq!(sampler.sample(&mut *seeded_rng(42)).unwrap(), "Actuality-Contrive-Shar-Macron-Knack");
+    }
+
+    #[test]
+    fn seeded_template_password() {
+        let password = generate_template_password("Cvcc-d{4}-ssXX", &CharacterSets::default(), &mut *seeded_rng(42));
+        assert_eq!(password.unwrap(), "Ribd-6389-.:BL");
+    }
+
+    #[test]
+    fn seeded_pronounceable_password() {
+        let sampler = PronounceableSampler::new(12, &words()).unwrap();
+        assert_eq!(sampler.sample(&mut *seeded_rng(42)), "momaduclitym");
+    }
+
+    #[test]
+    fn seeded_pin() {
+        assert_eq!(generate_pin(6, &mut *seeded_rng(42)).unwrap(), "856400");
+    }
+}
@@ -1,8 +1,14 @@
-use crate::app::state::{CharacterSets, PassGenApp, Tab, Theme};
-use crate::app::utils::{get_app_directory, load_words_from_file};
-use std::collections::HashMap;
+use crate::app::breach::PasswordScreen;
+use crate::app::sentence::{create_default_tagged_words_file, load_tagged_words, TaggedWords, DEFAULT_GRAMMAR};
+use crate::app::state::{
+    CharacterClass, CharacterSets, LengthUnit, PassGenApp, RepetitionRules, Tab, Theme, WordCase, WordFilters,
+    WordStyle,
+};
+use crate::app::utils::get_app_directory;
+use crate::app::wordlist::{load_default_words, load_words_from_file, Wordlist};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 impl PassGenApp {
     /// Creates a new application instance, loading config from file.
@@ -16,51 +22,165 @@ impl PassGenApp {
         let config_path = app.config_path.clone();
         let config_status = app.load_config_from_path(&config_path);
 
-        let words_file_path = app.words_file_path.clone();
-        let (words, word_status) = load_words_from_file(&words_file_path);
-        app.words = words;
+        let word_status = app.reload_words();
+        let tagged_status = app.reload_tagged_words();
 
-        app.status_message = format!("{}\n{}", config_status, word_status);
+        app.status_message = format!("{}\n{}\n{}", config_status, word_status, tagged_status);
         app.saved_config_state = app.get_current_config_as_string();
 
         app
     }
 
     pub fn get_current_config_as_string(&self) -> String {
-        format!(
+        let mut content = format!(
             "theme={:?}\n\
              words_file_path={}\n\
+             words_min_length={}\n\
+             words_max_length={}\n\
+             words_fold_ascii={}\n\
+             words_ascii_only={}\n\
+             words_lowercase_only={}\n\
+             words_no_punctuation={}\n\
+             words_dedupe={}\n\
+             words_unique_prefix={}\n\
+             words_exclude_file={}\n\
+             tagged_words_file_path={}\n\
+             breached_passwords_file={}\n\
+             banned_words_file={}\n\
              active_tab={:?}\n\
-             use_lowercase={}\n\
-             use_uppercase={}\n\
-             use_numbers={}\n\
-             use_special={}\n\
-             char_length={}\n\
-             lowercase_chars={}\n\
-             uppercase_chars={}\n\
-             number_chars={}\n\
-             special_chars={}\n\
-             word_count={}\n\
-             use_separator={}\n\
-             separator_char={}\n\
-             use_uppercase_words={}\n",
+             use_dice_rolls={}\n\
+             batch_count={}\n",
             self.theme,
             self.words_file_path.to_string_lossy(),
+            self.word_filters.min_length,
+            self.word_filters.max_length,
+            self.word_filters.fold_ascii,
+            self.word_filters.ascii_only,
+            self.word_filters.lowercase_only,
+            self.word_filters.no_punctuation,
+            self.word_filters.dedupe,
+            self.word_filters.unique_prefix,
+            self.word_filters.exclude_file,
+            self.tagged_words_file,
+            self.breached_file,
+            self.banned_words_file,
             self.active_tab,
-            self.use_lowercase,
-            self.use_uppercase,
-            self.use_numbers,
-            self.use_special,
+            self.use_dice_rolls,
+            self.batch_count,
+        );
+        content.push_str(&self.generation_settings_to_config());
+        content.push_str(&wordlists_to_config(&self.wordlists));
+        content.push_str(&profiles_to_config(&self.profiles));
+        content
+    }
+
+    /// Writes the settings that decide what gets generated: everything a profile bundles.
+    pub fn generation_settings_to_config(&self) -> String {
+        let mut content = format!(
+            "char_length={}\n\
+             char_length_unit={:?}\n\
+             exclude_ambiguous={}\n\
+             excluded_chars={}\n\
+             max_char_occurrences={}\n\
+             no_adjacent_repeats={}\n\
+             no_sequences={}\n\
+             no_keyboard_walks={}\n\
+             word_count={}\n\
+             wordlist={}\n\
+             use_separator={}\n\
+             separator_char={}\n\
+             random_separator={}\n\
+             word_case={:?}\n\
+             word_insertion={:?}\n\
+             insert_chars={}\n\
+             insert_count={}\n\
+             min_passphrase_length={}\n\
+             max_passphrase_length={}\n\
+             use_length_cap={}\n\
+             passphrase_length_unit={:?}\n\
+             abbreviate_words={}\n\
+             template={}\n\
+             pronounceable_length={}\n\
+             sentence_grammar={}\n\
+             sentence_separator={}\n\
+             pin_length={}\n\
+             derive_length={}\n",
             self.char_length,
-            self.char_sets.lowercase,
-            self.char_sets.uppercase,
-            self.char_sets.numbers,
-            self.char_sets.special,
+            self.char_length_unit,
+            self.exclude_ambiguous,
+            self.excluded_chars,
+            self.repetition_rules.max_occurrences,
+            self.repetition_rules.no_adjacent_repeats,
+            self.repetition_rules.no_sequences,
+            self.repetition_rules.no_keyboard_walks,
             self.word_count,
-            self.use_separator,
-            self.separator_char,
-            self.use_uppercase_words,
-        )
+            self.selected_wordlist,
+            self.word_style.use_separator,
+            self.word_style.separator,
+            self.word_style.random_separator,
+            self.word_style.case,
+            self.word_style.insertion,
+            self.word_style.insert_chars,
+            self.word_style.insert_count,
+            self.word_style.min_length,
+            self.word_style.max_length,
+            self.word_style.use_length_cap,
+            self.word_style.length_unit,
+            self.word_style.abbreviate,
+            self.template,
+            self.pronounceable_length,
+            self.sentence_grammar,
+            self.sentence_separator,
+            self.pin_length,
+            self.derive_length,
+        );
+        content.push_str(&character_classes_to_config(&self.char_classes));
+        content
+    }
+
+    /// Reads the settings written by `generation_settings_to_config`, using the defaults for missing keys.
+    pub fn apply_generation_settings(&mut self, config: &HashMap<String, String>) {
+        self.char_length = config.get("char_length").and_then(|s| s.parse().ok()).unwrap_or(16);
+        self.char_length_unit =
+            config.get("char_length_unit").and_then(|s| s.parse().ok()).unwrap_or(LengthUnit::Characters);
+        self.char_classes = load_character_classes(config);
+        self.exclude_ambiguous = config.get("exclude_ambiguous").and_then(|s| s.parse().ok()).unwrap_or(false);
+        self.excluded_chars = config.get("excluded_chars").cloned().unwrap_or_default();
+        self.repetition_rules = load_repetition_rules(config);
+        self.word_count = config.get("word_count").and_then(|s| s.parse().ok()).unwrap_or(3);
+        self.selected_wordlist = config.get("wordlist").cloned().unwrap_or_default();
+        self.word_style = load_word_style(config);
+        self.template = config.get("template").cloned().unwrap_or_else(|| PassGenApp::default().template);
+        self.pronounceable_length = config.get("pronounceable_length").and_then(|s| s.parse().ok()).unwrap_or(12);
+        self.sentence_grammar = config.get("sentence_grammar").cloned().unwrap_or_else(|| DEFAULT_GRAMMAR.to_string());
+        self.sentence_separator = config.get("sentence_separator").cloned().unwrap_or_else(|| " ".to_string());
+        self.pin_length = config.get("pin_length").and_then(|s| s.parse().ok()).unwrap_or(6);
+        self.derive_length = config.get("derive_length").and_then(|s| s.parse().ok()).unwrap_or(16);
+    }
+
+    /// Switches to a saved profile's settings.
+    pub fn apply_profile(&mut self, name: &str) -> String {
+        match self.profiles.get(name) {
+            Some(content) => {
+                let settings = parse_config(content);
+                let wordlist = self.selected_wordlist.clone();
+                self.apply_generation_settings(&settings);
+                self.selected_profile = name.to_string();
+                match self.selected_wordlist == wordlist {
+                    true => format!("Switched to profile '{}'.", name),
+                    false => format!("Switched to profile '{}'. {}", name, self.reload_words()),
+                }
+            }
+            None => format!("Error: No profile named '{}'.", name),
+        }
+    }
+
+    /// Stores the current generation settings under a profile name, replacing any profile of that name.
+    pub fn save_current_as_profile(&mut self, name: &str) -> Result<String, String> {
+        let name = check_profile_name(name)?;
+        self.profiles.insert(name.clone(), self.generation_settings_to_config());
+        self.selected_profile = name.clone();
+        Ok(format!("Profile '{}' saved. Save the configuration to keep it.", name))
     }
 
     pub fn save_config(&mut self) -> String {
@@ -79,6 +199,7 @@ impl PassGenApp {
             return if path == &self.config_path {
                 self.words_file_path = get_app_directory().join("words.txt");
                 self.words_file_path_str = self.words_file_path.to_string_lossy().to_string();
+                self.tagged_words_file = default_tagged_words_file();
                 self.save_config()
             } else {
                 format!("Error: Failed to load or parse config from {:?}", path)
@@ -90,30 +211,251 @@ impl PassGenApp {
         let default_words_path = get_app_directory().join("words.txt");
         self.words_file_path = config.get("words_file_path").map(PathBuf::from).unwrap_or(default_words_path);
         self.words_file_path_str = self.words_file_path.to_string_lossy().to_string();
+        self.word_filters = load_word_filters(&config);
+        self.tagged_words_file =
+            config.get("tagged_words_file_path").cloned().unwrap_or_else(default_tagged_words_file);
+        self.breached_file = config.get("breached_passwords_file").cloned().unwrap_or_default();
+        self.banned_words_file = config.get("banned_words_file").cloned().unwrap_or_default();
 
         self.active_tab = config.get("active_tab").and_then(|s| s.parse().ok()).unwrap_or(Tab::Character);
-        self.use_lowercase = config.get("use_lowercase").and_then(|s| s.parse().ok()).unwrap_or(true);
-        self.use_uppercase = config.get("use_uppercase").and_then(|s| s.parse().ok()).unwrap_or(true);
-        self.use_numbers = config.get("use_numbers").and_then(|s| s.parse().ok()).unwrap_or(true);
-        self.use_special = config.get("use_special").and_then(|s| s.parse().ok()).unwrap_or(true);
-        self.char_length = config.get("char_length").and_then(|s| s.parse().ok()).unwrap_or(16);
-        self.char_sets.lowercase = config.get("lowercase_chars").cloned().unwrap_or_else(|| CharacterSets::default().lowercase);
-        self.char_sets.uppercase = config.get("uppercase_chars").cloned().unwrap_or_else(|| CharacterSets::default().uppercase);
-        self.char_sets.numbers = config.get("number_chars").cloned().unwrap_or_else(|| CharacterSets::default().numbers);
-        self.char_sets.special = config.get("special_chars").cloned().unwrap_or_else(|| CharacterSets::default().special);
-        self.word_count = config.get("word_count").and_then(|s| s.parse().ok()).unwrap_or(3);
-        self.use_separator = config.get("use_separator").and_then(|s| s.parse().ok()).unwrap_or(true);
-        self.separator_char = config.get("separator_char").cloned().unwrap_or_else(|| "-".to_string());
-        self.use_uppercase_words = config.get("use_uppercase_words").and_then(|s| s.parse().ok()).unwrap_or(true);
+        self.use_dice_rolls = config.get("use_dice_rolls").and_then(|s| s.parse().ok()).unwrap_or(false);
+        self.batch_count = config.get("batch_count").and_then(|s| s.parse().ok()).unwrap_or(10);
+        self.apply_generation_settings(&config);
+        self.wordlists = load_wordlists(&config);
+        self.profiles = load_profiles(&config);
+        self.selected_profile.clear();
 
         self.saved_config_state = self.get_current_config_as_string();
-        format!("Successfully loaded configuration from {:?}", path)
+        let screen_status = self.reload_password_screen();
+        format!("Successfully loaded configuration from {:?}\n{}", path, screen_status)
+    }
+
+    /// Loads the wordlist in use with the current filters: the selected registered list, or the
+    /// default words file, which falls back to the built-in list when it is missing.
+    pub fn reload_words(&mut self) -> String {
+        let (loaded, warning) = match self.selected_wordlist.as_str() {
+            "" | DEFAULT_WORDLIST => (load_default_words(&self.words_file_path, &self.word_filters), String::new()),
+            name => match self.wordlists.get(name) {
+                Some(path) => (load_words_from_file(Path::new(path), &self.word_filters), String::new()),
+                None => {
+                    let warning = unknown_wordlist(name, &self.wordlists);
+                    let loaded = load_default_words(&self.words_file_path, &self.word_filters);
+                    (loaded, format!("Warning: {} Using the default list. ", warning))
+                }
+            },
+        };
+        match loaded {
+            Ok(wordlist) => {
+                let status = format!("{}{}", warning, wordlist.status);
+                self.wordlist = wordlist;
+                status
+            }
+            Err(e) => {
+                self.wordlist = Wordlist::default();
+                format!("{}{}", warning, e)
+            }
+        }
+    }
+
+    /// Registers a wordlist under a name, replacing any list of that name.
+    pub fn add_wordlist(&mut self, name: &str, path: &str) -> Result<String, String> {
+        let name = check_wordlist_name(name)?;
+        self.wordlists.insert(name.clone(), path.to_string());
+        Ok(format!("Wordlist '{}' added. Save the configuration to keep it.", name))
+    }
+
+    /// Loads the tagged wordlist for sentence passphrases, creating the default one if it is missing.
+    pub fn reload_tagged_words(&mut self) -> String {
+        let path = PathBuf::from(&self.tagged_words_file);
+        create_default_tagged_words_file(&path);
+        match load_tagged_words(&path) {
+            Ok((tagged, status)) => {
+                self.tagged_words = tagged;
+                status
+            }
+            Err(e) => {
+                self.tagged_words = TaggedWords::default();
+                format!("Error: {}", e)
+            }
+        }
+    }
+
+    /// Reopens the breached password and banned words lists named in the settings.
+    pub fn reload_password_screen(&mut self) -> String {
+        match PasswordScreen::load(&self.breached_file, &self.banned_words_file) {
+            Ok(screen) => {
+                self.password_screen = screen;
+                format!("Screening passwords against {}.", self.password_screen.describe())
+            }
+            Err(e) => {
+                self.password_screen = PasswordScreen::default();
+                format!("Error: {} Passwords are not being screened.", e)
+            }
+        }
+    }
+}
+
+/// The name the wordlist at `words_file_path` goes by.
+pub const DEFAULT_WORDLIST: &str = "default";
+
+const WORDLIST_PREFIX: &str = "wordlist.";
+
+/// Trims a wordlist name and checks that it can be stored in the config.
+pub fn check_wordlist_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Enter a name for the wordlist.".to_string());
+    }
+    if name == DEFAULT_WORDLIST {
+        return Err(format!("'{}' is the wordlist set as the Wordlist File Path.", DEFAULT_WORDLIST));
+    }
+    if name.contains(['=', '\n', '\r']) {
+        return Err("Wordlist names cannot contain '=' or line breaks.".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// Writes each registered wordlist as `wordlist.NAME=PATH`.
+pub fn wordlists_to_config(wordlists: &BTreeMap<String, String>) -> String {
+    wordlists.iter().map(|(name, path)| format!("{}{}={}\n", WORDLIST_PREFIX, name, path)).collect()
+}
+
+/// Reads the registered wordlists.
+pub fn load_wordlists(config: &HashMap<String, String>) -> BTreeMap<String, String> {
+    config
+        .iter()
+        .filter_map(|(key, path)| Some((key.strip_prefix(WORDLIST_PREFIX)?.to_string(), path.clone())))
+        .collect()
+}
+
+/// The error for a wordlist name that is not registered, listing the ones that are.
+pub fn unknown_wordlist(name: &str, wordlists: &BTreeMap<String, String>) -> String {
+    let names: Vec<&str> = std::iter::once(DEFAULT_WORDLIST).chain(wordlists.keys().map(String::as_str)).collect();
+    format!("No wordlist named '{}'. Available: {}.", name, names.join(", "))
+}
+
+/// The tagged wordlist used when config.toml names none: tagged_words.txt next to the executable.
+pub fn default_tagged_words_file() -> String {
+    get_app_directory().join("tagged_words.txt").to_string_lossy().to_string()
+}
+
+/// Reads the repetition and sequence rules, using the defaults for missing keys.
+pub fn load_repetition_rules(config: &HashMap<String, String>) -> RepetitionRules {
+    let defaults = RepetitionRules::default();
+    let flag = |key: &str, default: bool| config.get(key).and_then(|s| s.parse().ok()).unwrap_or(default);
+    RepetitionRules {
+        max_occurrences: config
+            .get("max_char_occurrences")
+            .and_then(|s| s.parse().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(defaults.max_occurrences),
+        no_adjacent_repeats: flag("no_adjacent_repeats", defaults.no_adjacent_repeats),
+        no_sequences: flag("no_sequences", defaults.no_sequences),
+        no_keyboard_walks: flag("no_keyboard_walks", defaults.no_keyboard_walks),
+    }
+}
+
+/// Reads the wordlist filters; missing keys leave a filter off.
+pub fn load_word_filters(config: &HashMap<String, String>) -> WordFilters {
+    let number = |key: &str| config.get(key).and_then(|s| s.parse().ok()).unwrap_or(0);
+    let flag = |key: &str| config.get(key).and_then(|s| s.parse().ok()).unwrap_or(false);
+    WordFilters {
+        min_length: number("words_min_length"),
+        max_length: number("words_max_length"),
+        fold_ascii: flag("words_fold_ascii"),
+        ascii_only: flag("words_ascii_only"),
+        lowercase_only: flag("words_lowercase_only"),
+        no_punctuation: flag("words_no_punctuation"),
+        dedupe: flag("words_dedupe"),
+        unique_prefix: number("words_unique_prefix"),
+        exclude_file: config.get("words_exclude_file").cloned().unwrap_or_default(),
+    }
+}
+
+/// Reads the passphrase style. Configs from before `word_case` existed only have
+/// `use_uppercase_words`, which maps to Capitalized or lowercase words.
+pub fn load_word_style(config: &HashMap<String, String>) -> WordStyle {
+    let defaults = WordStyle::default();
+    let legacy_case = match config.get("use_uppercase_words").and_then(|s| s.parse::<bool>().ok()) {
+        Some(false) => WordCase::Lower,
+        _ => defaults.case,
+    };
+    WordStyle {
+        case: config.get("word_case").and_then(|s| s.parse().ok()).unwrap_or(legacy_case),
+        use_separator: config.get("use_separator").and_then(|s| s.parse().ok()).unwrap_or(defaults.use_separator),
+        separator: config.get("separator_char").cloned().unwrap_or(defaults.separator),
+        random_separator: config.get("random_separator").and_then(|s| s.parse().ok()).unwrap_or(false),
+        insertion: config.get("word_insertion").and_then(|s| s.parse().ok()).unwrap_or(defaults.insertion),
+        insert_chars: config.get("insert_chars").cloned().unwrap_or(defaults.insert_chars),
+        insert_count: config.get("insert_count").and_then(|s| s.parse().ok()).unwrap_or(defaults.insert_count),
+        min_length: config.get("min_passphrase_length").and_then(|s| s.parse().ok()).unwrap_or(defaults.min_length),
+        max_length: config.get("max_passphrase_length").and_then(|s| s.parse().ok()).unwrap_or(defaults.max_length),
+        use_length_cap: config.get("use_length_cap").and_then(|s| s.parse().ok()).unwrap_or(defaults.use_length_cap),
+        length_unit: config.get("passphrase_length_unit").and_then(|s| s.parse().ok()).unwrap_or(defaults.length_unit),
+        abbreviate: config.get("abbreviate_words").and_then(|s| s.parse().ok()).unwrap_or(defaults.abbreviate),
+    }
+}
+
+/// Prefix of the config keys that belong to profiles: `profile.NAME.key=value`.
+const PROFILE_PREFIX: &str = "profile.";
+
+/// Trims a profile name and checks that it can be stored in the config.
+pub fn check_profile_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Enter a name for the profile.".to_string());
+    }
+    if name.contains(['.', '=', '\n', '\r']) {
+        return Err("Profile names cannot contain '.', '=' or line breaks.".to_string());
     }
+    Ok(name.to_string())
+}
+
+/// Writes each profile's settings with its `profile.NAME.` prefix.
+pub fn profiles_to_config(profiles: &BTreeMap<String, String>) -> String {
+    let mut content = String::new();
+    for (name, settings) in profiles {
+        for line in settings.lines() {
+            content.push_str(&format!("{}{}.{}\n", PROFILE_PREFIX, name, line));
+        }
+    }
+    content
+}
+
+/// The settings of one profile, as a config map of its own, or `None` if there is no such profile.
+pub fn profile_settings(config: &HashMap<String, String>, name: &str) -> Option<HashMap<String, String>> {
+    let prefix = format!("{}{}.", PROFILE_PREFIX, name);
+    let settings: HashMap<String, String> = config
+        .iter()
+        .filter_map(|(key, value)| key.strip_prefix(&prefix).map(|key| (key.to_string(), value.clone())))
+        .collect();
+    (!settings.is_empty()).then_some(settings)
+}
+
+/// Reads every profile. Each is written back in the usual key order, with
+/// defaults for any setting it lacks.
+pub fn load_profiles(config: &HashMap<String, String>) -> BTreeMap<String, String> {
+    let names: BTreeSet<&str> = config
+        .keys()
+        .filter_map(|key| key.strip_prefix(PROFILE_PREFIX)?.split_once('.').map(|(name, _)| name))
+        .collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let mut app = PassGenApp::default();
+            app.apply_generation_settings(&profile_settings(config, name)?);
+            Some((name.to_string(), app.generation_settings_to_config()))
+        })
+        .collect()
 }
 
 pub fn load_config_map(path: &PathBuf) -> HashMap<String, String> {
-    fs::read_to_string(path)
-        .unwrap_or_default()
+    parse_config(&fs::read_to_string(path).unwrap_or_default())
+}
+
+/// Splits `key=value` lines into a map.
+pub fn parse_config(content: &str) -> HashMap<String, String> {
+    content
         .lines()
         .filter_map(|line| {
             let mut parts = line.splitn(2, '=');
@@ -121,3 +463,64 @@ pub fn load_config_map(path: &PathBuf) -> HashMap<String, String> {
         })
         .collect()
 }
+
+/// Writes the character classes as numbered `char_class_N_*` entries.
+pub fn character_classes_to_config(classes: &[CharacterClass]) -> String {
+    let mut content = format!("char_class_count={}\n", classes.len());
+    for (i, class) in classes.iter().enumerate() {
+        let n = i + 1;
+        content.push_str(&format!(
+            "char_class_{n}_name={}\n\
+             char_class_{n}_chars={}\n\
+             char_class_{n}_enabled={}\n\
+             char_class_{n}_min={}\n\
+             char_class_{n}_max={}\n\
+             char_class_{n}_weight={}\n",
+            class.name,
+            class.chars,
+            class.enabled,
+            class.min,
+            class.max.map(|m| m.to_string()).unwrap_or_default(),
+            class.weight,
+        ));
+    }
+    content
+}
+
+/// Reads the character classes from a config map.
+///
+/// Configs written before classes existed only have the four fixed sets; those are
+/// turned into classes that must appear once when enabled.
+pub fn load_character_classes(config: &HashMap<String, String>) -> Vec<CharacterClass> {
+    let Some(count) = config.get("char_class_count").and_then(|s| s.parse::<usize>().ok()) else {
+        let defaults = CharacterSets::default();
+        let legacy = [
+            ("Lowercase", "use_lowercase", "lowercase_chars", defaults.lowercase),
+            ("Uppercase", "use_uppercase", "uppercase_chars", defaults.uppercase),
+            ("Numbers", "use_numbers", "number_chars", defaults.numbers),
+            ("Special", "use_special", "special_chars", defaults.special),
+        ];
+        return legacy
+            .into_iter()
+            .map(|(name, use_key, chars_key, fallback)| {
+                let mut class = CharacterClass::new(name, &config.get(chars_key).cloned().unwrap_or(fallback));
+                class.enabled = config.get(use_key).and_then(|s| s.parse().ok()).unwrap_or(true);
+                class
+            })
+            .collect();
+    };
+
+    (1..=count)
+        .map(|n| {
+            let get = |field: &str| config.get(&format!("char_class_{}_{}", n, field));
+            CharacterClass {
+                name: get("name").cloned().unwrap_or_else(|| format!("Class {}", n)),
+                chars: get("chars").cloned().unwrap_or_default(),
+                enabled: get("enabled").and_then(|s| s.parse().ok()).unwrap_or(true),
+                min: get("min").and_then(|s| s.parse().ok()).unwrap_or(0),
+                max: get("max").and_then(|s| s.parse().ok()),
+                weight: get("weight").and_then(|s| s.parse().ok()).unwrap_or(1).max(1),
+            }
+        })
+        .collect()
+}
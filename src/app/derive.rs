@@ -0,0 +1,296 @@
+use crate::app::state::{CharacterClass, RepetitionRules};
+use crate::app::unicode::nfc;
+use argon2::{Algorithm, Argon2, Params, Version};
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::{One, Zero};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// A version of the site-based derivation algorithm.
+///
+/// Each version fixes everything that decides the output for a given master
+/// passphrase, site, counter and length: the Argon2id parameters, the salt
+/// layout, the random stream and the character policy. Any change to those must
+/// add a new version so that passwords derived with older ones still work.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DerivationVersion {
+    /// Argon2id (64 MiB, 3 passes, 4 lanes) seeding ChaCha20, mapped by the exact class sampler.
+    V1,
+}
+
+/// The version used unless another one is asked for.
+pub const CURRENT_DERIVATION_VERSION: DerivationVersion = DerivationVersion::V1;
+
+impl FromStr for DerivationVersion {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().trim_start_matches(['v', 'V']) {
+            "1" => Ok(DerivationVersion::V1),
+            _ => Err(format!("Unknown derivation version '{}'. This build supports: v1.", s)),
+        }
+    }
+}
+
+impl fmt::Display for DerivationVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerivationVersion::V1 => write!(f, "v1"),
+        }
+    }
+}
+
+impl DerivationVersion {
+    /// The classes this version draws from.
+    ///
+    /// These are frozen copies of the defaults at the time the version was
+    /// introduced; they must not follow later changes to `CharacterSets::default()`
+    /// or to the user's settings.
+    pub fn classes(&self) -> Vec<CharacterClass> {
+        match self {
+            DerivationVersion::V1 => vec![
+                CharacterClass::new("Lowercase", "abcdefghijklmnopqrstuvwxyz"),
+                CharacterClass::new("Uppercase", "ABCDEFGHIJKLMNOPQRSTUVWXYZ"),
+                CharacterClass::new("Numbers", "0123456789"),
+                CharacterClass::new("Special", "!@#$%^&*()-_=+[]{}|;:,.<>?"),
+            ],
+        }
+    }
+
+    /// The repetition rules this version follows, frozen like its classes.
+    pub fn rules(&self) -> RepetitionRules {
+        match self {
+            DerivationVersion::V1 => RepetitionRules {
+                max_occurrences: 3,
+                no_adjacent_repeats: false,
+                no_sequences: false,
+                no_keyboard_walks: false,
+            },
+        }
+    }
+}
+
+/// Site names are compared in NFC without surrounding whitespace and case, so `GitHub.com ` and `github.com` match.
+pub fn normalize_site(site: &str) -> String {
+    nfc(site.trim()).to_lowercase()
+}
+
+/// Stretches the master passphrase into a 32-byte seed for one site, counter and length.
+///
+/// The master passphrase is NFC-normalized, so an accented letter typed as one
+/// code point or as a letter plus a combining mark gives the same seed.
+fn v1_seed(master: &str, site: &str, counter: u32, length: usize) -> Result<[u8; 32], String> {
+    let master = nfc(master);
+    let site = normalize_site(site);
+    let mut salt = b"passgen.derive.v1".to_vec();
+    salt.extend_from_slice(&(site.len() as u32).to_be_bytes());
+    salt.extend_from_slice(site.as_bytes());
+    salt.extend_from_slice(&counter.to_be_bytes());
+    salt.extend_from_slice(&(length as u32).to_be_bytes());
+
+    let params = Params::new(64 * 1024, 3, 4, Some(32)).map_err(|e| format!("Invalid Argon2 parameters: {}", e))?;
+    let mut seed = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(master.as_bytes(), &salt, &mut seed)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(seed)
+}
+
+/// Derives the password for a site from a master passphrase.
+///
+/// The same inputs give the same password on any machine, so nothing has to be
+/// stored or synced; raise `counter` to rotate a site's password. The KDF output
+/// seeds a ChaCha20 stream that drives the version's own copy of the exact class
+/// sampler over its own classes and rules, so the result is unbiased over every
+/// password that policy allows and no setting or later sampler change can alter it.
+pub fn derive_password(
+    version: DerivationVersion,
+    master: &str,
+    site: &str,
+    counter: u32,
+    length: usize,
+) -> Result<String, String> {
+    if master.is_empty() {
+        return Err("Enter a master passphrase.".to_string());
+    }
+    if normalize_site(site).is_empty() {
+        return Err("Enter a site or account name.".to_string());
+    }
+    if counter == 0 {
+        return Err("The counter starts at 1.".to_string());
+    }
+
+    match version {
+        DerivationVersion::V1 => {
+            let classes: Vec<Vec<char>> = version.classes().iter().map(|c| c.chars.chars().collect()).collect();
+            let max_repeats = version.rules().max_occurrences;
+            if length < classes.len() {
+                return Err(format!(
+                    "Password length must be at least {} to include the minimum of each class.",
+                    classes.len()
+                ));
+            }
+            if length > classes.iter().map(Vec::len).sum::<usize>() * max_repeats {
+                return Err(
+                    "Cannot generate: not enough unique characters for the requested length and repetition rule."
+                        .to_string(),
+                );
+            }
+            let mut rng = ChaCha20Rng::from_seed(v1_seed(master, site, counter, length)?);
+            Ok(v1_sample(&classes, length, max_repeats.min(length), &mut rng))
+        }
+    }
+}
+
+/// The v1 mapping from a random stream to a password: a frozen copy of the class
+/// sampler as it was when v1 was introduced.
+///
+/// Every class fills at least one position, no character appears more than
+/// `max_repeats` times, and every such password is equally likely. It must keep
+/// drawing from `rng` in exactly this order; improvements belong in `sampler.rs`
+/// and in a new version, never here.
+fn v1_sample<R: Rng + ?Sized>(classes: &[Vec<char>], length: usize, max_repeats: usize, rng: &mut R) -> String {
+    let zero_row = || vec![BigUint::zero(); length + 1];
+
+    let mut binomials: Vec<Vec<BigUint>> = Vec::with_capacity(length + 1);
+    for i in 0..=length {
+        let mut row = vec![BigUint::one(); i + 1];
+        for j in 1..i {
+            row[j] = &binomials[i - 1][j - 1] + &binomials[i - 1][j];
+        }
+        binomials.push(row);
+    }
+
+    // arrangements[s][l]: strings of length `l` over `s` characters within the repeat limit.
+    let mut arrangements = vec![zero_row()];
+    arrangements[0][0] = BigUint::one();
+    for s in 1..=classes.iter().map(Vec::len).max().unwrap_or(0) {
+        let previous = &arrangements[s - 1];
+        let next = (0..=length)
+            .map(|l| (0..=max_repeats.min(l)).map(|j| &binomials[l][j] * &previous[l - j]).sum())
+            .collect();
+        arrangements.push(next);
+    }
+
+    // fills[a][l]: strings class `a` can place in `l` positions, at least one.
+    // layers[a][t]: ways for the first `a` classes to fill `t` positions.
+    let fills: Vec<Vec<BigUint>> = classes
+        .iter()
+        .map(|chars| {
+            (0..=length).map(|l| if l == 0 { BigUint::zero() } else { arrangements[chars.len()][l].clone() }).collect()
+        })
+        .collect();
+    let mut layers = vec![zero_row()];
+    layers[0][0] = BigUint::one();
+    for fill in &fills {
+        let previous = layers.last().unwrap();
+        let mut next = zero_row();
+        for (t, count) in previous.iter().enumerate().filter(|(_, c)| !c.is_zero()) {
+            for (l, ways) in fill.iter().enumerate().take(length - t + 1).filter(|(_, w)| !w.is_zero()) {
+                next[t + l] += count * &binomials[t + l][l] * ways;
+            }
+        }
+        layers.push(next);
+    }
+
+    // How many positions each class fills, walking the layers backwards.
+    let mut lengths = vec![0; classes.len()];
+    let mut t = length;
+    for a in (0..classes.len()).rev() {
+        let candidates: Vec<(usize, BigUint)> = (0..=t)
+            .map(|l| (l, &layers[a][t - l] * &binomials[t][l] * &fills[a][l]))
+            .filter(|(_, weight)| !weight.is_zero())
+            .collect();
+        lengths[a] = v1_pick(rng, &candidates);
+        t -= lengths[a];
+    }
+
+    // Spread the classes over the positions, then fill each class's positions.
+    let mut slots: Vec<usize> = lengths.iter().enumerate().flat_map(|(a, &l)| std::iter::repeat_n(a, l)).collect();
+    slots.shuffle(rng);
+    let mut class_fills: Vec<std::vec::IntoIter<char>> = classes
+        .iter()
+        .zip(&lengths)
+        .map(|(chars, &l)| {
+            // How often each character appears, one character at a time, then in a shuffled order.
+            let mut result = Vec::with_capacity(l);
+            let mut remaining = l;
+            for (i, &c) in chars.iter().enumerate() {
+                let rest = &arrangements[chars.len() - i - 1];
+                let candidates: Vec<(usize, BigUint)> = (0..=max_repeats.min(remaining))
+                    .map(|k| (k, &binomials[remaining][k] * &rest[remaining - k]))
+                    .filter(|(_, weight)| !weight.is_zero())
+                    .collect();
+                let k = v1_pick(rng, &candidates);
+                result.extend(std::iter::repeat_n(c, k));
+                remaining -= k;
+            }
+            result.shuffle(rng);
+            result.into_iter()
+        })
+        .collect();
+    slots.into_iter().map(|a| class_fills[a].next().unwrap()).collect()
+}
+
+/// Picks one candidate with probability proportional to its weight, as v1 always has.
+fn v1_pick<R: Rng + ?Sized>(rng: &mut R, candidates: &[(usize, BigUint)]) -> usize {
+    let total: BigUint = candidates.iter().map(|(_, w)| w).sum();
+    let mut roll = rng.gen_biguint_below(&total);
+    for (value, weight) in candidates {
+        if roll < *weight {
+            return *value;
+        }
+        roll -= weight;
+    }
+    unreachable!("roll is always below the total weight")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Known answers for v1; a change here means passwords derived with v1 no longer match.
+    #[test]
+    fn v1_known_answers() {
+        let cases = [
+            ("correct horse battery staple", "github.com", 1, 16, "*2Pcl>gshb8IpwVB"),
+            ("correct horse battery staple", " GitHub.com ", 1, 16, "*2Pcl>gshb8IpwVB"),
+            ("correct horse battery staple", "github.com", 2, 16, "7.IfyU9i[nG5bu>O"),
+            ("Tr0ub4dor&3", "example.org", 1, 24, "9|GQD_!Q#4;#bNZ4d1W)7p}<"),
+        ];
+        for (master, site, counter, length, expected) in cases {
+            let password = derive_password(DerivationVersion::V1, master, site, counter, length).unwrap();
+            assert_eq!(password, expected, "{} / {} / {}", master, site, counter);
+        }
+    }
+
+    /// A master passphrase and site typed with combining accents derive the same password as precomposed ones.
+    #[test]
+    fn v1_normalizes_to_nfc() {
+        let precomposed = derive_password(DerivationVersion::V1, "caf\u{e9} cr\u{e8}me", "Z\u{fc}rich.example", 1, 16);
+        let decomposed =
+            derive_password(DerivationVersion::V1, "cafe\u{301} cre\u{300}me", "Zu\u{308}rich.example", 1, 16);
+        assert_eq!(precomposed, decomposed);
+        assert_eq!(decomposed.unwrap(), ";Vy|%_6O@KRVJ?3x");
+    }
+
+    /// The frozen v1 sampler still draws exactly what the shared class sampler does.
+    #[test]
+    fn v1_sampler_matches_the_class_sampler() {
+        use crate::app::password::{build_char_sampler, CharSampler};
+        let version = DerivationVersion::V1;
+        let classes: Vec<Vec<char>> = version.classes().iter().map(|c| c.chars.chars().collect()).collect();
+        for length in [4, 5, 16, 40] {
+            let sampler = build_char_sampler(length, &version.classes(), &version.rules()).unwrap();
+            assert!(matches!(sampler, CharSampler::Classes(_)));
+            let mut shared = ChaCha20Rng::seed_from_u64(length as u64);
+            let mut frozen = ChaCha20Rng::seed_from_u64(length as u64);
+            for _ in 0..20 {
+                let expected = sampler.sample(&mut shared).unwrap();
+                assert_eq!(expected, v1_sample(&classes, length, 3.min(length), &mut frozen));
+            }
+        }
+    }
+}
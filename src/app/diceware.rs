@@ -0,0 +1,190 @@
+use crate::app::password::apply_case;
+use crate::app::state::{Insertion, WordCase, WordStyle};
+use crate::app::wordlist::Wordlist;
+use std::collections::BTreeMap;
+
+/// The most dice a numbered wordlist may use per word.
+const MAX_DICE: usize = 8;
+
+/// A wordlist parsed from the numbered Diceware format (`11111<TAB>abacus`).
+pub struct DicewareList {
+    /// The words, ordered by their dice number.
+    pub words: Vec<String>,
+    /// How many dice select one word.
+    pub dice: usize,
+    /// Dice numbers that are missing from the list, if any.
+    pub missing: Vec<String>,
+    /// Dice numbers that appear more than once.
+    pub duplicates: Vec<String>,
+}
+
+impl DicewareList {
+    /// Whether every dice number appears exactly once.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.duplicates.is_empty()
+    }
+}
+
+/// Returns the number of entries a complete list for `dice` dice must have.
+pub fn diceware_list_size(dice: usize) -> usize {
+    6usize.pow(dice as u32)
+}
+
+/// Parses a numbered Diceware list, or returns `None` if `content` is not in that format.
+///
+/// Every non-empty line must start with the same number of digits 1-6, followed
+/// by whitespace and the word.
+pub fn parse_diceware_list(content: &str) -> Option<DicewareList> {
+    let mut dice = None;
+    let mut entries: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let (number, word) = line.split_once(|c: char| c.is_whitespace())?;
+        let word = word.trim();
+        if number.is_empty() || word.is_empty() || !number.chars().all(|c| ('1'..='6').contains(&c)) {
+            return None;
+        }
+        match dice {
+            None if number.len() <= MAX_DICE => dice = Some(number.len()),
+            Some(d) if d == number.len() => {}
+            _ => return None,
+        }
+        entries.entry(number.to_string()).or_default().push(word.to_string());
+    }
+
+    let dice = dice?;
+    let missing = (0..diceware_list_size(dice))
+        .map(|index| index_to_rolls(index, dice))
+        .filter(|rolls| !entries.contains_key(rolls))
+        .collect();
+    let duplicates = entries.iter().filter(|(_, words)| words.len() > 1).map(|(n, _)| n.clone()).collect();
+    let words = entries.into_values().flatten().collect();
+
+    Some(DicewareList { words, dice, missing, duplicates })
+}
+
+/// Converts a word index into its dice number, e.g. 0 -> "11111".
+fn index_to_rolls(mut index: usize, dice: usize) -> String {
+    let mut rolls = vec![b'1'; dice];
+    for position in (0..dice).rev() {
+        rolls[position] = b'1' + (index % 6) as u8;
+        index /= 6;
+    }
+    String::from_utf8(rolls).unwrap()
+}
+
+/// Converts a dice number such as "43125" into a word index.
+fn rolls_to_index(rolls: &str) -> Option<usize> {
+    rolls.chars().try_fold(0usize, |index, c| {
+        c.to_digit(10).filter(|d| (1..=6).contains(d)).map(|d| index * 6 + (d as usize - 1))
+    })
+}
+
+/// Checks that a passphrase style has no random parts the dice cannot decide.
+pub fn check_dice_style(style: &WordStyle) -> Result<(), String> {
+    if style.case == WordCase::Random || (style.use_separator && style.random_separator) {
+        return Err("Dice rolls cannot drive random casing or separators; turn those off.".to_string());
+    }
+    if style.insertion != Insertion::None {
+        return Err("Dice rolls cannot drive inserted characters; turn insertions off.".to_string());
+    }
+    Ok(())
+}
+
+/// Explains why `wordlist` cannot be used with dice rolls.
+pub fn dice_list_error(wordlist: &Wordlist) -> String {
+    format!(
+        "Dice rolls need a complete numbered Diceware wordlist, with every dice number listed exactly once. {}",
+        wordlist.status
+    )
+}
+
+/// Builds a passphrase from physical dice rolls instead of the random number generator.
+///
+/// `rolls` holds one group of digits 1-6 per word; whitespace and commas between
+/// the digits are ignored. The wordlist must be a complete numbered Diceware
+/// list, so every roll names exactly the word printed next to it. Only the
+/// fixed parts of the style apply, since the dice decide nothing else.
+pub fn generate_dice_passphrase(
+    rolls: &str,
+    count: usize,
+    wordlist: &Wordlist,
+    style: &WordStyle,
+) -> Result<String, String> {
+    check_dice_style(style)?;
+
+    let dice = wordlist.dice.ok_or_else(|| dice_list_error(wordlist))?;
+
+    let digits: String = rolls.chars().filter(|c| !c.is_whitespace() && *c != ',').collect();
+    if let Some(bad) = digits.chars().find(|c| !('1'..='6').contains(c)) {
+        return Err(format!("Dice rolls may only contain the digits 1 to 6 (found '{}').", bad));
+    }
+    if digits.len() != count * dice {
+        return Err(format!(
+            "Expected {} dice rolls ({} words x {} dice), got {}.",
+            count * dice,
+            count,
+            dice,
+            digits.len()
+        ));
+    }
+
+    let chosen: Vec<String> = digits
+        .as_bytes()
+        .chunks(dice)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let index = rolls_to_index(std::str::from_utf8(chunk).unwrap()).unwrap();
+            apply_case(&wordlist.words[index], style.case, i)
+        })
+        .collect();
+
+    let separator_str = if style.use_separator { style.separator.as_str() } else { "" };
+    Ok(chosen.join(separator_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::state::WordFilters;
+    use crate::app::wordlist::load_words_from_str;
+
+    fn wordlist(content: &str) -> Wordlist {
+        load_words_from_str(content, "test list", &WordFilters::default())
+    }
+
+    fn style() -> WordStyle {
+        WordStyle { case: WordCase::Lower, ..WordStyle::default() }
+    }
+
+    #[test]
+    fn complete_list_maps_each_roll_to_its_word() {
+        let list = wordlist("1 ant\n2 bee\n3 cat\n4 dog\n5 eel\n6 fox\n");
+        assert_eq!(list.dice, Some(1));
+        assert_eq!(generate_dice_passphrase("6, 1 3", 3, &list, &style()).unwrap(), "fox-ant-cat");
+    }
+
+    #[test]
+    fn incomplete_list_is_refused() {
+        let list = wordlist("1 ant\n2 bee\n3 cat\n4 dog\n5 eel\n");
+        assert_eq!(list.dice, None);
+        assert!(generate_dice_passphrase("1", 1, &list, &style()).unwrap_err().contains("complete numbered Diceware"));
+    }
+
+    #[test]
+    fn duplicate_number_is_refused_even_when_the_count_fits() {
+        // Six entries, as a one-die list needs, but 5 appears twice and 6 not at all.
+        let list = wordlist("1 ant\n2 bee\n3 cat\n4 dog\n5 eel\n5 fox\n");
+        assert_eq!(list.words.len(), 6);
+        assert_eq!(list.dice, None);
+        assert!(generate_dice_passphrase("5", 1, &list, &style()).is_err());
+    }
+
+    #[test]
+    fn unnumbered_list_is_refused_even_with_a_power_of_six_words() {
+        let list = wordlist("ant\nbee\ncat\ndog\neel\nfox\n");
+        assert_eq!(list.words.len(), 6);
+        assert_eq!(list.dice, None);
+        assert!(generate_dice_passphrase("1", 1, &list, &style()).is_err());
+    }
+}
@@ -0,0 +1,573 @@
+use crate::app::diceware::diceware_list_size;
+use crate::app::password::{
+    abbreviate_word, build_char_sampler, case_key, class_limits, insert_pool, length_bounds_error, longest_total,
+    passphrase_length_cap, random_case_forms, separator_pool, word_length_bounds, CharSampler,
+};
+use crate::app::pin::count_allowed_pins;
+use crate::app::pronounceable::{PronounceableModel, PronounceableSampler};
+use crate::app::sentence::{parse_grammar, TaggedWords};
+use crate::app::rules::{SequenceSampler, SpacedSampler, SEQUENCE_LIMIT};
+use crate::app::sampler::{log2_biguint, ClassLimits, ClassSampler};
+use crate::app::state::{CharacterClass, CharacterSets, Insertion, RepetitionRules, WordCase, WordStyle};
+use crate::app::template::{parse_template, TemplateSlot};
+use crate::app::unicode::{distinct_graphemes, grapheme_count, measure};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::f64::consts::LN_2;
+
+/// Describes the strength of a generator configuration.
+#[derive(Clone, Debug)]
+pub struct EntropyReport {
+    /// Bits of entropy of a single generated password or passphrase.
+    pub bits: f64,
+    /// Bits of entropy of the abbreviated form of a word passphrase, when one is given.
+    pub abbreviated_bits: Option<f64>,
+    /// Number of distinct characters or words the generator draws from.
+    pub pool_size: usize,
+    /// Number of characters or words in the output.
+    pub length: usize,
+    /// Caveats and details about how the figure was derived.
+    pub notes: Vec<String>,
+}
+
+impl fmt::Display for EntropyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Entropy: {:.2} bits ({} units drawn from a pool of {})",
+            self.bits, self.length, self.pool_size
+        )?;
+        if let Some(bits) = self.abbreviated_bits {
+            write!(f, "\nAbbreviated form: {:.2} bits", bits)?;
+        }
+        for note in &self.notes {
+            write!(f, "\n  - {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+/// Calculates the entropy of `generate_char_password` for the given classes.
+///
+/// With equal weights the generator is uniform over every password that meets
+/// the class limits and the repetition rule, so the figure is log2 of their
+/// exact number. Weighted classes lower it to the Shannon entropy of the skewed
+/// distribution.
+pub fn char_password_entropy(
+    length: usize,
+    classes: &[CharacterClass],
+    rules: &RepetitionRules,
+) -> Result<EntropyReport, String> {
+    let sampler = build_char_sampler(length, classes, rules)?;
+    let limits = class_limits(classes, length)?;
+
+    let mut notes: Vec<String> = classes
+        .iter()
+        .filter(|c| c.enabled)
+        .map(|c| {
+            let max = c.max.map(|m| m.to_string()).unwrap_or_else(|| "any".to_string());
+            let weight = if c.weight > 1 { format!(", weight {}", c.weight) } else { String::new() };
+            format!("{}: {} to {} characters{}", c.name, c.min, max, weight)
+        })
+        .collect();
+    notes.push(format!("No character more than {} times.", rules.max_occurrences));
+    if rules.no_adjacent_repeats {
+        notes.push("No character twice in a row.".to_string());
+    }
+    if rules.no_sequences {
+        notes.push(format!("No {} letters or digits in order, such as abc or 321.", SEQUENCE_LIMIT));
+    }
+    if rules.no_keyboard_walks {
+        notes.push(format!("No {} neighbouring keys along a keyboard row, such as qwe.", SEQUENCE_LIMIT));
+    }
+    let listed: usize = classes
+        .iter()
+        .filter(|c| c.enabled)
+        .map(|c| distinct_graphemes(&c.chars).len())
+        .sum();
+    if listed > sampler.pool_size() {
+        notes.push(format!(
+            "Some classes overlap; {} distinct characters each count toward the first class listing them.",
+            sampler.pool_size()
+        ));
+    }
+    if limits.iter().any(|c| c.weight > 1) {
+        let unweighted: Vec<ClassLimits> = limits.into_iter().map(|c| ClassLimits { weight: 1, ..c }).collect();
+        let uniform_bits = match &sampler {
+            CharSampler::Classes(_) => {
+                log2_biguint(ClassSampler::new(unweighted, length, rules.max_occurrences.min(length)).count())
+            }
+            CharSampler::Sequence(_) => SequenceSampler::new(&unweighted, length, rules)?.entropy_bits(),
+            CharSampler::Spaced(_) => SpacedSampler::new(unweighted, length, rules.max_occurrences).entropy_bits(),
+        };
+        notes.push(format!("Weights skew the choice; equal weights would give {:.2} bits.", uniform_bits));
+    }
+
+    Ok(EntropyReport {
+        bits: sampler.entropy_bits(),
+        abbreviated_bits: None,
+        pool_size: sampler.pool_size(),
+        length,
+        notes,
+    })
+}
+
+/// Calculates the entropy of `generate_word_password` for the given settings.
+///
+/// The generator picks distinct wordlist entries uniformly among the choices
+/// that fit the length bounds, so the figure is the Shannon entropy of those
+/// passphrases; when bounds apply, a note says how many bits they cost against
+/// picking any words at all. Entries that become identical after capitalization
+/// are weighted by how many entries produce them. Random casing, random
+/// separators and inserted characters add their own bits on top; the casing and
+/// inside-insertion terms average over the usable words. With abbreviation on,
+/// the abbreviated form gets a figure of its own, which treats the prefix each
+/// word shares with others as lost independently.
+pub fn word_password_entropy(count: usize, words: &[String], style: &WordStyle) -> Result<EntropyReport, String> {
+    if count == 0 {
+        return Err("A passphrase needs at least one word.".to_string());
+    }
+    if words.len() < count {
+        return Err(format!(
+            "Not enough words in words.txt (found {}, need at least {}).",
+            words.len(),
+            count
+        ));
+    }
+    let (min, max) = word_length_bounds(count, style)?;
+
+    // Group entries by the word they turn into, then batch words by (byte length, multiplicity).
+    let mut multiplicity: HashMap<String, usize> = HashMap::new();
+    for word in words {
+        *multiplicity.entry(case_key(word, style.case)).or_insert(0) += 1;
+    }
+    let mut batches: HashMap<(usize, usize), usize> = HashMap::new();
+    let length = |word: &str| measure(word, style.length_unit);
+    for (word, m) in &multiplicity {
+        if max.is_none_or(|max| length(word) <= max) {
+            *batches.entry((length(word), *m)).or_insert(0) += 1;
+        }
+    }
+
+    // The same sums with every length ignored give the entropy of unrestricted picks.
+    let mut unrestricted: HashMap<(usize, usize), usize> = HashMap::new();
+    for m in multiplicity.values() {
+        *unrestricted.entry((0, *m)).or_insert(0) += 1;
+    }
+    let (total, total_log) = word_set_sums(&unrestricted, count, 0, 0);
+    let unbounded_bits = total + log2_factorial(count) - (total_log - total).exp2();
+
+    let bounded = min > 0 || max.is_some();
+    let (accepted, weighted_log) = if bounded {
+        let max = max.unwrap_or_else(|| longest_total(multiplicity.keys().map(|w| length(w)), count));
+        word_set_sums(&batches, count, min, max)
+    } else {
+        (total, total_log)
+    };
+    if accepted == f64::NEG_INFINITY {
+        return Err(length_bounds_error(style, count));
+    }
+    let mut bits = accepted + log2_factorial(count) - (weighted_log - accepted).exp2();
+
+    let mut notes = vec![match passphrase_length_cap(count, style) {
+        Some(cap) => format!(
+            "{} distinct words, passphrases capped at {} {}.",
+            multiplicity.len(),
+            cap,
+            style.length_unit.label()
+        ),
+        None => format!("{} distinct words, passphrases of any length.", multiplicity.len()),
+    }];
+    if bounded {
+        notes.push(format!(
+            "The length limits leave {:.2} of {:.2} bits of word choice; words are drawn evenly among those that fit.",
+            bits, unbounded_bits
+        ));
+    }
+    let usable_words: Vec<&String> =
+        multiplicity.keys().filter(|word| max.is_none_or(|max| length(word) <= max)).collect();
+    let usable = usable_words.len();
+    let average = |f: &dyn Fn(&String) -> f64| usable_words.iter().map(|w| f(w)).sum::<f64>() / usable.max(1) as f64;
+    if usable < multiplicity.len() {
+        notes.push(format!("{} words are too long to ever fit under the cap.", multiplicity.len() - usable));
+    }
+    if multiplicity.len() < words.len() {
+        notes.push(format!(
+            "{} entries collapse into {} words after capitalization.",
+            words.len(),
+            multiplicity.len()
+        ));
+    }
+    let letters = style.abbreviate;
+    let mut abbreviated_bits = (letters > 0).then_some(bits);
+    if let Some(abbreviated_bits) = &mut abbreviated_bits {
+        // Words sharing a prefix look the same once abbreviated: subtract what the prefix no longer tells apart.
+        let weight = |word: &String| multiplicity[word] as f64;
+        let mut groups: HashMap<String, f64> = HashMap::new();
+        for word in &usable_words {
+            *groups.entry(abbreviate_word(word, letters)).or_insert(0.0) += weight(word);
+        }
+        let entries: f64 = groups.values().sum();
+        let loss: f64 = usable_words
+            .iter()
+            .map(|word| weight(word) / entries * (groups[&abbreviate_word(word, letters)] / weight(word)).log2())
+            .sum();
+        let shared = usable_words.iter().filter(|word| groups[&abbreviate_word(word, letters)] > weight(word));
+        match shared.count() {
+            0 => notes.push(format!(
+                "Typing only the first {} letters of each word loses nothing: no two words share them.",
+                letters
+            )),
+            shared => {
+                *abbreviated_bits -= count as f64 * loss;
+                notes.push(format!(
+                    "{} of {} words share their first {} letters with another, so the abbreviated form loses about \
+                     {:.2} bits per word. Filter the wordlist to unique {}-letter prefixes to avoid this.",
+                    shared, usable, letters, loss, letters
+                ));
+            }
+        }
+    }
+    // Both forms share the separators and inserted characters; casing and inside insertions see the shorter words.
+    let mut add = |full: f64, abbreviated: f64| {
+        bits += full;
+        if let Some(bits) = &mut abbreviated_bits {
+            *bits += abbreviated;
+        }
+    };
+    if style.case == WordCase::Random {
+        let per_word = |letters| average(&|w| (random_case_forms(&abbreviate_word(w, letters)).len() as f64).log2());
+        add(count as f64 * per_word(0), count as f64 * per_word(letters));
+        notes.push(format!("Random casing adds about {:.2} bits per word.", per_word(0)));
+    }
+    let separators = separator_pool(style);
+    if style.use_separator && style.random_separator && count > 1 {
+        let per_gap = (separators.len() as f64).log2();
+        add((count - 1) as f64 * per_gap, (count - 1) as f64 * per_gap);
+        notes.push(format!("Each of {} separators adds {:.2} bits ({} choices).", count - 1, per_gap, separators.len()));
+    }
+    let inserts = insert_pool(style);
+    let k = style.insert_count;
+    match style.insertion {
+        Insertion::Between if k > 0 => {
+            let placement = log2_binomial(k + count, k);
+            let inserted = k as f64 * (inserts.len() as f64).log2() + placement;
+            add(inserted, inserted);
+            notes.push(format!(
+                "{} inserted characters from {} choices, placed around the words ({:.2} bits for placement).",
+                k,
+                inserts.len(),
+                placement
+            ));
+        }
+        Insertion::Inside if k > 0 => {
+            // An insertion past the abbreviation's end goes at its end, so abbreviating leaves fewer places.
+            let inserted = |cap: usize| {
+                let places = |w: &String| grapheme_count(w).saturating_sub(1).min(cap);
+                let per_position = average(&|w| (places(w).max(1) as f64).log2());
+                k as f64 * ((inserts.len() as f64).log2() + per_position) + log2_binomial(count, k)
+            };
+            add(inserted(usize::MAX), inserted(letters));
+            notes.push(format!(
+                "{} inserted characters from {} choices inside {} of the words (approximate).",
+                k,
+                inserts.len(),
+                k
+            ));
+        }
+        _ => {}
+    }
+    if style.insertion != Insertion::None
+        && inserts.iter().any(|c| separators.contains(c) || usable_words.iter().any(|w| w.contains(c.as_str())))
+    {
+        notes.push("Inserted characters also appear in words or separators, so some outputs coincide.".to_string());
+    }
+    if !style.use_separator || (!style.random_separator && style.separator.is_empty()) {
+        notes.push("No separator is used; assumes word boundaries cannot be confused.".to_string());
+    }
+
+    Ok(EntropyReport { bits, abbreviated_bits, pool_size: usable, length: count, notes })
+}
+
+/// Sums over every unordered choice of `count` entries whose lengths add up to
+/// between `min` and `max`, from words batched by (byte length, multiplicity).
+///
+/// Returns log2 of the number of choices, and log2 of the same sum weighted by
+/// log2 of how many entry sets yield the same words; `-inf` stands for zero. Both
+/// sums stay in log space, since they outgrow `f64` for long passphrases from large lists.
+fn word_set_sums(batches: &HashMap<(usize, usize), usize>, count: usize, min: usize, max: usize) -> (f64, f64) {
+    // counts[j][t]: log2 of the ways to choose j entries with total length t; logs[j][t]: of the weighted sum.
+    let mut counts = vec![vec![f64::NEG_INFINITY; max + 1]; count + 1];
+    let mut logs = counts.clone();
+    counts[0][0] = 0.0;
+    for (&(len, m), &n) in batches {
+        // Words backed by a single entry are folded in together; the rest one word at a time.
+        let steps: Vec<Vec<(usize, f64, f64)>> = if m == 1 {
+            vec![(1..=count.min(n)).map(|c| (c, log2_binomial(n, c), f64::NEG_INFINITY)).collect()]
+        } else {
+            let options: Vec<_> =
+                (1..=count.min(m)).map(|c| (c, log2_binomial(m, c), log2_falling_factorial(m, c).log2())).collect();
+            vec![options; n]
+        };
+        for options in steps {
+            let mut next_counts = counts.clone();
+            let mut next_logs = logs.clone();
+            for j in 0..count {
+                for t in 0..=max {
+                    if counts[j][t] == f64::NEG_INFINITY {
+                        continue;
+                    }
+                    for &(c, ways, weight) in &options {
+                        let (nj, nt) = (j + c, t + c * len);
+                        if nj > count || nt > max {
+                            continue;
+                        }
+                        let weighted = log2_add(logs[j][t], counts[j][t] + weight) + ways;
+                        next_counts[nj][nt] = log2_add(next_counts[nj][nt], counts[j][t] + ways);
+                        next_logs[nj][nt] = log2_add(next_logs[nj][nt], weighted);
+                    }
+                }
+            }
+            counts = next_counts;
+            logs = next_logs;
+        }
+    }
+    let sum = |row: &[f64]| row[min.min(max + 1)..].iter().fold(f64::NEG_INFINITY, |a, &b| log2_add(a, b));
+    (sum(&counts[count]), sum(&logs[count]))
+}
+
+/// log2(2^a + 2^b), where `-inf` stands for zero.
+fn log2_add(a: f64, b: f64) -> f64 {
+    let (high, low) = if a > b { (a, b) } else { (b, a) };
+    if low == f64::NEG_INFINITY {
+        return high;
+    }
+    high + (low - high).exp2().ln_1p() / LN_2
+}
+
+/// Calculates the entropy of `generate_template_password` for a template.
+///
+/// Each token is filled independently and uniformly, so the figure is the sum
+/// of log2 of every token's pool size. Literal characters add nothing.
+pub fn template_password_entropy(template: &str, sets: &CharacterSets) -> Result<EntropyReport, String> {
+    let slots = parse_template(template, sets)?;
+
+    // Per token: (pool size, number of occurrences), in order of first appearance.
+    let mut tokens: Vec<(char, usize, usize)> = Vec::new();
+    let mut pool: HashSet<&str> = HashSet::new();
+    let mut literals = 0;
+    for slot in &slots {
+        match slot {
+            TemplateSlot::Literal(_) => literals += 1,
+            TemplateSlot::Pool { token, chars } => {
+                pool.extend(chars.iter().map(String::as_str));
+                match tokens.iter_mut().find(|(t, _, _)| t == token) {
+                    Some(entry) => entry.2 += 1,
+                    None => tokens.push((*token, chars.len(), 1)),
+                }
+            }
+        }
+    }
+
+    let bits = tokens.iter().map(|(_, size, n)| *n as f64 * (*size as f64).log2()).sum();
+    let mut notes: Vec<String> = tokens
+        .iter()
+        .map(|(token, size, n)| {
+            format!("'{}': {} x {:.2} bits ({} characters)", token, n, (*size as f64).log2(), size)
+        })
+        .collect();
+    if literals > 0 {
+        notes.push(format!("{} literal characters add no entropy.", literals));
+    }
+
+    Ok(EntropyReport { bits, abbreviated_bits: None, pool_size: pool.len(), length: slots.len(), notes })
+}
+
+/// Calculates the entropy of the passwords a `PronounceableSampler` draws.
+///
+/// Each letter is picked uniformly among those the model allows after the
+/// previous two, so the figure is the exact Shannon entropy of the walk. The
+/// weakest possible output is reported as well, since some paths offer fewer
+/// choices than others.
+pub fn pronounceable_password_entropy(sampler: &PronounceableSampler) -> EntropyReport {
+    let (model, length) = (sampler.model(), sampler.length());
+    let contexts: Vec<(usize, usize)> = PronounceableModel::contexts().collect();
+
+    // probability[i]: chance of being in context i; weakest[i]: fewest bits of any path to it.
+    let mut probability = vec![0.0f64; contexts.len()];
+    let mut weakest = vec![f64::INFINITY; contexts.len()];
+    let (a, b) = PronounceableModel::start();
+    probability[PronounceableModel::context_index(a, b)] = 1.0;
+    weakest[PronounceableModel::context_index(a, b)] = 0.0;
+
+    let mut bits = 0.0;
+    for _ in 0..length {
+        let mut next_probability = vec![0.0f64; contexts.len()];
+        let mut next_weakest = vec![f64::INFINITY; contexts.len()];
+        for &(a, b) in &contexts {
+            let i = PronounceableModel::context_index(a, b);
+            if weakest[i].is_infinite() {
+                continue;
+            }
+            let allowed = model.allowed(a, b);
+            let step = (allowed.len() as f64).log2();
+            bits += probability[i] * step;
+            for &c in allowed {
+                let j = PronounceableModel::context_index(b, c as usize);
+                next_probability[j] += probability[i] / allowed.len() as f64;
+                next_weakest[j] = next_weakest[j].min(weakest[i] + step);
+            }
+        }
+        probability = next_probability;
+        weakest = next_weakest;
+    }
+
+    let weakest_bits = weakest.iter().copied().fold(f64::INFINITY, f64::min);
+    let notes = vec![
+        format!("Letters follow patterns learned from {} words.", sampler.trained_words()),
+        format!("The least random output this model can produce has {:.2} bits.", weakest_bits),
+        format!(
+            "A fully random lowercase password of the same length has {:.2} bits.",
+            length as f64 * 26f64.log2()
+        ),
+    ];
+
+    EntropyReport { bits, abbreviated_bits: None, pool_size: 26, length, notes }
+}
+
+/// Calculates the entropy of `generate_sentence_passphrase` for a grammar.
+///
+/// Each slot is filled independently and uniformly, so the figure is the sum
+/// of log2 of every slot's pool size.
+pub fn sentence_passphrase_entropy(
+    grammar: &str,
+    words: &TaggedWords,
+    separator: &str,
+) -> Result<EntropyReport, String> {
+    let slots = parse_grammar(grammar)?;
+    let pools = words.pools(&slots)?;
+    let bits = pools.iter().map(|pool| (pool.len() as f64).log2()).sum();
+    let mut notes: Vec<String> = slots
+        .iter()
+        .zip(&pools)
+        .map(|(slot, pool)| format!("{}: {} words ({:.2} bits)", slot, pool.len(), (pool.len() as f64).log2()))
+        .collect();
+    if separator.is_empty() || pools.iter().flatten().any(|word| word.contains(separator)) {
+        notes.push("The separator does not always mark word boundaries; assumes they cannot be confused.".to_string());
+    }
+    let distinct: HashSet<&str> = pools.iter().flatten().copied().collect();
+    Ok(EntropyReport { bits, abbreviated_bits: None, pool_size: distinct.len(), length: slots.len(), notes })
+}
+
+/// Calculates the entropy of `generate_pin`.
+///
+/// Every allowed PIN is equally likely, so the figure is log2 of their exact
+/// number; a note says how many bits the left-out patterns cost.
+pub fn pin_entropy(length: usize) -> Result<EntropyReport, String> {
+    let (allowed, rejected) = count_allowed_pins(length)?;
+    let bits = (allowed as f64).log2();
+    let notes = vec![
+        format!(
+            "Leaves out {} of {} PINs: common PINs, runs, repeats, keypad lines, mirrored PINs and dates.",
+            rejected,
+            allowed + rejected
+        ),
+        format!("That costs {:.2} bits against any {} digits.", length as f64 * 10f64.log2() - bits, length),
+    ];
+    Ok(EntropyReport { bits, abbreviated_bits: None, pool_size: 10, length, notes })
+}
+
+/// Calculates the entropy of a passphrase built from physical dice rolls.
+///
+/// Fair dice select every word uniformly and independently, so each word adds
+/// log2 of the list size no matter how long it is.
+pub fn dice_passphrase_entropy(count: usize, dice: usize) -> EntropyReport {
+    let list_size = diceware_list_size(dice);
+    EntropyReport {
+        bits: count as f64 * (list_size as f64).log2(),
+        abbreviated_bits: None,
+        pool_size: list_size,
+        length: count,
+        notes: vec![format!("{} words x {} fair dice rolls each.", count, dice)],
+    }
+}
+
+/// The number of ways to choose `k` of `n` items, as a float.
+pub fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+fn log2_binomial(n: usize, k: usize) -> f64 {
+    log2_falling_factorial(n, k) - log2_factorial(k)
+}
+
+/// log2 of `n!`.
+pub fn log2_factorial(n: usize) -> f64 {
+    (2..=n).map(|i| (i as f64).log2()).sum()
+}
+
+fn log2_falling_factorial(n: usize, k: usize) -> f64 {
+    (0..k).map(|i| ((n - i) as f64).log2()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|w| w.to_string()).collect()
+    }
+
+    fn plain_style() -> WordStyle {
+        WordStyle { case: WordCase::Lower, use_length_cap: false, ..WordStyle::default() }
+    }
+
+    fn assert_bits(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} bits, expected {}", actual, expected);
+    }
+
+    #[test]
+    fn word_entropy_of_a_small_list() {
+        let list = words(&["apple", "apricot", "banana", "cherry"]);
+        // 4 x 3 ordered pairs of distinct words.
+        let report = word_password_entropy(2, &list, &plain_style()).unwrap();
+        assert_bits(report.bits, 12f64.log2());
+        assert_eq!(report.abbreviated_bits, None);
+
+        // "ap" stands for two words: the full word still carries 2 bits, its abbreviation 1.5.
+        let style = WordStyle { abbreviate: 2, ..plain_style() };
+        let report = word_password_entropy(1, &list, &style).unwrap();
+        assert_bits(report.bits, 2.0);
+        assert_bits(report.abbreviated_bits.unwrap(), 1.5);
+        assert!(report.to_string().contains("Abbreviated form: 1.50 bits"));
+    }
+
+    #[test]
+    fn word_entropy_within_length_bounds() {
+        let list = words(&["a", "bb", "ccc", "dddd"]);
+        let style = WordStyle { use_separator: false, use_length_cap: true, max_length: 5, ..plain_style() };
+        // {a, bb}, {a, ccc}, {a, dddd} and {bb, ccc}, in either order.
+        assert_bits(word_password_entropy(2, &list, &style).unwrap().bits, 3.0);
+        // Only {a, dddd} and {bb, ccc} reach 5 letters.
+        let style = WordStyle { min_length: 5, ..style };
+        assert_bits(word_password_entropy(2, &list, &style).unwrap().bits, 2.0);
+    }
+
+    #[test]
+    fn word_entropy_weighs_entries_that_collapse() {
+        // "a" is drawn two times in three.
+        let report = word_password_entropy(1, &words(&["a", "A", "b"]), &plain_style()).unwrap();
+        assert_bits(report.bits, 1.5f64.log2() * 2.0 / 3.0 + 3f64.log2() / 3.0);
+    }
+
+    #[test]
+    fn word_entropy_of_long_passphrases_stays_finite() {
+        // C(5000, 200) is far beyond the range of f64.
+        let list: Vec<String> = (0..5000).map(|i| format!("w{}", i)).collect();
+        let report = word_password_entropy(200, &list, &plain_style()).unwrap();
+        assert!((report.bits - log2_falling_factorial(5000, 200)).abs() < 1e-6, "{} bits", report.bits);
+    }
+}
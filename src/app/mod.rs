@@ -1,7 +1,23 @@
 // Declare all the modules in the `app` directory so they can see each other.
+pub mod batch;
+pub mod breach;
 pub mod cli;
 pub mod config;
+pub mod derive;
+pub mod diceware;
+pub mod entropy;
 pub mod password;
+pub mod pin;
+pub mod policy;
+pub mod pronounceable;
+pub mod rng;
+pub mod rules;
+pub mod sampler;
+pub mod sentence;
 pub mod state;
+pub mod strength;
+pub mod template;
+pub mod unicode;
 pub mod ui;
 pub mod utils;
+pub mod wordlist;
@@ -1,153 +1,547 @@
-use crate::app::state::CharacterSets;
+use crate::app::rules::{SequenceSampler, SpacedSampler};
+use crate::app::sampler::{ClassLimits, ClassSampler, WordLengthSampler};
+use crate::app::state::{CharacterClass, CharacterSets, Insertion, LengthUnit, RepetitionRules, WordCase, WordStyle};
+use crate::app::unicode::{distinct_graphemes, grapheme_count, graphemes, measure, nfc};
+use rand::seq::index::sample;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use std::collections::HashMap;
+use rand::Rng;
+use std::collections::HashSet;
 
-/// Generates a password from random characters.
-pub fn generate_char_password(
-    length: usize,
-    sets: &CharacterSets,
-    use_lowercase: bool,
-    use_uppercase: bool,
-    use_numbers: bool,
-    use_special: bool,
-) -> Result<String, String> {
-    let mut password_chars: Vec<char> = Vec::new();
-    let mut all_chars: Vec<char> = Vec::new();
-    let mut selected_categories = 0;
+/// The default maximum number of times any single character may appear in a password.
+pub const MAX_CHAR_REPEATS: usize = 3;
+
+/// Characters that are easily confused with one another when read or typed.
+pub const AMBIGUOUS_CHARS: &str = "0Oo1lI|5S'\"`";
+
+/// Collects every character that must not appear in generated passwords.
+pub fn excluded_characters(exclude_ambiguous: bool, excluded_chars: &str) -> HashSet<String> {
+    let mut excluded: HashSet<String> =
+        graphemes(excluded_chars).into_iter().filter(|g| !g.chars().all(char::is_whitespace)).collect();
+    if exclude_ambiguous {
+        excluded.extend(graphemes(AMBIGUOUS_CHARS));
+    }
+    excluded
+}
+
+/// Returns a copy of the classes with the excluded characters removed.
+pub fn remove_excluded_from_classes(classes: &[CharacterClass], excluded: &HashSet<String>) -> Vec<CharacterClass> {
+    classes
+        .iter()
+        .map(|class| CharacterClass {
+            chars: graphemes(&class.chars).into_iter().filter(|g| !excluded.contains(g)).collect(),
+            ..class.clone()
+        })
+        .collect()
+}
+
+/// Returns a copy of the character sets with the excluded characters removed.
+pub fn remove_excluded_from_sets(sets: &CharacterSets, excluded: &HashSet<String>) -> CharacterSets {
+    let keep = |set: &str| graphemes(set).into_iter().filter(|g| !excluded.contains(g)).collect();
+    CharacterSets {
+        lowercase: keep(&sets.lowercase),
+        uppercase: keep(&sets.uppercase),
+        numbers: keep(&sets.numbers),
+        special: keep(&sets.special),
+    }
+}
+
+/// Turns the enabled classes into disjoint classes for the sampler.
+///
+/// Characters are grapheme clusters after NFC normalization. A character listed
+/// in several classes belongs to the first of them only. The length is checked
+/// against the minimums before maximums are capped at it, so a short length is
+/// reported as such rather than as a class whose minimum exceeds its maximum.
+pub fn class_limits(classes: &[CharacterClass], length: usize) -> Result<Vec<ClassLimits>, String> {
+    let required: usize = classes.iter().filter(|c| c.enabled).map(|c| c.min).sum();
+    if length < required {
+        return Err(format!(
+            "Password length must be at least {} to include the minimum of each class.",
+            required
+        ));
+    }
 
-    if use_lowercase {
-        let chars: Vec<char> = sets.lowercase.chars().collect();
-        if !chars.is_empty() {
-            password_chars.push(*chars.choose(&mut thread_rng()).unwrap());
-            all_chars.extend(chars);
-            selected_categories += 1;
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut limits = Vec::new();
+    for class in classes.iter().filter(|c| c.enabled) {
+        let chars: Vec<String> = graphemes(&class.chars).into_iter().filter(|g| seen.insert(g.clone())).collect();
+        let max = class.max.unwrap_or(length).min(length);
+        if class.min > max {
+            return Err(format!("Class '{}' has a minimum above its maximum.", class.name));
+        }
+        if chars.is_empty() {
+            if class.min > 0 {
+                return Err(format!("Class '{}' has no characters of its own to draw from.", class.name));
+            }
+            continue;
         }
+        limits.push(ClassLimits { chars, min: class.min, max, weight: class.weight.max(1) });
     }
-    if use_uppercase {
-        let chars: Vec<char> = sets.uppercase.chars().collect();
-        if !chars.is_empty() {
-            password_chars.push(*chars.choose(&mut thread_rng()).unwrap());
-            all_chars.extend(chars);
-            selected_categories += 1;
+    Ok(limits)
+}
+
+/// Draws character passwords, picking the sampler that can enforce the rules.
+pub enum CharSampler {
+    /// Exact counting by class, used when no rule depends on character order.
+    Classes(ClassSampler),
+    /// Position-by-position counting for the sequence rules.
+    Sequence(SequenceSampler),
+    /// Counting by runs, used when doubled characters are the only order rule and the per-character limit binds.
+    Spaced(SpacedSampler),
+}
+
+impl CharSampler {
+    /// The number of distinct characters across all classes.
+    pub fn pool_size(&self) -> usize {
+        match self {
+            CharSampler::Classes(sampler) => sampler.pool_size(),
+            CharSampler::Sequence(sampler) => sampler.pool_size(),
+            CharSampler::Spaced(sampler) => sampler.pool_size(),
         }
     }
-    if use_numbers {
-        let chars: Vec<char> = sets.numbers.chars().collect();
-        if !chars.is_empty() {
-            password_chars.push(*chars.choose(&mut thread_rng()).unwrap());
-            all_chars.extend(chars);
-            selected_categories += 1;
+
+    /// Shannon entropy in bits of the passwords this sampler draws.
+    pub fn entropy_bits(&self) -> f64 {
+        match self {
+            CharSampler::Classes(sampler) => sampler.entropy_bits(),
+            CharSampler::Sequence(sampler) => sampler.entropy_bits(),
+            CharSampler::Spaced(sampler) => sampler.entropy_bits(),
         }
     }
-    if use_special {
-        let chars: Vec<char> = sets.special.chars().collect();
-        if !chars.is_empty() {
-            password_chars.push(*chars.choose(&mut thread_rng()).unwrap());
-            all_chars.extend(chars);
-            selected_categories += 1;
+
+    /// Draws one password.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<String, String> {
+        match self {
+            CharSampler::Classes(sampler) => sampler
+                .sample(rng)
+                .ok_or_else(|| "No password satisfies the class limits and repetition rule.".to_string()),
+            CharSampler::Sequence(sampler) => sampler.sample(rng),
+            CharSampler::Spaced(sampler) => sampler.sample(rng),
         }
     }
+}
 
-    if selected_categories == 0 {
-        return Err("You must select at least one character set.".to_string());
+/// Builds the sampler for a character password, checking that the policy can be met.
+pub fn build_char_sampler(
+    length: usize,
+    classes: &[CharacterClass],
+    rules: &RepetitionRules,
+) -> Result<CharSampler, String> {
+    let limits = class_limits(classes, length)?;
+    if limits.is_empty() {
+        return Err("You must enable at least one character class.".to_string());
     }
-    if length < selected_categories {
-        return Err(format!(
-            "Password length must be at least {} to include one of each selected type.",
-            selected_categories
-        ));
+    if length == 0 {
+        return Err("Password length must be at least 1.".to_string());
+    }
+    if rules.max_occurrences == 0 {
+        return Err("Each character must be allowed at least once.".to_string());
     }
 
-    let unique_chars_count = all_chars.iter().collect::<std::collections::HashSet<_>>().len();
-    if length > unique_chars_count * 3 {
+    let unique_chars_count: usize = limits.iter().map(|c| c.chars.len()).sum();
+    if length > unique_chars_count.saturating_mul(rules.max_occurrences) {
         return Err(
             "Cannot generate: not enough unique characters for the requested length and repetition rule.".to_string()
         );
     }
 
-    for _ in 0..100 {
-        let mut candidate_chars = password_chars.clone();
-        let remaining_length = length - candidate_chars.len();
+    // Doubles alone are counted by runs, which keeps a binding per-character limit cheap to track.
+    let only_doubles = rules.no_adjacent_repeats && !rules.no_sequences && !rules.no_keyboard_walks;
+    if only_doubles && limits.iter().any(|c| c.max > rules.max_occurrences) {
+        let sampler = SpacedSampler::new(limits, length, rules.max_occurrences);
+        if sampler.is_empty() {
+            return Err(format!(
+                "No password of length {} satisfies the class limits and sequence rules.",
+                length
+            ));
+        }
+        return Ok(CharSampler::Spaced(sampler));
+    }
+    if rules.has_sequence_rules() {
+        let sampler = SequenceSampler::new(&limits, length, rules)?;
+        if sampler.is_empty() {
+            return Err(format!(
+                "No password of length {} satisfies the class limits and sequence rules.",
+                length
+            ));
+        }
+        return Ok(CharSampler::Sequence(sampler));
+    }
+
+    let sampler = ClassSampler::new(limits, length, rules.max_occurrences.min(length));
+    if sampler.is_empty() {
+        return Err(format!(
+            "No password of length {} satisfies the class limits and repetition rule.",
+            length
+        ));
+    }
+    Ok(CharSampler::Classes(sampler))
+}
+
+/// Returns how many characters fill a password of `length` in the given unit.
+///
+/// The samplers fix the number of characters, so a length in bytes needs every
+/// character in the pool to take the same number of bytes.
+pub fn char_password_units(length: usize, unit: LengthUnit, classes: &[CharacterClass]) -> Result<usize, String> {
+    if unit == LengthUnit::Characters {
+        return Ok(length);
+    }
+    let mut widths: Vec<usize> =
+        class_limits(classes, length)?.iter().flat_map(|class| class.chars.iter().map(|c| c.len())).collect();
+    widths.sort_unstable();
+    widths.dedup();
+    match widths[..] {
+        [] => Ok(length),
+        [width] if length.is_multiple_of(width) => Ok(length / width),
+        [width] => Err(format!("Every character takes {} bytes, so {} bytes cannot be filled exactly.", width, length)),
+        _ => Err(format!(
+            "The characters take between {} and {} bytes each, so a length in bytes cannot be met exactly. \
+             Count the length in characters instead.",
+            widths[0],
+            widths[widths.len() - 1]
+        )),
+    }
+}
+
+/// Generates a password from random characters.
+///
+/// Every password that meets each class's minimum and maximum and the
+/// repetition and sequence rules is equally likely, unless classes are given
+/// weights.
+pub fn generate_char_password<R: Rng + ?Sized>(
+    length: usize,
+    classes: &[CharacterClass],
+    rules: &RepetitionRules,
+    rng: &mut R,
+) -> Result<String, String> {
+    build_char_sampler(length, classes, rules)?.sample(rng)
+}
+
+/// Draws passphrases for one word count, wordlist and style.
+///
+/// When the passphrase length is bounded, the words are drawn uniformly among
+/// every choice that fits, rather than by redrawing until one does.
+pub struct WordSampler<'a> {
+    count: usize,
+    words: &'a [String],
+    style: &'a WordStyle,
+    /// Present when the length is bounded.
+    lengths: Option<WordLengthSampler<'a>>,
+}
+
+impl<'a> WordSampler<'a> {
+    /// Checks the settings and prepares the length tables, if any.
+    pub fn new(count: usize, words: &'a [String], style: &'a WordStyle) -> Result<Self, String> {
+        if count == 0 {
+            return Err("A passphrase needs at least one word.".to_string());
+        }
+        if words.len() < count {
+            return Err(format!(
+                "Not enough words in words.txt (found {}, need at least {}).",
+                words.len(),
+                count
+            ));
+        }
+
+        let lengths = match word_length_bounds(count, style)? {
+            (0, None) => None,
+            (min, max) => {
+                let entries: Vec<(usize, &String)> =
+                    words.iter().map(|word| (measure(&case_key(word, style.case), style.length_unit), word)).collect();
+                let max = max.unwrap_or_else(|| longest_total(entries.iter().map(|(length, _)| *length), count));
+                Some(WordLengthSampler::new(entries, count, min, max))
+            }
+        };
+        Ok(Self { count, words, style, lengths })
+    }
+
+    /// Draws one passphrase.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<String, String> {
+        self.sample_abbreviated(rng).map(|(passphrase, _)| passphrase)
+    }
 
-        if remaining_length > 0 {
-            candidate_chars.extend(all_chars.choose_multiple(&mut thread_rng(), remaining_length));
+    /// Draws one passphrase together with its abbreviated form, when the style asks for one.
+    pub fn sample_abbreviated<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<(String, Option<String>), String> {
+        // Only inside insertions can fail after the words are picked, when too few of them are long enough.
+        for _ in 0..100 {
+            let chosen_words: Vec<&String> = match &self.lengths {
+                None => self.words.choose_multiple(rng, self.count).collect(),
+                Some(lengths) => lengths.sample(rng).ok_or_else(|| length_bounds_error(self.style, self.count))?,
+            };
+            if let Some(passphrase) = style_passphrase(&chosen_words, self.style, rng) {
+                return Ok(passphrase);
+            }
         }
+        Err(format!("Could not find {} words long enough to insert characters into.", self.style.insert_count))
+    }
+}
+
+/// Generates a passphrase from a list of words, respecting length constraints,
+/// together with its abbreviated form when the style asks for one.
+pub fn generate_word_password<R: Rng + ?Sized>(
+    count: usize,
+    words: &[String],
+    style: &WordStyle,
+    rng: &mut R,
+) -> Result<(String, Option<String>), String> {
+    WordSampler::new(count, words, style)?.sample_abbreviated(rng)
+}
+
+/// The sum of the `count` largest lengths.
+pub fn longest_total(lengths: impl Iterator<Item = usize>, count: usize) -> usize {
+    let mut lengths: Vec<usize> = lengths.collect();
+    lengths.sort_unstable_by(|a, b| b.cmp(a));
+    lengths.iter().take(count).sum()
+}
+
+/// Explains that no passphrase fits the length bounds.
+pub fn length_bounds_error(style: &WordStyle, count: usize) -> String {
+    let unit = style.length_unit.label();
+    match passphrase_length_cap(count, style) {
+        Some(max) if style.min_length > 0 => format!(
+            "No {}-word passphrase fits between {} and {} {}. Check words.txt and the length limits.",
+            count, style.min_length, max, unit
+        ),
+        Some(max) => format!(
+            "Could not generate a passphrase under {} {}. Check words.txt for long words.",
+            max, unit
+        ),
+        None => format!(
+            "No {}-word passphrase reaches {} {}. Check words.txt and the minimum length.",
+            count, style.min_length, unit
+        ),
+    }
+}
 
-        candidate_chars.shuffle(&mut thread_rng());
+/// Turns chosen words into a passphrase: casing, inserted characters and separators.
+///
+/// When `style.abbreviate` is set, also returns the same passphrase with each word
+/// cut to its first letters; characters inserted past the cut go at its end.
+/// Returns `None` if too few of the words are long enough for the inside insertions.
+fn style_passphrase<R: Rng + ?Sized>(
+    chosen: &[&String],
+    style: &WordStyle,
+    rng: &mut R,
+) -> Option<(String, Option<String>)> {
+    let mut words: Vec<String> = chosen
+        .iter()
+        .enumerate()
+        .map(|(i, word)| match style.case {
+            WordCase::Random => random_case_forms(word).choose(rng).unwrap().clone(),
+            case => apply_case(word, case, i),
+        })
+        .collect();
+    let mut abbreviations: Vec<String> = words.iter().map(|word| abbreviate_word(word, style.abbreviate)).collect();
 
-        let mut counts = HashMap::new();
-        let mut is_valid = true;
-        for &char in &candidate_chars {
-            let count = counts.entry(char).or_insert(0);
-            *count += 1;
-            if *count > 3 {
-                is_valid = false;
-                break;
+    // inserted[i] goes right before word i; the last entry follows the final word.
+    let mut inserted = vec![String::new(); words.len() + 1];
+    let pool = insert_pool(style);
+    match style.insertion {
+        Insertion::None => {}
+        Insertion::Between => {
+            // A uniform arrangement of the inserted characters among the words (stars and bars).
+            let total = style.insert_count + words.len();
+            let stars: HashSet<usize> = sample(rng, total, style.insert_count).into_iter().collect();
+            let mut boundary = 0;
+            for slot in 0..total {
+                if stars.contains(&slot) {
+                    inserted[boundary].push_str(pool.choose(rng).unwrap());
+                } else {
+                    boundary += 1;
+                }
+            }
+        }
+        Insertion::Inside => {
+            let long: Vec<usize> = (0..words.len()).filter(|&i| grapheme_count(&words[i]) >= 2).collect();
+            if long.len() < style.insert_count {
+                return None;
+            }
+            for &i in long.choose_multiple(rng, style.insert_count) {
+                let mut characters = graphemes(&words[i]);
+                let position = rng.gen_range(1..characters.len());
+                let insert = pool.choose(rng).unwrap().clone();
+                let mut abbreviated = graphemes(&abbreviations[i]);
+                abbreviated.insert(position.min(abbreviated.len()), insert.clone());
+                abbreviations[i] = abbreviated.concat();
+                characters.insert(position, insert);
+                words[i] = characters.concat();
             }
         }
+    }
 
-        if is_valid {
-            return Ok(candidate_chars.into_iter().collect());
+    let separators = separator_pool(style);
+    let mut passphrase = String::new();
+    let mut abbreviated = String::new();
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 && style.use_separator {
+            let separator = match style.random_separator {
+                true => separators.choose(rng).unwrap().clone(),
+                false => nfc(&style.separator),
+            };
+            passphrase.push_str(&separator);
+            abbreviated.push_str(&separator);
         }
+        passphrase.push_str(&inserted[i]);
+        passphrase.push_str(word);
+        abbreviated.push_str(&inserted[i]);
+        abbreviated.push_str(&abbreviations[i]);
     }
+    passphrase.push_str(&inserted[words.len()]);
+    abbreviated.push_str(&inserted[words.len()]);
+    Some((passphrase, Some(abbreviated).filter(|_| style.abbreviate > 0)))
+}
 
-    Err("Failed to generate a valid password after multiple attempts.".to_string())
+/// The first `letters` characters of a word, or the whole word if it is that short; 0 keeps the whole word.
+pub fn abbreviate_word(word: &str, letters: usize) -> String {
+    match letters {
+        0 => word.to_string(),
+        _ => graphemes(word).into_iter().take(letters).collect(),
+    }
 }
 
-/// Generates a passphrase from a list of words, respecting length constraints.
-pub fn generate_word_password(
-    count: usize,
-    words: &[String],
-    use_separator: bool,
-    separator: &str,
-    use_uppercase: bool,
-) -> Result<String, String> {
-    if words.len() < count {
-        return Err(format!(
-            "Not enough words in words.txt (found {}, need at least {}).",
-            words.len(),
-            count
-        ));
+/// Returns how long the chosen words may be together, in the style's length
+/// unit: the passphrase bounds minus the room taken by separators and inserted
+/// characters. The upper bound is `None` when the length is not capped.
+pub fn word_length_bounds(count: usize, style: &WordStyle) -> Result<(usize, Option<usize>), String> {
+    if style.use_separator && style.random_separator && separator_pool(style).is_empty() {
+        return Err("Enter at least one separator character to draw from.".to_string());
+    }
+    if style.insertion != Insertion::None && style.insert_count > 0 && insert_pool(style).is_empty() {
+        return Err("Enter at least one character to insert.".to_string());
+    }
+    if style.insertion == Insertion::Inside && style.insert_count > count {
+        return Err(format!("Cannot insert into more than {} words.", count));
+    }
+    let cap = passphrase_length_cap(count, style);
+    if let Some(max) = cap {
+        if style.min_length > max {
+            return Err(format!("The minimum length {} is above the maximum of {}.", style.min_length, max));
+        }
     }
 
-    let max_length = match count {
-        3 => 30,
-        4 => 35,
-        5 => 40,
-        _ => return Err("Invalid word count.".to_string()),
+    // The widest characters decide what still fits under the cap, the narrowest what reaches the minimum.
+    let widths = |chars: &[String], widest: bool| {
+        let widths = chars.iter().map(|c| measure(c, style.length_unit));
+        if widest { widths.max() } else { widths.min() }.unwrap_or(0)
+    };
+    let extra = |widest: bool| {
+        let separator_width = match (style.use_separator, style.random_separator) {
+            (false, _) => 0,
+            (true, true) => widths(&separator_pool(style), widest),
+            (true, false) => measure(&style.separator, style.length_unit),
+        };
+        let inserted_width = match style.insertion {
+            Insertion::None => 0,
+            _ => style.insert_count * widths(&insert_pool(style), widest),
+        };
+        separator_width * count.saturating_sub(1) + inserted_width
     };
 
-    for _ in 0..100 {
-        let chosen_words: Vec<&String> = words.choose_multiple(&mut thread_rng(), count).collect();
-
-        let processed_words: Vec<String> = chosen_words
-            .iter()
-            .map(|word| {
-                let mut chars = word.chars();
-                match chars.next() {
-                    None => String::new(),
-                    Some(first) => {
-                        if use_uppercase {
-                            first.to_uppercase().to_string() + &word[first.len_utf8()..]
-                        } else {
-                            first.to_lowercase().to_string() + &word[first.len_utf8()..]
-                        }
-                    }
-                }
-            })
-            .collect();
+    let min = style.min_length.saturating_sub(extra(false));
+    let max = match cap {
+        None => None,
+        Some(max) => Some(max.checked_sub(extra(true)).ok_or_else(|| {
+            format!(
+                "The separators and inserted characters alone exceed the limit of {} {}.",
+                max,
+                style.length_unit.label()
+            )
+        })?),
+    };
+    Ok((min, max))
+}
+
+/// The distinct characters a random separator is drawn from.
+pub fn separator_pool(style: &WordStyle) -> Vec<String> {
+    distinct_graphemes(&style.separator)
+}
+
+/// The distinct characters random insertions are drawn from.
+pub fn insert_pool(style: &WordStyle) -> Vec<String> {
+    distinct_graphemes(&style.insert_chars)
+}
+
+/// Applies a fixed casing to the word at position `index` of a passphrase.
+///
+/// `WordCase::Random` has no single form; it gives the lowercase one here.
+pub fn apply_case(word: &str, case: WordCase, index: usize) -> String {
+    match case {
+        WordCase::Lower | WordCase::Random => word.to_lowercase(),
+        WordCase::Capitalized => capitalize_word(word, true),
+        WordCase::Upper => word.to_uppercase(),
+        WordCase::Camel if index == 0 => word.to_lowercase(),
+        WordCase::Camel => capitalize_word(&word.to_lowercase(), true),
+    }
+}
+
+/// The distinct forms random casing picks from: lowercase, Capitalized and UPPERCASE.
+pub fn random_case_forms(word: &str) -> Vec<String> {
+    let mut forms = vec![word.to_lowercase(), capitalize_word(&word.to_lowercase(), true), word.to_uppercase()];
+    forms.sort();
+    forms.dedup();
+    forms
+}
+
+/// The form of a word that decides its length under a casing, and which
+/// wordlist entries turn into the same output.
+pub fn case_key(word: &str, case: WordCase) -> String {
+    match case {
+        WordCase::Capitalized => capitalize_word(word, true),
+        WordCase::Upper => word.to_uppercase(),
+        WordCase::Lower | WordCase::Random | WordCase::Camel => word.to_lowercase(),
+    }
+}
+
+/// Returns the longest passphrase allowed, or `None` when the length is not capped.
+///
+/// Without an explicit maximum the cap grows with the word count: 30 characters
+/// for three words and five more for each further word.
+pub fn passphrase_length_cap(count: usize, style: &WordStyle) -> Option<usize> {
+    match (style.use_length_cap, style.max_length) {
+        (false, _) => None,
+        (true, 0) => Some(5 * count + 15),
+        (true, max) => Some(max),
+    }
+}
 
-        let separator_str = if use_separator { separator } else { "" };
-        let passphrase = processed_words.join(separator_str);
+/// Upper- or lowercases the first character of a word, leaving the rest untouched.
+pub fn capitalize_word(word: &str, use_uppercase: bool) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        None => String::new(),
+        Some(first) => {
+            if use_uppercase {
+                first.to_uppercase().to_string() + &word[first.len_utf8()..]
+            } else {
+                first.to_lowercase().to_string() + &word[first.len_utf8()..]
+            }
+        }
+    }
+}
 
-        if passphrase.len() <= max_length {
-            return Ok(passphrase);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_length_is_reported_before_class_maximums() {
+        let rules = RepetitionRules::default();
+        for length in [0, 3] {
+            let error = build_char_sampler(length, &CharacterClass::defaults(), &rules).err().unwrap();
+            assert_eq!(error, "Password length must be at least 4 to include the minimum of each class.");
         }
+
+        let mut classes = CharacterClass::defaults();
+        classes.iter_mut().for_each(|c| c.min = 0);
+        let error = build_char_sampler(0, &classes, &rules).err().unwrap();
+        assert_eq!(error, "Password length must be at least 1.");
     }
 
-    Err(format!(
-        "Could not generate a passphrase under {} characters. Check words.txt for long words.",
-        max_length
-    ))
+    #[test]
+    fn minimum_above_maximum_is_still_caught() {
+        let mut classes = CharacterClass::defaults();
+        classes[2].min = 3;
+        classes[2].max = Some(2);
+        let error = class_limits(&classes, 16).err().unwrap();
+        assert_eq!(error, "Class 'Numbers' has a minimum above its maximum.");
+    }
 }
@@ -0,0 +1,294 @@
+use rand::Rng;
+use std::collections::HashSet;
+
+/// The shortest PIN the generator makes.
+pub const MIN_PIN_LENGTH: usize = 4;
+/// The longest PIN the generator makes.
+pub const MAX_PIN_LENGTH: usize = 12;
+/// How many draws a PIN may take before generation gives up; far more than the rules ever need.
+const MAX_PIN_ATTEMPTS: usize = 10_000;
+
+/// The PINs people choose most often, and so the ones tried first.
+const COMMON_PINS: &[&str] = &[
+    "1234", "1111", "0000", "1212", "7777", "1004", "2000", "4444", "2222", "6969", "9999", "3333", "5555", "6666",
+    "1122", "1313", "8888", "4321", "2001", "1010", "2580", "0852", "1357", "2468", "1478", "1590", "0987", "5683",
+    "12345", "11111", "54321", "123456", "654321", "111111", "000000", "123123", "666666", "121212", "112233",
+    "789456", "159753", "147258", "123321", "696969", "7777777", "1234567", "12345678", "87654321", "11111111",
+    "147258369", "123456789", "987654321", "741852963", "1234567890", "0987654321",
+];
+
+/// Where each digit sits on a phone keypad, as (row, column); index is the digit.
+const KEYPAD: [(i8, i8); 10] = [(3, 1), (0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+
+/// Checks that a PIN length is within the supported range.
+pub fn check_pin_length(length: usize) -> Result<(), String> {
+    if (MIN_PIN_LENGTH..=MAX_PIN_LENGTH).contains(&length) {
+        Ok(())
+    } else {
+        Err(format!("PINs are between {} and {} digits long.", MIN_PIN_LENGTH, MAX_PIN_LENGTH))
+    }
+}
+
+/// Generates a random PIN of `length` digits that avoids common and patterned PINs.
+///
+/// Digits are drawn uniformly and the whole PIN is drawn again when it breaks a
+/// rule, so every allowed PIN is equally likely.
+pub fn generate_pin<R: Rng + ?Sized>(length: usize, rng: &mut R) -> Result<String, String> {
+    check_pin_length(length)?;
+    for _ in 0..MAX_PIN_ATTEMPTS {
+        let digits: Vec<u8> = (0..length).map(|_| rng.gen_range(0..10)).collect();
+        if pin_weakness(&digits).is_none() {
+            return Ok(digits.iter().map(|&d| char::from(b'0' + d)).collect());
+        }
+    }
+    Err("Could not find a PIN that avoids the common patterns.".to_string())
+}
+
+/// Says why a PIN is weak, or `None` if it breaks no rule.
+pub fn pin_weakness(digits: &[u8]) -> Option<&'static str> {
+    if let Some(reason) = local_weakness(digits) {
+        return Some(reason);
+    }
+    if is_common(digits) {
+        Some("is one of the most common PINs")
+    } else if smallest_period(digits) < digits.len() {
+        Some("repeats a shorter block of digits")
+    } else if digits.iter().eq(digits.iter().rev()) {
+        Some("reads the same backwards")
+    } else if is_date(digits) {
+        Some("looks like a date or a year")
+    } else {
+        None
+    }
+}
+
+/// The rules that look at a few neighbouring digits at a time: runs, repeats and keypad lines.
+fn local_weakness(digits: &[u8]) -> Option<&'static str> {
+    for window in digits.windows(3) {
+        if window[0] == window[1] && window[1] == window[2] {
+            return Some("repeats a digit three times in a row");
+        }
+        if is_keypad_line(window[0], window[1], window[2]) {
+            return Some("follows a straight line on the keypad");
+        }
+    }
+    if digits.windows(4).any(is_run) {
+        return Some("has four digits counting up or down");
+    }
+    None
+}
+
+/// Whether three keys lie next to each other in a straight line on a phone keypad, like 258 or 753.
+fn is_keypad_line(a: u8, b: u8, c: u8) -> bool {
+    let [(ar, ac), (br, bc), (cr, cc)] = [KEYPAD[a as usize], KEYPAD[b as usize], KEYPAD[c as usize]];
+    let step = (br - ar, bc - ac);
+    step != (0, 0) && step.0.abs() <= 1 && step.1.abs() <= 1 && step == (cr - br, cc - bc)
+}
+
+/// Whether four digits count up or down by one, like 3456 or 8765.
+fn is_run(window: &[u8]) -> bool {
+    let steps = || window.windows(2).map(|pair| pair[1] as i8 - pair[0] as i8);
+    steps().all(|step| step == 1) || steps().all(|step| step == -1)
+}
+
+/// Whether the PIN is on the list of most common PINs.
+fn is_common(digits: &[u8]) -> bool {
+    COMMON_PINS.iter().any(|pin| pin.bytes().map(|b| b - b'0').eq(digits.iter().copied()))
+}
+
+/// The length of the shortest block the PIN is made of, like 2 for 1212; the PIN's length if there is none.
+fn smallest_period(digits: &[u8]) -> usize {
+    let n = digits.len();
+    (1..n).find(|&p| n.is_multiple_of(p) && digits[p..] == digits[..n - p]).unwrap_or(n)
+}
+
+/// Reads a run of digits as a number.
+fn number(digits: &[u8]) -> u32 {
+    digits.iter().fold(0, |n, &d| n * 10 + d as u32)
+}
+
+/// Whether a day and month make a real date, allowing 29 February.
+fn is_day_month(day: u32, month: u32) -> bool {
+    let days = match month {
+        2 => 29,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+/// Whether four digits are a year people use in PINs: birth years and recent years.
+fn is_year(digits: &[u8]) -> bool {
+    (1900..=2099).contains(&number(digits))
+}
+
+/// Whether the whole PIN reads as a year or a date in a common order.
+///
+/// Four digits: a year, DDMM or MMDD. Six digits: DDMMYY, MMDDYY or YYMMDD.
+/// Eight digits: DDMMYYYY, MMDDYYYY or YYYYMMDD.
+fn is_date(digits: &[u8]) -> bool {
+    let pair = |i: usize| number(&digits[i..i + 2]);
+    match digits.len() {
+        4 => is_year(digits) || is_day_month(pair(0), pair(2)) || is_day_month(pair(2), pair(0)),
+        6 => is_day_month(pair(0), pair(2)) || is_day_month(pair(2), pair(0)) || is_day_month(pair(4), pair(2)),
+        8 => {
+            (is_year(&digits[4..]) && (is_day_month(pair(0), pair(2)) || is_day_month(pair(2), pair(0))))
+                || (is_year(&digits[..4]) && is_day_month(pair(6), pair(4)))
+        }
+        _ => false,
+    }
+}
+
+/// Counts the PINs of `length` digits that `generate_pin` can produce, returning
+/// that number together with the number it leaves out.
+///
+/// PINs that pass the neighbouring-digit rules are counted exactly by walking
+/// the last three digits; the few that other rules catch on top of those are
+/// listed and taken away.
+pub fn count_allowed_pins(length: usize) -> Result<(u64, u64), String> {
+    check_pin_length(length)?;
+
+    // counts[abc]: PINs so far ending in the digits a, b, c that break no neighbouring-digit rule.
+    let mut counts = vec![0u64; 1000];
+    for (abc, count) in counts.iter_mut().enumerate() {
+        let digits = [(abc / 100) as u8, (abc / 10 % 10) as u8, (abc % 10) as u8];
+        if local_weakness(&digits).is_none() {
+            *count = 1;
+        }
+    }
+    for _ in 3..length {
+        let mut next = vec![0u64; 1000];
+        for (abc, &count) in counts.iter().enumerate().filter(|(_, &count)| count > 0) {
+            let window = [(abc / 100) as u8, (abc / 10 % 10) as u8, (abc % 10) as u8];
+            for d in 0..10u8 {
+                if local_weakness(&[window[0], window[1], window[2], d]).is_none() {
+                    next[abc % 100 * 10 + d as usize] += count;
+                }
+            }
+        }
+        counts = next;
+    }
+    let locally_allowed: u64 = counts.iter().sum();
+
+    let allowed = locally_allowed - whole_pin_rejections(length);
+    Ok((allowed, 10u64.pow(length as u32) - allowed))
+}
+
+/// Writes a PIN stored as a number into `digits`, keeping leading zeros.
+fn to_digits(mut pin: u64, digits: &mut [u8]) {
+    for digit in digits.iter_mut().rev() {
+        *digit = (pin % 10) as u8;
+        pin /= 10;
+    }
+}
+
+/// Counts the PINs of `length` digits that pass the neighbouring-digit rules
+/// but break a whole-PIN rule: common PINs, dates, repeated blocks and mirrored PINs.
+fn whole_pin_rejections(length: usize) -> u64 {
+    let mut buffer = [0u8; MAX_PIN_LENGTH];
+    let digits = &mut buffer[..length];
+
+    // Common PINs and dates are few, but may coincide, so they are collected first.
+    let mut listed: HashSet<u64> =
+        COMMON_PINS.iter().filter(|pin| pin.len() == length).filter_map(|pin| pin.parse().ok()).collect();
+    let day_months = (1..=12).flat_map(|month| (1..=31).map(move |day| (day, month)));
+    for (day, month) in day_months.filter(|&(day, month)| is_day_month(day, month)) {
+        let (dm, md) = (day as u64 * 100 + month as u64, month as u64 * 100 + day as u64);
+        match length {
+            4 => listed.extend([dm, md]),
+            6 => listed.extend((0..100).flat_map(|yy| [dm * 100 + yy, md * 100 + yy, yy * 10_000 + md])),
+            8 => listed
+                .extend((1900..=2099).flat_map(|yyyy| [dm * 10_000 + yyyy, md * 10_000 + yyyy, yyyy * 10_000 + md])),
+            _ => {}
+        }
+    }
+    if length == 4 {
+        listed.extend(1900..=2099);
+    }
+    let mut rejected = 0;
+    for &pin in &listed {
+        to_digits(pin, digits);
+        rejected += local_weakness(digits).is_none() as u64;
+    }
+
+    // Repeated blocks, each counted once under its shortest block, then mirrored PINs that repeat no block.
+    for period in (1..length).filter(|&p| length.is_multiple_of(p)) {
+        let scale = 10u64.pow(period as u32);
+        for block in 0..scale {
+            let pin = (0..length / period).fold(0, |pin, _| pin * scale + block);
+            to_digits(pin, digits);
+            if smallest_period(digits) == period && local_weakness(digits).is_none() && !listed.contains(&pin) {
+                rejected += 1;
+            }
+        }
+    }
+    let half = length.div_ceil(2);
+    for front in 0..10u64.pow(half as u32) {
+        to_digits(front, &mut digits[..half]);
+        for i in half..length {
+            digits[i] = digits[length - 1 - i];
+        }
+        let pin = digits.iter().fold(0, |pin, &d| pin * 10 + d as u64);
+        if smallest_period(digits) == length && local_weakness(digits).is_none() && !listed.contains(&pin) {
+            rejected += 1;
+        }
+    }
+    rejected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(pin: &str) -> Vec<u8> {
+        pin.bytes().map(|b| b - b'0').collect()
+    }
+
+    #[test]
+    fn counts_match_brute_force() {
+        for length in 4..=6 {
+            let mut buffer = vec![0u8; length];
+            let (mut allowed, mut whole_pin) = (0, 0);
+            for pin in 0..10u64.pow(length as u32) {
+                to_digits(pin, &mut buffer);
+                match pin_weakness(&buffer) {
+                    None => allowed += 1,
+                    Some(_) if local_weakness(&buffer).is_none() => whole_pin += 1,
+                    Some(_) => {}
+                }
+            }
+            assert_eq!(whole_pin_rejections(length), whole_pin, "length {}", length);
+            assert_eq!(count_allowed_pins(length).unwrap(), (allowed, 10u64.pow(length as u32) - allowed));
+        }
+    }
+
+    #[test]
+    fn dates() {
+        for pin in ["1987", "2024", "2512", "1225", "0229", "251287", "122587", "871225", "25121987", "12251987"] {
+            assert!(is_date(&digits(pin)), "{}", pin);
+        }
+        for pin in ["1899", "2100", "3113", "0230", "3231", "311387", "19871313", "123", "1234567"] {
+            assert!(!is_date(&digits(pin)), "{}", pin);
+        }
+        assert!(is_date(&digits("19871225")));
+        assert!(!is_date(&digits("19872512")));
+    }
+
+    #[test]
+    fn keypad_lines() {
+        for [a, b, c] in [[2, 5, 8], [8, 5, 2], [1, 2, 3], [3, 2, 1], [7, 5, 3], [1, 5, 9], [5, 8, 0], [4, 5, 6]] {
+            assert!(is_keypad_line(a, b, c), "{}{}{}", a, b, c);
+        }
+        for [a, b, c] in [[1, 1, 1], [1, 3, 5], [1, 5, 8], [2, 4, 6], [7, 8, 0], [3, 6, 8], [0, 8, 6]] {
+            assert!(!is_keypad_line(a, b, c), "{}{}{}", a, b, c);
+        }
+    }
+
+    #[test]
+    fn periods() {
+        for (pin, period) in [("1212", 2), ("7777", 1), ("123123", 3), ("121212", 2), ("1231", 4), ("12121", 5)] {
+            assert_eq!(smallest_period(&digits(pin)), period, "{}", pin);
+        }
+    }
+}
@@ -0,0 +1,539 @@
+use crate::app::state::{CharacterClass, CharacterSets, RepetitionRules};
+use crate::app::unicode::graphemes;
+use std::collections::{BTreeSet, HashSet};
+
+/// The characters of the Apple `special` class. The space it also allows is
+/// left out, since many sign-up forms trim or reject it.
+const APPLE_SPECIAL: &str = "-~!@#$%^&*_+=`|(){}[:;\"'<>,.?]";
+
+/// Short reference of the supported syntaxes, shown in the GUI.
+pub const RULES_HELP: &str = "Apple passwordrules, e.g. \"minlength: 20; required: lower; required: [-_]; \
+                              allowed: digit\", or a HashiCorp Vault password policy with length = N and \
+                              rule \"charset\" blocks. max-consecutive above 1 is only approximated, by \
+                              limiting how often each character appears at all.";
+
+/// Character password settings translated from a service's published password rules.
+#[derive(Clone, Debug)]
+pub struct ImportedRules {
+    /// One class per requirement, followed by the classes that are only allowed.
+    pub classes: Vec<CharacterClass>,
+    pub min_length: usize,
+    pub max_length: Option<usize>,
+    /// The most times the same character may appear in a row, if limited.
+    pub max_consecutive: Option<usize>,
+    /// Where the settings are stricter than the rules as written.
+    pub notes: Vec<String>,
+}
+
+impl ImportedRules {
+    /// The length closest to `preferred` that the rules allow.
+    pub fn fit_length(&self, preferred: usize) -> usize {
+        preferred.max(self.min_length).min(self.max_length.unwrap_or(usize::MAX))
+    }
+
+    /// Checks that the rules allow a password of `length` characters.
+    pub fn check_length(&self, length: usize) -> Result<(), String> {
+        if self.fit_length(length) == length {
+            Ok(())
+        } else {
+            Err(format!("The password rules require a length of {}.", self.length_range()))
+        }
+    }
+
+    /// Tightens the repetition rules so no character appears more than `max_consecutive` times in a row.
+    ///
+    /// The samplers cannot limit runs directly, so a limit above one is met by
+    /// limiting how often each character appears at all.
+    pub fn apply_to_rules(&self, rules: &mut RepetitionRules) {
+        match self.max_consecutive {
+            Some(1) => rules.no_adjacent_repeats = true,
+            Some(max) => rules.max_occurrences = rules.max_occurrences.min(max),
+            None => {}
+        }
+    }
+
+    /// The allowed lengths, e.g. "20 to 64".
+    fn length_range(&self) -> String {
+        match self.max_length {
+            Some(max) if max == self.min_length => format!("exactly {}", max),
+            Some(max) if self.min_length == 0 => format!("at most {}", max),
+            Some(max) => format!("{} to {}", self.min_length, max),
+            None if self.min_length == 0 => "any".to_string(),
+            None => format!("at least {}", self.min_length),
+        }
+    }
+
+    /// A one-line description of the imported settings and any notes.
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} character classes, length: {}.", self.classes.len(), self.length_range());
+        for note in &self.notes {
+            text.push(' ');
+            text.push_str(note);
+        }
+        text
+    }
+}
+
+/// Parses password rules in either supported syntax, recognizing a Vault policy
+/// by a line that starts with `length` or `rule`.
+pub fn parse_password_rules(text: &str) -> Result<ImportedRules, String> {
+    let is_vault = text.lines().any(|line| {
+        let word: String = line.trim_start().chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+        word == "length" || word == "rule"
+    });
+    if is_vault {
+        parse_vault_policy(text)
+    } else {
+        parse_apple_rules(text)
+    }
+}
+
+/// Parses the Apple `passwordrules` syntax, such as
+/// `minlength: 20; required: lower; required: upper, digit; allowed: [-_]`.
+///
+/// Each `required` property becomes a class that must appear at least once; the
+/// classes listed together in one property count as one. Without any `required`
+/// or `allowed` property, all printable ASCII characters are allowed.
+pub fn parse_apple_rules(text: &str) -> Result<ImportedRules, String> {
+    let mut classes: Vec<CharacterClass> = Vec::new();
+    let mut allowed: Vec<CharacterClass> = Vec::new();
+    let mut min_length = 0;
+    let mut max_length: Option<usize> = None;
+    let mut max_consecutive: Option<usize> = None;
+    let mut notes = Vec::new();
+
+    for property in split_apple_properties(text)? {
+        let property = property.trim();
+        if property.is_empty() {
+            continue;
+        }
+        let (name, value) =
+            property.split_once(':').ok_or_else(|| format!("Expected 'name: value' in the rule '{}'.", property))?;
+        let name = name.trim().to_ascii_lowercase();
+        let value = value.trim();
+        let number = || value.parse::<usize>().map_err(|_| format!("'{}' must be a whole number.", name));
+        match name.as_str() {
+            "required" => {
+                let listed = parse_apple_classes(value, &mut notes)?;
+                let names: Vec<&str> = listed.iter().map(|(name, _)| name.as_str()).collect();
+                let chars: String = listed.iter().map(|(_, chars)| chars.as_str()).collect();
+                classes.push(CharacterClass::new(&names.join(" or "), &chars));
+            }
+            "allowed" => {
+                for (name, chars) in parse_apple_classes(value, &mut notes)? {
+                    let mut class = CharacterClass::new(&name, &chars);
+                    class.min = 0;
+                    allowed.push(class);
+                }
+            }
+            "minlength" => min_length = min_length.max(number()?),
+            "maxlength" => max_length = Some(max_length.unwrap_or(usize::MAX).min(number()?)),
+            "max-consecutive" => match number()? {
+                0 => return Err("'max-consecutive' must be at least 1.".to_string()),
+                max => max_consecutive = Some(max_consecutive.unwrap_or(usize::MAX).min(max)),
+            },
+            _ => return Err(format!("Unknown password rule '{}'.", name)),
+        }
+    }
+
+    if classes.is_empty() && allowed.is_empty() {
+        let (name, chars) = apple_class("ascii-printable", &mut notes)?;
+        let mut class = CharacterClass::new(&name, &chars);
+        class.min = 0;
+        allowed.push(class);
+    }
+    order_requirements(&mut classes);
+    for class in allowed {
+        if !classes.iter().any(|c| c.name == class.name) {
+            classes.push(class);
+        }
+    }
+    if max_length.is_some_and(|max| max < min_length) {
+        return Err("'maxlength' is shorter than 'minlength'.".to_string());
+    }
+    if let Some(max) = max_consecutive.filter(|&max| max > 1) {
+        notes.push(format!(
+            "max-consecutive: {} is approximated by using each character at most {} times in all, \
+             which is stricter than the rule.",
+            max, max
+        ));
+    }
+    notes.extend(overlap_notes(&classes));
+    Ok(ImportedRules { classes, min_length, max_length, max_consecutive, notes })
+}
+
+/// Splits Apple rules at the semicolons outside custom classes.
+fn split_apple_properties(text: &str) -> Result<Vec<String>, String> {
+    let mut properties = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => properties.push(String::new()),
+            '[' => {
+                let class = read_custom_class(&mut chars)?;
+                properties.last_mut().unwrap().push_str(&format!("[{}]", class));
+            }
+            c => properties.last_mut().unwrap().push(c),
+        }
+    }
+    Ok(properties)
+}
+
+/// Reads the characters of a custom class after its `[`, up to the closing `]`.
+/// A `]` right after the `[` is a member of the class.
+fn read_custom_class(chars: &mut std::str::Chars) -> Result<String, String> {
+    let mut class = String::new();
+    loop {
+        match chars.next() {
+            Some(']') if !class.is_empty() => return Ok(class),
+            Some(c) => class.push(c),
+            None => return Err("A custom character class is missing its closing ']'.".to_string()),
+        }
+    }
+}
+
+/// Parses a comma-separated list of Apple classes into names and characters.
+fn parse_apple_classes(value: &str, notes: &mut Vec<String>) -> Result<Vec<(String, String)>, String> {
+    let mut classes = Vec::new();
+    let mut chars = value.chars();
+    let mut name = String::new();
+    loop {
+        match chars.next() {
+            Some('[') => {
+                let custom = read_custom_class(&mut chars)?;
+                classes.push((format!("[{}]", custom), custom));
+            }
+            Some(c) if c == ',' || c.is_whitespace() => {
+                if !name.is_empty() {
+                    classes.push(apple_class(&std::mem::take(&mut name), notes)?);
+                }
+            }
+            Some(c) => name.push(c),
+            None => break,
+        }
+    }
+    if !name.is_empty() {
+        classes.push(apple_class(&name, notes)?);
+    }
+    if classes.is_empty() {
+        return Err("A 'required' or 'allowed' rule lists no characters.".to_string());
+    }
+    Ok(classes)
+}
+
+/// Returns the class name and characters of a named Apple class.
+fn apple_class(name: &str, notes: &mut Vec<String>) -> Result<(String, String), String> {
+    let sets = CharacterSets::default();
+    Ok(match name.to_ascii_lowercase().as_str() {
+        "lower" => ("Lowercase".to_string(), sets.lowercase),
+        "upper" => ("Uppercase".to_string(), sets.uppercase),
+        "digit" => ("Numbers".to_string(), sets.numbers),
+        "special" => ("Special".to_string(), APPLE_SPECIAL.to_string()),
+        "ascii-printable" | "unicode" => {
+            if name.eq_ignore_ascii_case("unicode") {
+                notes.push("'unicode' is drawn from printable ASCII only.".to_string());
+            }
+            ("ASCII".to_string(), format!("{}{}{}{}", sets.lowercase, sets.uppercase, sets.numbers, APPLE_SPECIAL))
+        }
+        _ => return Err(format!("Unknown character class '{}'; use upper, lower, digit, special, \
+                                 ascii-printable, unicode or [custom].", name)),
+    })
+}
+
+/// A token of the HCL subset used by Vault password policies.
+#[derive(Clone, Debug, PartialEq)]
+enum HclToken {
+    Ident(String),
+    Str(String),
+    Number(usize),
+    Equals,
+    Open,
+    Close,
+}
+
+/// Splits a Vault policy into tokens, skipping `#`, `//` and `/* */` comments.
+fn hcl_tokens(text: &str) -> Result<Vec<HclToken>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() || c == ',' => {}
+            '#' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = '\0';
+                loop {
+                    match chars.next() {
+                        Some('/') if previous == '*' => break,
+                        Some(c) => previous = c,
+                        None => return Err("A /* comment in the Vault policy is never closed.".to_string()),
+                    }
+                }
+            }
+            '=' => tokens.push(HclToken::Equals),
+            '{' => tokens.push(HclToken::Open),
+            '}' => tokens.push(HclToken::Close),
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => string.push('\n'),
+                            Some('t') => string.push('\t'),
+                            Some(c @ ('"' | '\\')) => string.push(c),
+                            _ => return Err("Unsupported escape in a Vault policy string.".to_string()),
+                        },
+                        Some(c) => string.push(c),
+                        None => return Err("A string in the Vault policy is never closed.".to_string()),
+                    }
+                }
+                tokens.push(HclToken::Str(string));
+            }
+            c if c.is_ascii_digit() => {
+                let mut digits = c.to_string();
+                while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(d);
+                }
+                let number = digits.parse().map_err(|_| format!("The number {} is too large.", digits))?;
+                tokens.push(HclToken::Number(number));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(c) = chars.next_if(|&c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                    ident.push(c);
+                }
+                tokens.push(HclToken::Ident(ident));
+            }
+            c => return Err(format!("Unexpected '{}' in the Vault policy.", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Reads the `= value` that follows a setting's name.
+fn hcl_value(tokens: &mut std::vec::IntoIter<HclToken>, key: &str) -> Result<HclToken, String> {
+    match (tokens.next(), tokens.next()) {
+        (Some(HclToken::Equals), Some(value)) => Ok(value),
+        _ => Err(format!("Expected '{} = value' in the Vault policy.", key)),
+    }
+}
+
+/// Parses a HashiCorp Vault password policy, such as
+/// `length = 20` followed by `rule "charset" { charset = "abc" min-chars = 1 }` blocks.
+///
+/// Passwords are drawn from all charsets together, with at least `min-chars`
+/// characters from each.
+pub fn parse_vault_policy(text: &str) -> Result<ImportedRules, String> {
+    let mut tokens = hcl_tokens(text)?.into_iter();
+    let mut length = None;
+    let mut classes: Vec<CharacterClass> = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            HclToken::Ident(key) if key == "length" => match hcl_value(&mut tokens, &key)? {
+                HclToken::Number(n) => length = Some(n),
+                _ => return Err("'length' must be a whole number.".to_string()),
+            },
+            HclToken::Ident(key) if key == "rule" => {
+                match tokens.next() {
+                    Some(HclToken::Str(kind)) if kind == "charset" => {}
+                    Some(HclToken::Str(kind)) => {
+                        return Err(format!("Vault rule '{}' is not supported; only charset rules are.", kind));
+                    }
+                    _ => return Err("Expected a rule type, as in rule \"charset\" { ... }.".to_string()),
+                }
+                if tokens.next() != Some(HclToken::Open) {
+                    return Err("Expected '{' after rule \"charset\".".to_string());
+                }
+                let mut charset = String::new();
+                let mut min = 0;
+                loop {
+                    match tokens.next() {
+                        Some(HclToken::Close) => break,
+                        Some(HclToken::Ident(key)) if key == "charset" => match hcl_value(&mut tokens, &key)? {
+                            HclToken::Str(chars) => charset = chars,
+                            _ => return Err("'charset' must be a quoted string.".to_string()),
+                        },
+                        Some(HclToken::Ident(key)) if key == "min-chars" => match hcl_value(&mut tokens, &key)? {
+                            HclToken::Number(n) => min = n,
+                            _ => return Err("'min-chars' must be a whole number.".to_string()),
+                        },
+                        Some(HclToken::Ident(key)) => {
+                            return Err(format!("Unknown setting '{}' in a charset rule.", key));
+                        }
+                        _ => return Err("A charset rule in the Vault policy is not closed with '}'.".to_string()),
+                    }
+                }
+                if charset.is_empty() {
+                    return Err("Every charset rule needs a non-empty charset.".to_string());
+                }
+                let mut class = CharacterClass::new(&charset_name(&charset, classes.len() + 1), &charset);
+                class.min = min;
+                classes.push(class);
+            }
+            HclToken::Ident(key) => return Err(format!("Unknown setting '{}' in the Vault policy.", key)),
+            _ => return Err("Expected 'length = N' or a rule block in the Vault policy.".to_string()),
+        }
+    }
+
+    let length = length.ok_or_else(|| "The Vault policy must set a length.".to_string())?;
+    if classes.is_empty() {
+        return Err("The Vault policy needs at least one charset rule.".to_string());
+    }
+    order_requirements(&mut classes);
+    let notes = overlap_notes(&classes);
+    Ok(ImportedRules { classes, min_length: length, max_length: Some(length), max_consecutive: None, notes })
+}
+
+/// Names a charset after the default set it matches, or by its position.
+fn charset_name(charset: &str, position: usize) -> String {
+    let sets = CharacterSets::default();
+    let letters = |s: &str| s.chars().collect::<BTreeSet<char>>();
+    [("Lowercase", sets.lowercase), ("Uppercase", sets.uppercase), ("Numbers", sets.numbers), ("Special", sets.special)]
+        .into_iter()
+        .find(|(_, set)| letters(set) == letters(charset))
+        .map(|(name, _)| name.to_string())
+        .unwrap_or_else(|| format!("Charset {}", position))
+}
+
+/// Moves the required classes with the fewest characters first, so that a
+/// requirement inside a broader one (`[abc]` and `lower`) keeps characters of its own.
+///
+/// A shared character then counts toward the smallest class only. That is stricter
+/// than Vault, which counts it toward every charset that lists it.
+fn order_requirements(classes: &mut [CharacterClass]) {
+    classes.sort_by_key(|class| (class.min == 0, graphemes(&class.chars).len()));
+}
+
+/// Notes required classes that share characters with an earlier class, since a
+/// shared character only counts toward the first class that lists it.
+fn overlap_notes(classes: &[CharacterClass]) -> Vec<String> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut notes = Vec::new();
+    for class in classes {
+        let chars = graphemes(&class.chars);
+        if class.min > 0 && chars.iter().any(|c| seen.contains(c)) {
+            notes.push(format!(
+                "'{}' shares characters with an earlier class; they count toward the earlier one only.",
+                class.name
+            ));
+        }
+        seen.extend(chars);
+    }
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::password::class_limits;
+
+    fn names(rules: &ImportedRules) -> Vec<&str> {
+        rules.classes.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn apple_directives() {
+        let rules = parse_password_rules(
+            "minlength: 12; maxlength: 40; required: lower; required: upper, digit; allowed: [-_]; max-consecutive: 3",
+        )
+        .unwrap();
+        assert_eq!(names(&rules), ["Lowercase", "Uppercase or Numbers", "[-_]"]);
+        assert_eq!(rules.classes.iter().map(|c| c.min).collect::<Vec<_>>(), [1, 1, 0]);
+        assert_eq!(rules.classes[1].chars.len(), 36);
+        assert_eq!((rules.min_length, rules.max_length, rules.max_consecutive), (12, Some(40), Some(3)));
+        assert_eq!(rules.fit_length(8), 12);
+        assert_eq!(rules.fit_length(64), 40);
+        assert!(rules.check_length(41).is_err());
+
+        let special = parse_password_rules("required: special").unwrap();
+        assert_eq!(special.classes[0].chars, APPLE_SPECIAL);
+    }
+
+    #[test]
+    fn apple_rules_without_classes_allow_printable_ascii() {
+        let rules = parse_password_rules("minlength: 8").unwrap();
+        assert_eq!(names(&rules), ["ASCII"]);
+        assert_eq!(rules.classes[0].min, 0);
+    }
+
+    #[test]
+    fn apple_rules_keep_the_strictest_limits() {
+        let rules = parse_password_rules("minlength: 8; minlength: 10; maxlength: 30; maxlength: 20").unwrap();
+        assert_eq!((rules.min_length, rules.max_length), (10, Some(20)));
+    }
+
+    #[test]
+    fn unknown_or_contradictory_rules_are_rejected() {
+        for (text, error) in [
+            ("minlength: 8; color: blue", "Unknown password rule 'color'."),
+            ("required: emoji", "Unknown character class 'emoji'"),
+            ("minlength: twelve", "'minlength' must be a whole number."),
+            ("minlength: 20; maxlength: 10", "'maxlength' is shorter than 'minlength'."),
+            ("max-consecutive: 0", "'max-consecutive' must be at least 1."),
+            ("required: [abc", "missing its closing ']'"),
+            ("required lower", "Expected 'name: value'"),
+            ("length = 8\nrule \"charset\" { charset = \"ab\" max-chars = 2 }", "Unknown setting 'max-chars'"),
+            ("length = 8\nrule \"disallowed\" { }", "'disallowed' is not supported"),
+            ("rule \"charset\" { charset = \"ab\" }", "must set a length"),
+            ("length = 8", "at least one charset rule"),
+        ] {
+            let result = parse_password_rules(text).err().unwrap_or_default();
+            assert!(result.contains(error), "{:?} gave {:?}", text, result);
+        }
+    }
+
+    #[test]
+    fn vault_policy() {
+        let policy = r#"
+            # A comment
+            length = 20
+            rule "charset" { charset = "abcdefghijklmnopqrstuvwxyz" min-chars = 1 }
+            rule "charset" { charset = "0123456789" min-chars = 2 } // another
+            rule "charset" { charset = "!@" }
+        "#;
+        let rules = parse_password_rules(policy).unwrap();
+        assert_eq!((rules.min_length, rules.max_length, rules.max_consecutive), (20, Some(20), None));
+        assert_eq!(names(&rules), ["Numbers", "Lowercase", "Charset 3"]);
+        assert_eq!(rules.classes.iter().map(|c| c.min).collect::<Vec<_>>(), [2, 1, 0]);
+    }
+
+    #[test]
+    fn shared_characters_go_to_the_smallest_class() {
+        // Vault would count an "a" toward both charsets; here it only counts toward [abc].
+        let policy = "length = 8\nrule \"charset\" { charset = \"abcdefghijklmnopqrstuvwxyz\" min-chars = 1 }\n\
+                      rule \"charset\" { charset = \"abc\" min-chars = 1 }";
+        let rules = parse_password_rules(policy).unwrap();
+        assert_eq!(names(&rules), ["Charset 2", "Lowercase"]);
+        assert!(rules.notes.iter().any(|note| note.contains("'Lowercase' shares characters")));
+        let limits = class_limits(&rules.classes, 8).unwrap();
+        assert_eq!(limits[0].chars, ["a", "b", "c"]);
+        assert_eq!(limits[1].chars.len(), 23);
+
+        let apple = parse_password_rules("required: lower; required: [abc]").unwrap();
+        assert_eq!(names(&apple), ["[abc]", "Lowercase"]);
+    }
+
+    #[test]
+    fn max_consecutive_maps_to_repetition_rules() {
+        let mut rules = RepetitionRules::default();
+        parse_password_rules("max-consecutive: 1").unwrap().apply_to_rules(&mut rules);
+        assert!(rules.no_adjacent_repeats);
+        assert_eq!(rules.max_occurrences, RepetitionRules::default().max_occurrences);
+
+        // Above 1 the limit applies to every use of a character, not just runs, and a note says so.
+        let imported = parse_password_rules("max-consecutive: 2").unwrap();
+        let mut rules = RepetitionRules::default();
+        imported.apply_to_rules(&mut rules);
+        assert!(!rules.no_adjacent_repeats);
+        assert_eq!(rules.max_occurrences, 2);
+        assert!(imported.notes.iter().any(|note| note.contains("approximated")));
+
+        // A looser limit never loosens the existing one.
+        let mut rules = RepetitionRules::default();
+        parse_password_rules("max-consecutive: 5").unwrap().apply_to_rules(&mut rules);
+        assert_eq!(rules.max_occurrences, 3);
+    }
+}
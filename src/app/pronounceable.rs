@@ -0,0 +1,211 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Index used for "before the start of the word" in a context.
+const BOUNDARY: usize = 26;
+/// Number of distinct symbols in a context position: 26 letters plus the boundary.
+const SYMBOLS: usize = 27;
+/// A successor is kept if it follows its context at least this often (in percent).
+const MIN_SUCCESSOR_PERCENT: usize = 1;
+
+/// A letter-level Markov model of which letters may follow each pair of letters.
+///
+/// The model only decides which letters are allowed next; the generator picks
+/// uniformly among them so that every step contributes a known number of bits.
+pub struct PronounceableModel {
+    /// successors[a * SYMBOLS + b]: letters allowed after the pair (a, b).
+    successors: Vec<Vec<u8>>,
+}
+
+impl PronounceableModel {
+    /// Learns letter transitions from the alphabetic ASCII words of a wordlist.
+    pub fn train(words: &[String]) -> Result<Self, String> {
+        let mut pair_counts = vec![[0usize; 26]; SYMBOLS * SYMBOLS];
+        let mut single_counts = vec![[0usize; 26]; SYMBOLS];
+        let mut trained = 0;
+
+        for word in words {
+            if !is_trainable(word) {
+                continue;
+            }
+            let (mut a, mut b) = (BOUNDARY, BOUNDARY);
+            for byte in word.bytes() {
+                let c = (byte.to_ascii_lowercase() - b'a') as usize;
+                pair_counts[a * SYMBOLS + b][c] += 1;
+                single_counts[b][c] += 1;
+                (a, b) = (b, c);
+            }
+            trained += 1;
+        }
+        if trained == 0 {
+            return Err("The wordlist has no alphabetic words to learn pronounceable patterns from.".to_string());
+        }
+
+        // Fall back to the last letter alone, then to any letter, so no context is a dead end.
+        let common = |counts: &[usize; 26]| -> Vec<u8> {
+            let total: usize = counts.iter().sum();
+            (0..26u8)
+                .filter(|&c| counts[c as usize] > 0 && counts[c as usize] * 100 >= total * MIN_SUCCESSOR_PERCENT)
+                .collect()
+        };
+        let successors = (0..SYMBOLS * SYMBOLS)
+            .map(|context| {
+                let pair = common(&pair_counts[context]);
+                if !pair.is_empty() {
+                    return pair;
+                }
+                let single = common(&single_counts[context % SYMBOLS]);
+                if !single.is_empty() {
+                    return single;
+                }
+                (0..26).collect()
+            })
+            .collect();
+
+        Ok(Self { successors })
+    }
+
+    /// Letters that may follow the context `(a, b)`.
+    pub fn allowed(&self, a: usize, b: usize) -> &[u8] {
+        &self.successors[a * SYMBOLS + b]
+    }
+
+    /// The context at the start of a password.
+    pub fn start() -> (usize, usize) {
+        (BOUNDARY, BOUNDARY)
+    }
+
+    /// Every context the model knows about, as `(a, b)` pairs.
+    pub fn contexts() -> impl Iterator<Item = (usize, usize)> {
+        (0..SYMBOLS).flat_map(|a| (0..SYMBOLS).map(move |b| (a, b)))
+    }
+
+    /// The index of a context, for use in per-context tables.
+    pub fn context_index(a: usize, b: usize) -> usize {
+        a * SYMBOLS + b
+    }
+}
+
+/// Draws lowercase, pronounceable passwords of one length.
+///
+/// The model is trained once when the sampler is built, so a batch of
+/// passwords and its entropy report all come from the same model.
+pub struct PronounceableSampler {
+    model: PronounceableModel,
+    length: usize,
+    /// Number of words the model learned from.
+    trained: usize,
+}
+
+impl PronounceableSampler {
+    /// Checks the length and trains the model on the wordlist.
+    pub fn new(length: usize, words: &[String]) -> Result<Self, String> {
+        if length == 0 {
+            return Err("Password length must be at least 1.".to_string());
+        }
+        let model = PronounceableModel::train(words)?;
+        let trained = words.iter().filter(|word| is_trainable(word)).count();
+        Ok(Self { model, length, trained })
+    }
+
+    /// The trained model.
+    pub fn model(&self) -> &PronounceableModel {
+        &self.model
+    }
+
+    /// Letters in each password.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Number of words the model learned from.
+    pub fn trained_words(&self) -> usize {
+        self.trained
+    }
+
+    /// Draws one password.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> String {
+        let (mut a, mut b) = PronounceableModel::start();
+        let mut password = String::with_capacity(self.length);
+        for _ in 0..self.length {
+            let c = *self.model.allowed(a, b).choose(rng).unwrap() as usize;
+            password.push((b'a' + c as u8) as char);
+            (a, b) = (b, c);
+        }
+        password
+    }
+}
+
+/// Whether a word is used to train the model: at least two ASCII letters and nothing else.
+fn is_trainable(word: &str) -> bool {
+    word.len() >= 2 && word.bytes().all(|b| b.is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::entropy::pronounceable_password_entropy;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|w| w.to_string()).collect()
+    }
+
+    /// The exact entropy of every walk of `length` letters, by listing them with their probabilities.
+    fn brute_force_bits(model: &PronounceableModel, context: (usize, usize), length: usize, p: f64) -> f64 {
+        if length == 0 {
+            return -p * p.log2();
+        }
+        let allowed = model.allowed(context.0, context.1);
+        let p = p / allowed.len() as f64;
+        allowed.iter().map(|&c| brute_force_bits(model, (context.1, c as usize), length - 1, p)).sum()
+    }
+
+    #[test]
+    fn passwords_have_the_requested_shape() {
+        let list = words(&["banana", "cabana", "bandana", "Canal", "it's", "x"]);
+        let sampler = PronounceableSampler::new(12, &list).unwrap();
+        assert_eq!(sampler.trained_words(), 4);
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..50 {
+            let password = sampler.sample(&mut rng);
+            assert_eq!(password.len(), 12);
+            let (mut a, mut b) = PronounceableModel::start();
+            for byte in password.bytes() {
+                assert!(byte.is_ascii_lowercase(), "{}", password);
+                let c = (byte - b'a') as usize;
+                assert!(sampler.model().allowed(a, b).contains(&(c as u8)), "{}", password);
+                (a, b) = (b, c);
+            }
+        }
+    }
+
+    #[test]
+    fn entropy_of_a_forced_walk() {
+        // Only the first letter is a choice: "ab" leads to "abab...", "ba" to "baba...".
+        let sampler = PronounceableSampler::new(6, &words(&["ab", "ba"])).unwrap();
+        assert_eq!(pronounceable_password_entropy(&sampler).bits, 1.0);
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            assert!(["ababab", "bababa"].contains(&sampler.sample(&mut rng).as_str()));
+        }
+    }
+
+    #[test]
+    fn entropy_matches_brute_force() {
+        let list = words(&["banana", "cabana", "bandana", "canal", "nab", "cab", "scan"]);
+        for length in 1..=6 {
+            let sampler = PronounceableSampler::new(length, &list).unwrap();
+            let expected = brute_force_bits(sampler.model(), PronounceableModel::start(), length, 1.0);
+            let bits = pronounceable_password_entropy(&sampler).bits;
+            assert!((bits - expected).abs() < 1e-9, "length {}: {} bits, expected {}", length, bits, expected);
+        }
+    }
+
+    #[test]
+    fn rejects_unusable_settings() {
+        assert!(PronounceableSampler::new(0, &words(&["banana"])).is_err());
+        assert!(PronounceableSampler::new(8, &words(&["it's", "a", "42"])).is_err());
+    }
+}
@@ -0,0 +1,10 @@
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+/// The random number generator used for real passwords: the operating system's CSPRNG.
+///
+/// This is the only generator the GUI can reach. The seeded one behind `--seed`
+/// is private to the command line in cli.rs.
+pub fn system_rng() -> Box<dyn RngCore> {
+    Box::new(OsRng)
+}
@@ -0,0 +1,1099 @@
+use crate::app::sampler::{binomial_table, choose_row, log2_biguint, pick_weighted, ClassLimits};
+use crate::app::state::RepetitionRules;
+use crate::app::unicode::single_char;
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Pow, Zero};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Sequences and keyboard walks of this many characters are forbidden.
+pub const SEQUENCE_LIMIT: usize = 3;
+
+/// The largest number of class-count combinations the sequence sampler tracks.
+const MAX_CLASS_STATES: usize = 4096;
+
+/// The most states the sequence sampler builds while tracking how often each character is used.
+const MAX_LEVEL_STATES: usize = 300_000;
+
+/// US keyboard rows, unshifted and shifted, used to detect keyboard walks.
+pub const KEYBOARD_ROWS: [&str; 8] = [
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+    "~!@#$%^&*()_+",
+    "QWERTYUIOP{}|",
+    "ASDFGHJKL:\"",
+    "ZXCVBNM<>?",
+];
+
+/// The direction of a step between two neighbouring characters.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Step {
+    None = 0,
+    Up = 1,
+    Down = 2,
+}
+
+/// Finds the keyboard row (shifted rows share the unshifted index) and column of a key.
+pub fn key_position(c: char) -> Option<(usize, usize)> {
+    KEYBOARD_ROWS
+        .iter()
+        .enumerate()
+        .find_map(|(row, keys)| keys.chars().position(|k| k == c).map(|col| (row % 4, col)))
+}
+
+/// Whether `b` follows `a` in alphabetical or numeric order, e.g. `a`->`b` or `3`->`2`.
+fn sequence_step(a: char, b: char) -> Step {
+    let same_kind = (a.is_ascii_digit() && b.is_ascii_digit()) || (a.is_ascii_alphabetic() && b.is_ascii_alphabetic());
+    if !same_kind {
+        return Step::None;
+    }
+    let (a, b) = (a.to_ascii_lowercase() as i32, b.to_ascii_lowercase() as i32);
+    match b - a {
+        1 => Step::Up,
+        -1 => Step::Down,
+        _ => Step::None,
+    }
+}
+
+/// Whether `b` is the key right next to `a` on the same keyboard row.
+fn keyboard_step(a: char, b: char) -> Step {
+    match (key_position(a), key_position(b)) {
+        (Some((row_a, col_a)), Some((row_b, col_b))) if row_a == row_b => match col_b as i32 - col_a as i32 {
+            1 => Step::Up,
+            -1 => Step::Down,
+            _ => Step::None,
+        },
+        _ => Step::None,
+    }
+}
+
+/// Applies a step test to two characters; only single code points can form runs.
+fn unit_step(step: fn(char, char) -> Step, a: &str, b: &str) -> Step {
+    match (single_char(a), single_char(b)) {
+        (Some(a), Some(b)) => step(a, b),
+        _ => Step::None,
+    }
+}
+
+/// Samples passwords that meet the class limits and the order rules: no
+/// doubled characters, no alphabetical or numeric runs and no keyboard walks.
+///
+/// Completions are counted exactly, position by position, over states made of
+/// the class counts so far, the previous character and the direction of the
+/// previous step, so every allowed password is drawn in proportion to its class
+/// weights. When the per-character limit can bind, the state also records how
+/// often each character has been used; characters no order rule can tell apart
+/// share one entry per class, so that stays small unless the sequence or
+/// keyboard rules single out many characters. Configurations whose combinations
+/// would not fit in memory are refused when the sampler is built, so every
+/// password is always drawn from an exact count.
+pub struct SequenceSampler {
+    kinds: Vec<Kind>,
+    limits: Vec<(usize, usize)>,
+    /// Largest count tracked per class: its maximum, or its minimum when nothing above that matters.
+    caps: Vec<usize>,
+    length: usize,
+    rules: RepetitionRules,
+    /// The per-character limit when uses are tracked in the state, or 0 when they are not.
+    levels: usize,
+    /// steps[last * kinds + next]: (sequence step, keyboard step) between two kinds.
+    steps: Vec<(Step, Step)>,
+    /// Reachable states, numbered in the order they are reached from the empty password.
+    states: Vec<State>,
+    /// moves[state]: every way to extend the state without breaking a rule.
+    moves: Vec<Vec<Move>>,
+    /// With uses tracked, each state belongs to one position, and layer_starts[t]
+    /// is the first state after `t` characters.
+    layer_starts: Vec<usize>,
+    /// Weighted completions of each state: indexed by state when uses are tracked,
+    /// by position and state otherwise.
+    completions: Vec<BigUint>,
+}
+
+/// Pool characters the rules cannot tell apart: a single character that can
+/// take part in a sequence or keyboard walk, or the other characters of a class.
+struct Kind {
+    chars: Vec<String>,
+    class: usize,
+    weight: u32,
+}
+
+/// A partly built password, as far as the rules can see it.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct State {
+    /// Without tracked uses, the characters placed from each class, capped where
+    /// more no longer matters. With them, for each kind, how many of its characters
+    /// were used once, twice and so on; a single-character kind holds its own count.
+    counts: Vec<u16>,
+    /// The kind of the previous character, or `kinds.len()` when no rule looks at it.
+    last: usize,
+    /// How often the previous character has been used, when uses are tracked.
+    level: usize,
+    sequence: Step,
+    keyboard: Step,
+}
+
+/// One way to extend a state: any of `choices` characters of `kind` used `level` times so far.
+struct Move {
+    kind: u32,
+    level: u32,
+    choices: u32,
+    next: u32,
+}
+
+impl SequenceSampler {
+    /// Builds a sampler for the given classes and rules.
+    pub fn new(classes: &[ClassLimits], length: usize, rules: &RepetitionRules) -> Result<Self, String> {
+        let kinds = group_kinds(classes, rules);
+        let n = kinds.len();
+        let steps = (0..n * n)
+            .map(|i| match (&kinds[i / n].chars[..], &kinds[i % n].chars[..]) {
+                ([a], [b]) => (
+                    if rules.no_sequences { unit_step(sequence_step, a, b) } else { Step::None },
+                    if rules.no_keyboard_walks { unit_step(keyboard_step, a, b) } else { Step::None },
+                ),
+                _ => (Step::None, Step::None),
+            })
+            .collect();
+
+        let limits: Vec<(usize, usize)> = classes.iter().map(|c| (c.min, c.max.min(length))).collect();
+        let caps: Vec<usize> = limits.iter().map(|&(min, max)| if max < length { max } else { min }).collect();
+        let mut sampler = Self {
+            kinds,
+            limits,
+            caps,
+            length,
+            rules: rules.clone(),
+            levels: 0,
+            steps,
+            states: Vec::new(),
+            moves: Vec::new(),
+            layer_starts: Vec::new(),
+            completions: Vec::new(),
+        };
+
+        // The limit can only bind if some class may hold more characters than it allows.
+        let limit_binds = sampler.limits.iter().any(|&(_, max)| max > rules.max_occurrences);
+        if limit_binds {
+            sampler.levels = rules.max_occurrences;
+            let fits = sampler.estimated_level_states(rules.max_occurrences) <= MAX_LEVEL_STATES as f64;
+            if !fits || !sampler.explore(MAX_LEVEL_STATES) {
+                return Err(format!(
+                    "The limit of {} uses per character cannot be counted together with the sequence and \
+                     keyboard-walk rules over {} characters. Allow each character {} uses, draw from fewer \
+                     characters, or turn those rules off.",
+                    rules.max_occurrences,
+                    sampler.pool_size(),
+                    length
+                ));
+            }
+            sampler.count_completions();
+            return Ok(sampler);
+        }
+
+        let class_states = sampler.caps.iter().fold(1usize, |states, cap| states.saturating_mul(cap + 1));
+        if class_states > MAX_CLASS_STATES {
+            return Err("The class minimums and maximums have too many combinations to count together with \
+                        the sequence rules. Remove some class maximums or turn the sequence rules off."
+                .to_string());
+        }
+        sampler.explore(usize::MAX);
+        sampler.count_completions();
+        Ok(sampler)
+    }
+
+    /// An upper bound on the combinations of per-character uses, to skip tracking them when it cannot fit.
+    fn estimated_level_states(&self, levels: usize) -> f64 {
+        let mut total = vec![0.0f64; self.length + 1];
+        total[0] = 1.0;
+        for kind in &self.kinds {
+            // ways[u]: how many ways the kind's characters can share `u` uses, up to the limit each.
+            let mut ways = vec![vec![0.0f64; kind.chars.len() + 1]; self.length + 1];
+            ways[0][0] = 1.0;
+            for level in 1..=levels {
+                for u in (0..=self.length).rev() {
+                    for used in (0..=kind.chars.len()).rev() {
+                        let mut extra = 1;
+                        while extra <= used && extra * level <= u {
+                            ways[u][used] += ways[u - extra * level][used - extra];
+                            extra += 1;
+                        }
+                    }
+                }
+            }
+            let per_use: Vec<f64> = ways.iter().map(|row| row.iter().sum()).collect();
+            total = (0..=self.length).map(|u| (0..=u).map(|a| total[a] * per_use[u - a]).sum()).collect();
+        }
+        total.iter().sum()
+    }
+
+    /// How many characters of `kind` have been used `level` times in `counts`.
+    fn at_level(&self, counts: &[u16], kind: usize, level: usize) -> usize {
+        let (offset, size) = (self.offset(kind), self.kinds[kind].chars.len());
+        if size == 1 {
+            return (counts[offset] as usize == level) as usize;
+        }
+        match level {
+            0 => size - counts[offset..offset + self.levels].iter().map(|&c| c as usize).sum::<usize>(),
+            _ => counts[offset + level - 1] as usize,
+        }
+    }
+
+    /// Where a kind's counts start in a state with tracked uses.
+    fn offset(&self, kind: usize) -> usize {
+        self.kinds[..kind].iter().map(|k| if k.chars.len() == 1 { 1 } else { self.levels }).sum()
+    }
+
+    /// Characters placed from class `class` so far.
+    fn class_count(&self, counts: &[u16], class: usize) -> usize {
+        if self.levels == 0 {
+            return counts[class] as usize;
+        }
+        (0..self.kinds.len())
+            .filter(|&k| self.kinds[k].class == class)
+            .map(|k| (1..=self.levels).map(|level| level * self.at_level(counts, k, level)).sum::<usize>())
+            .sum()
+    }
+
+    /// Characters placed so far in a state with tracked uses.
+    fn position(&self, state: &State) -> usize {
+        (0..self.limits.len()).map(|class| self.class_count(&state.counts, class)).sum()
+    }
+
+    /// The state after appending a character of `kind` used `level` times so far,
+    /// with the number of characters that lead there, or `None` if that breaks a rule.
+    fn advance(&self, state: &State, kind: usize, level: usize) -> Option<(State, usize)> {
+        let class = self.kinds[kind].class;
+        let mut counts = state.counts.clone();
+        let available = if self.levels > 0 {
+            let available = self.at_level(&counts, kind, level);
+            if available == 0 || self.class_count(&counts, class) >= self.limits[class].1 {
+                return None;
+            }
+            let offset = self.offset(kind);
+            if self.kinds[kind].chars.len() == 1 {
+                counts[offset] += 1;
+            } else {
+                if level > 0 {
+                    counts[offset + level - 1] -= 1;
+                }
+                counts[offset + level] += 1;
+            }
+            available
+        } else {
+            let count = counts[class] as usize;
+            if count < self.caps[class] {
+                counts[class] += 1;
+            } else if self.limits[class].1 < self.length {
+                return None;
+            }
+            self.kinds[kind].chars.len()
+        };
+        let repeats = self.rules.no_adjacent_repeats && state.last == kind && state.level == level;
+        let choices = available - repeats as usize;
+        if choices == 0 {
+            return None;
+        }
+
+        let n = self.kinds.len();
+        let (sequence, keyboard) =
+            if state.last == n { (Step::None, Step::None) } else { self.steps[state.last * n + kind] };
+        // A run of SEQUENCE_LIMIT characters is SEQUENCE_LIMIT - 1 steps in the same direction.
+        if (sequence != Step::None && sequence == state.sequence)
+            || (keyboard != Step::None && keyboard == state.keyboard)
+        {
+            return None;
+        }
+        // The previous character only matters for the order rules, or for doubled
+        // characters while it can still be used again.
+        let level = if self.levels > 0 { level + 1 } else { 0 };
+        let watched = self.kinds[kind].chars.len() == 1
+            || (self.rules.no_adjacent_repeats && (self.levels == 0 || level < self.levels));
+        let (last, level) = if watched { (kind, level) } else { (n, 0) };
+        Some((State { counts, last, level, sequence, keyboard }, choices))
+    }
+
+    fn accepts(&self, state: &State) -> bool {
+        self.limits.iter().enumerate().all(|(class, &(min, _))| self.class_count(&state.counts, class) >= min)
+    }
+
+    /// Finds every state reachable from the empty password and the moves between
+    /// them. Gives up once there are more than `limit` states.
+    fn explore(&mut self, limit: usize) -> bool {
+        let width = if self.levels > 0 { self.offset(self.kinds.len()) } else { self.limits.len() };
+        let start = State {
+            counts: vec![0; width],
+            last: self.kinds.len(),
+            level: 0,
+            sequence: Step::None,
+            keyboard: Step::None,
+        };
+        let mut states = vec![start.clone()];
+        let mut index: HashMap<State, usize> = HashMap::from([(start, 0)]);
+        let mut moves: Vec<Vec<Move>> = Vec::new();
+        while moves.len() < states.len() {
+            let state = states[moves.len()].clone();
+            if self.levels > 0 && self.position(&state) == self.length {
+                moves.push(Vec::new());
+                continue;
+            }
+            let mut here = Vec::new();
+            for kind in 0..self.kinds.len() {
+                for level in 0..self.levels.max(1) {
+                    let Some((target, choices)) = self.advance(&state, kind, level) else {
+                        continue;
+                    };
+                    let next = *index.entry(target.clone()).or_insert_with(|| {
+                        states.push(target);
+                        states.len() - 1
+                    });
+                    let (kind, level, choices, next) = (kind as u32, level as u32, choices as u32, next as u32);
+                    here.push(Move { kind, level, choices, next });
+                }
+            }
+            if states.len() > limit {
+                return false;
+            }
+            // Moves with the same factor are summed before multiplying.
+            here.sort_by_key(|m| self.factor(m));
+            moves.push(here);
+        }
+
+        if self.levels > 0 {
+            // States are reached one position at a time, so each position is a contiguous range.
+            let mut layer_starts = vec![0; self.length + 2];
+            for (id, state) in states.iter().enumerate() {
+                layer_starts[self.position(state) + 1] = id + 1;
+            }
+            for t in 1..layer_starts.len() {
+                layer_starts[t] = layer_starts[t].max(layer_starts[t - 1]);
+            }
+            self.layer_starts = layer_starts;
+        }
+        self.states = states;
+        self.moves = moves;
+        true
+    }
+
+    /// The states that can be reached after `t` characters.
+    fn layer(&self, t: usize) -> std::ops::Range<usize> {
+        if self.levels > 0 {
+            self.layer_starts[t]..self.layer_starts[t + 1]
+        } else {
+            0..self.states.len()
+        }
+    }
+
+    /// Where the completions of `state` after `t` characters are kept.
+    fn slot(&self, t: usize, state: usize) -> usize {
+        if self.levels > 0 {
+            state
+        } else {
+            t * self.states.len() + state
+        }
+    }
+
+    /// The weight a move adds: its class weight times the characters it stands for.
+    fn factor(&self, m: &Move) -> u64 {
+        self.kinds[m.kind as usize].weight as u64 * m.choices as u64
+    }
+
+    /// Counts the weighted completions of every state, from the last position back.
+    fn count_completions(&mut self) {
+        let size = if self.levels > 0 { self.states.len() } else { (self.length + 1) * self.states.len() };
+        let mut completions = vec![BigUint::zero(); size];
+        for id in self.layer(self.length) {
+            if self.accepts(&self.states[id]) {
+                completions[self.slot(self.length, id)] = BigUint::one();
+            }
+        }
+        for t in (0..self.length).rev() {
+            for id in self.layer(t) {
+                let mut total = BigUint::zero();
+                let mut group = BigUint::zero();
+                let mut factor = 0;
+                for m in &self.moves[id] {
+                    if self.factor(m) != factor {
+                        total += &group * factor;
+                        group.set_zero();
+                        factor = self.factor(m);
+                    }
+                    group += &completions[self.slot(t + 1, m.next as usize)];
+                }
+                total += group * factor;
+                completions[self.slot(t, id)] = total;
+            }
+        }
+        self.completions = completions;
+    }
+
+    /// The total weight of the passwords the automaton allows.
+    fn total(&self) -> &BigUint {
+        &self.completions[self.slot(0, 0)]
+    }
+
+    /// The number of distinct characters across all classes.
+    pub fn pool_size(&self) -> usize {
+        self.kinds.iter().map(|k| k.chars.len()).sum()
+    }
+
+    /// Whether no password satisfies the classes and rules.
+    pub fn is_empty(&self) -> bool {
+        self.total().is_zero()
+    }
+
+    /// Shannon entropy in bits of the passwords this sampler draws.
+    pub fn entropy_bits(&self) -> f64 {
+        let total = self.total();
+        if total.is_zero() {
+            return 0.0;
+        }
+        let log_total = log2_biguint(total);
+        if self.kinds.iter().all(|k| k.weight == 1) {
+            return log_total;
+        }
+
+        // Subtract the expected log2 weight of the drawn characters, following the
+        // weighted number of ways to reach each state forwards.
+        let mut reach = vec![BigUint::zero(); self.completions.len()];
+        reach[self.slot(0, 0)] = BigUint::one();
+        let mut expected_log_weight = 0.0;
+        for t in 0..self.length {
+            for id in self.layer(t) {
+                let here = reach[self.slot(t, id)].clone();
+                if here.is_zero() {
+                    continue;
+                }
+                for m in &self.moves[id] {
+                    let next = self.slot(t + 1, m.next as usize);
+                    if self.completions[next].is_zero() {
+                        continue;
+                    }
+                    let flow = &here * self.factor(m);
+                    let weight = self.kinds[m.kind as usize].weight;
+                    if weight > 1 {
+                        let share = (log2_biguint(&(&flow * &self.completions[next])) - log_total).exp2();
+                        expected_log_weight += share * (weight as f64).log2();
+                    }
+                    reach[next] += flow;
+                }
+            }
+        }
+        log_total - expected_log_weight
+    }
+
+    /// Draws one password, or an error if none satisfies every rule.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<String, String> {
+        if self.is_empty() {
+            return Err("No password satisfies the class limits and sequence rules.".to_string());
+        }
+        Ok(self.walk(rng).concat())
+    }
+
+    /// Walks the automaton from the empty password, picking each move in proportion
+    /// to the weighted completions it leads to.
+    fn walk<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<&str> {
+        let mut uses: Vec<Vec<usize>> = self.kinds.iter().map(|k| vec![0; k.chars.len()]).collect();
+        let mut previous: Option<(usize, usize)> = None;
+        let mut password = Vec::with_capacity(self.length);
+        let mut state = 0;
+        for t in 0..self.length {
+            let candidates: Vec<(usize, BigUint)> = self.moves[state]
+                .iter()
+                .enumerate()
+                .map(|(i, m)| (i, &self.completions[self.slot(t + 1, m.next as usize)] * self.factor(m)))
+                .filter(|(_, weight)| !weight.is_zero())
+                .collect();
+            let m = &self.moves[state][pick_weighted(rng, &candidates)];
+
+            // Every character the move stands for leads to the same state, so pick one evenly.
+            let (kind, level) = (m.kind as usize, m.level as usize);
+            let options: Vec<usize> = (0..self.kinds[kind].chars.len())
+                .filter(|&i| self.levels == 0 || uses[kind][i] == level)
+                .filter(|&i| !(self.rules.no_adjacent_repeats && previous == Some((kind, i))))
+                .collect();
+            let i = *options.choose(rng).unwrap();
+            uses[kind][i] += 1;
+            previous = Some((kind, i));
+            password.push(self.kinds[kind].chars[i].as_str());
+            state = m.next as usize;
+        }
+        password
+    }
+}
+
+/// A count split by total uses `n` and runs `k`: entry `[n][k]`, stored times `k!`
+/// so that runs from different characters combine with binomial coefficients.
+type Runs = Vec<Vec<BigInt>>;
+
+/// Samples passwords with no character twice in a row that meet the class limits
+/// and the per-character limit, when no other order rule applies.
+///
+/// Counting position by position would have to remember how often every character
+/// has been used, so counts go through runs instead. By inclusion and exclusion,
+/// the arrangements of a fixed multiset with no doubles are the sum, over ways to
+/// glue each character's `n` copies into `k` runs, of the sign `(-1)^(n - k)` and
+/// the number of orders of all the runs. The run counts combine character by
+/// character and class by class like exponential generating functions, so the
+/// totals stay exact without tracking any one character. A draw first picks how
+/// often each character appears, in proportion to its weighted arrangements, then
+/// one of those arrangements uniformly.
+pub struct SpacedSampler {
+    classes: Vec<ClassLimits>,
+    length: usize,
+    max_repeats: usize,
+    binomials: Vec<Vec<BigInt>>,
+    /// runs[a][i]: `i` distinct characters of class `a`, each used at least once.
+    runs: Vec<Vec<Runs>>,
+    /// fills[a]: any characters of class `a`, zero outside its limits.
+    fills: Vec<Runs>,
+    /// suffixes[a]: the fills of class `a` and every later class combined.
+    suffixes: Vec<Runs>,
+}
+
+impl SpacedSampler {
+    /// Builds a sampler for the given classes.
+    pub fn new(classes: Vec<ClassLimits>, length: usize, max_repeats: usize) -> Self {
+        let max_repeats = max_repeats.min(length);
+        let binomials: Vec<Vec<BigInt>> =
+            binomial_table(length).into_iter().map(|row| row.into_iter().map(BigInt::from).collect()).collect();
+
+        let mut runs = Vec::with_capacity(classes.len());
+        let mut fills = Vec::with_capacity(classes.len());
+        for class in &classes {
+            // One character used n times in k runs: C(n - 1, k - 1) ways, with sign (-1)^(n - k).
+            let mut single = empty_runs(length);
+            let weight = BigInt::from(class.weight);
+            for n in 1..=max_repeats {
+                for k in 1..=n {
+                    let ways = Pow::pow(&weight, n) * &binomials[n - 1][k - 1];
+                    single[n][k] = if (n - k) % 2 == 0 { ways } else { -ways };
+                }
+            }
+            let mut class_runs = vec![unit_runs(length)];
+            for _ in 0..class.chars.len().min(length) {
+                let next = join(class_runs.last().unwrap(), &single, &binomials);
+                class_runs.push(next);
+            }
+
+            let choose = choose_row(class.chars.len(), length);
+            let mut fill = empty_runs(length);
+            for n in class.min..=class.max.min(length) {
+                for (i, ways) in class_runs.iter().enumerate() {
+                    for k in 0..=n {
+                        fill[n][k] += BigInt::from(choose[i].clone()) * &ways[n][k];
+                    }
+                }
+            }
+            runs.push(class_runs);
+            fills.push(fill);
+        }
+
+        let mut suffixes = vec![unit_runs(length)];
+        for fill in fills.iter().rev() {
+            let next = join(fill, suffixes.last().unwrap(), &binomials);
+            suffixes.push(next);
+        }
+        suffixes.reverse();
+        Self { classes, length, max_repeats, binomials, runs, fills, suffixes }
+    }
+
+    /// The total weight of all valid passwords; the number of them when every weight is 1.
+    fn count(&self) -> BigUint {
+        arrangements(&self.suffixes[0][self.length])
+    }
+
+    /// Whether no password satisfies the classes and rules.
+    pub fn is_empty(&self) -> bool {
+        self.count().is_zero()
+    }
+
+    /// The number of distinct characters across all classes.
+    pub fn pool_size(&self) -> usize {
+        self.classes.iter().map(|c| c.chars.len()).sum()
+    }
+
+    /// Exact Shannon entropy in bits of the passwords this sampler draws.
+    pub fn entropy_bits(&self) -> f64 {
+        let total = self.count();
+        if total.is_zero() {
+            return 0.0;
+        }
+        let mut bits = log2_biguint(&total);
+        if self.classes.iter().all(|c| c.weight == 1) {
+            return bits;
+        }
+
+        // H = log2(total) - sum over classes of E[positions filled] * log2(weight).
+        let mut prefix = unit_runs(self.length);
+        for (a, class) in self.classes.iter().enumerate() {
+            if class.weight > 1 {
+                let others = join(&prefix, &self.suffixes[a + 1], &self.binomials);
+                let filled: BigUint = (1..=self.length)
+                    .map(|n| arrangements(&self.join_all(&[&self.fills[a][n], &others[self.length - n]])) * n)
+                    .sum();
+                if !filled.is_zero() {
+                    let expected = (log2_biguint(&filled) - log2_biguint(&total)).exp2();
+                    bits -= expected * (class.weight as f64).log2();
+                }
+            }
+            prefix = join(&prefix, &self.fills[a], &self.binomials);
+        }
+        bits
+    }
+
+    /// Draws one password, or an error if none satisfies every rule.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<String, String> {
+        if self.is_empty() {
+            return Err("No password satisfies the class limits and sequence rules.".to_string());
+        }
+
+        // Decide how often each character appears, class by class, keeping the runs chosen so far in `placed`.
+        let mut placed = unit_runs(self.length).swap_remove(0);
+        let mut letters: Vec<(&str, usize)> = Vec::new();
+        let mut left = self.length;
+        for (a, class) in self.classes.iter().enumerate() {
+            let candidates: Vec<(usize, BigUint)> = (0..=left)
+                .map(|n| (n, self.weigh(&[&placed, &self.fills[a][n], &self.suffixes[a + 1][left - n]])))
+                .filter(|(_, weight)| !weight.is_zero())
+                .collect();
+            let mut n = pick_weighted(rng, &candidates);
+            let rest = &self.suffixes[a + 1][left - n];
+            left -= n;
+
+            let choose = choose_row(class.chars.len(), n);
+            let candidates: Vec<(usize, BigUint)> = (0..choose.len())
+                .map(|i| (i, self.weigh(&[&placed, &self.runs[a][i][n], rest]) * &choose[i]))
+                .filter(|(_, weight)| !weight.is_zero())
+                .collect();
+            let used = pick_weighted(rng, &candidates);
+            for (j, c) in class.chars.choose_multiple(rng, used).enumerate() {
+                let others = &self.runs[a][used - j - 1];
+                let candidates: Vec<(usize, BigUint)> = (1..=self.max_repeats.min(n))
+                    .map(|uses| (uses, self.weigh(&[&placed, &self.runs[a][1][uses], &others[n - uses], rest])))
+                    .filter(|(_, weight)| !weight.is_zero())
+                    .collect();
+                let uses = pick_weighted(rng, &candidates);
+                placed = self.join_all(&[&placed, &self.runs[a][1][uses]]);
+                letters.push((c.as_str(), uses));
+                n -= uses;
+            }
+        }
+        Ok(self.arrange(rng, letters).concat())
+    }
+
+    /// Orders the chosen characters uniformly among the arrangements with no doubles.
+    fn arrange<'a, R: Rng + ?Sized>(&self, rng: &mut R, mut letters: Vec<(&'a str, usize)>) -> Vec<&'a str> {
+        let mut password = Vec::with_capacity(self.length);
+        let mut previous = None;
+        for _ in 0..self.length {
+            // Characters with as many uses left lead to as many completions, so pick a use count first.
+            let candidates: Vec<(usize, BigUint)> = (1..=self.max_repeats)
+                .map(|uses| {
+                    let choices = (0..letters.len()).filter(|&i| letters[i].1 == uses && previous != Some(i)).count();
+                    if choices == 0 {
+                        return (uses, BigUint::zero());
+                    }
+                    let i = letters.iter().position(|l| l.1 == uses).unwrap();
+                    let rest = letters.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, l)| l.1);
+                    (uses, self.not_starting_with(rest, uses - 1) * choices)
+                })
+                .filter(|(_, weight)| !weight.is_zero())
+                .collect();
+            let uses = pick_weighted(rng, &candidates);
+            let choices: Vec<usize> =
+                (0..letters.len()).filter(|&i| letters[i].1 == uses && previous != Some(i)).collect();
+            let i = *choices.choose(rng).unwrap();
+            letters[i].1 -= 1;
+            password.push(letters[i].0);
+            previous = Some(i);
+        }
+        password
+    }
+
+    /// Arrangements with no doubles of the characters with `others` uses left and one
+    /// more with `uses` left, that do not start with that one.
+    fn not_starting_with(&self, others: impl Iterator<Item = usize> + Clone, uses: usize) -> BigUint {
+        // Those starting with it are the arrangements of one use fewer that do not start with it.
+        let mut total = BigInt::zero();
+        for j in 0..=uses {
+            let ways = self.unweighted_arrangements(others.clone().chain([uses - j]));
+            total += if j % 2 == 0 { ways } else { -ways };
+        }
+        total.to_biguint().unwrap_or_default()
+    }
+
+    /// Arrangements with no doubles of characters used the given numbers of times.
+    fn unweighted_arrangements(&self, uses: impl Iterator<Item = usize>) -> BigInt {
+        let mut runs = unit_runs(self.length).swap_remove(0);
+        for n in uses.filter(|&n| n > 0) {
+            let single: Vec<BigInt> = (0..=self.length)
+                .map(|k| match k {
+                    k if k == 0 || k > n => BigInt::zero(),
+                    k if (n - k) % 2 == 0 => self.binomials[n - 1][k - 1].clone(),
+                    k => -self.binomials[n - 1][k - 1].clone(),
+                })
+                .collect();
+            runs = self.join_all(&[&runs, &single]);
+        }
+        runs.iter().sum()
+    }
+
+    /// Combines run counts of fixed uses, placing all their runs in any order.
+    fn join_all(&self, parts: &[&Vec<BigInt>]) -> Vec<BigInt> {
+        let mut result = parts[0].clone();
+        for part in &parts[1..] {
+            let mut next = vec![BigInt::zero(); self.length + 1];
+            for (i, x) in result.iter().enumerate().filter(|(_, x)| !x.is_zero()) {
+                for (j, y) in part.iter().enumerate().take(self.length + 1 - i).filter(|(_, y)| !y.is_zero()) {
+                    next[i + j] += &self.binomials[i + j][i] * x * y;
+                }
+            }
+            result = next;
+        }
+        result
+    }
+
+    /// The weighted arrangements the combined parts stand for.
+    fn weigh(&self, parts: &[&Vec<BigInt>]) -> BigUint {
+        arrangements(&self.join_all(parts))
+    }
+}
+
+/// Run counts that are all zero.
+fn empty_runs(length: usize) -> Runs {
+    vec![vec![BigInt::zero(); length + 1]; length + 1]
+}
+
+/// The run counts of nothing at all: one way, with no uses and no runs.
+fn unit_runs(length: usize) -> Runs {
+    let mut runs = empty_runs(length);
+    runs[0][0] = BigInt::one();
+    runs
+}
+
+/// Combines two run counts, adding their uses and placing their runs in any order among each other.
+fn join(a: &Runs, b: &Runs, binomials: &[Vec<BigInt>]) -> Runs {
+    let length = a.len() - 1;
+    let mut result = empty_runs(length);
+    for n1 in 0..=length {
+        for n2 in 0..=length - n1 {
+            for k1 in (0..=n1).filter(|&k1| !a[n1][k1].is_zero()) {
+                for k2 in (0..=n2).filter(|&k2| !b[n2][k2].is_zero()) {
+                    result[n1 + n2][k1 + k2] += &binomials[k1 + k2][k1] * &a[n1][k1] * &b[n2][k2];
+                }
+            }
+        }
+    }
+    result
+}
+
+/// The arrangements counted by run counts of fixed uses: the sum over every number of runs.
+fn arrangements(runs: &[BigInt]) -> BigUint {
+    runs.iter().sum::<BigInt>().to_biguint().unwrap_or_default()
+}
+
+/// Splits the pool into kinds: every character that can form a sequence or
+/// keyboard step with another pool character stands alone, and the rest are
+/// grouped by class.
+fn group_kinds(classes: &[ClassLimits], rules: &RepetitionRules) -> Vec<Kind> {
+    let pool: Vec<&String> = classes.iter().flat_map(|c| &c.chars).collect();
+    let related = |a: &str| {
+        pool.iter().any(|b| {
+            (rules.no_sequences && (unit_step(sequence_step, a, b) != Step::None))
+                || (rules.no_keyboard_walks && (unit_step(keyboard_step, a, b) != Step::None))
+        })
+    };
+
+    let mut kinds = Vec::new();
+    for (class, limits) in classes.iter().enumerate() {
+        let weight = limits.weight;
+        let (single, plain): (Vec<&String>, Vec<&String>) = limits.chars.iter().partition(|c| related(c));
+        kinds.extend(single.into_iter().map(|c| Kind { chars: vec![c.clone()], class, weight }));
+        if !plain.is_empty() {
+            kinds.push(Kind { chars: plain.into_iter().cloned().collect(), class, weight });
+        }
+    }
+    kinds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::password::build_char_sampler;
+    use crate::app::state::CharacterClass;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+/// Whether a password, given as its characters, breaks any of the repetition and sequence rules.
+    fn breaks_rules(password: &[&str], rules: &RepetitionRules) -> bool {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        if password.iter().any(|c| {
+            let count = counts.entry(*c).or_insert(0);
+            *count += 1;
+            *count > rules.max_occurrences
+        }) {
+            return true;
+        }
+    
+        let run_of = |step: fn(char, char) -> Step| {
+            password.windows(SEQUENCE_LIMIT).any(|window| {
+                let first = unit_step(step, window[0], window[1]);
+                first != Step::None && window.windows(2).all(|pair| unit_step(step, pair[0], pair[1]) == first)
+            })
+        };
+        (rules.no_adjacent_repeats && password.windows(2).any(|pair| pair[0] == pair[1]))
+            || (rules.no_sequences && run_of(sequence_step))
+            || (rules.no_keyboard_walks && run_of(keyboard_step))
+    }
+
+    fn classes(min: usize) -> Vec<ClassLimits> {
+        let limits = |chars: &str, weight| ClassLimits {
+            chars: chars.chars().map(String::from).collect(),
+            min,
+            max: usize::MAX,
+            weight,
+        };
+        vec![limits("abcds", 1), limits("123", 1)]
+    }
+
+    /// Every password of `length` over the classes that meets the class limits and the rules.
+    fn brute_force(classes: &[ClassLimits], length: usize, rules: &RepetitionRules) -> usize {
+        let pool: Vec<(usize, &str)> =
+            classes.iter().enumerate().flat_map(|(a, c)| c.chars.iter().map(move |s| (a, s.as_str()))).collect();
+        let mut count = 0;
+        for mut i in 0..pool.len().pow(length as u32) {
+            let mut password = Vec::new();
+            for _ in 0..length {
+                password.push(pool[i % pool.len()]);
+                i /= pool.len();
+            }
+            let units: Vec<&str> = password.iter().map(|&(_, s)| s).collect();
+            let meets_limits = classes.iter().enumerate().all(|(a, c)| {
+                (c.min..=c.max).contains(&password.iter().filter(|&&(b, _)| a == b).count())
+            });
+            if meets_limits && !breaks_rules(&units, rules) {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn counts_match_brute_force() {
+        let length = 4;
+        for flags in 0..8 {
+            for max_occurrences in 1..=length {
+                let rules = RepetitionRules {
+                    max_occurrences,
+                    no_adjacent_repeats: flags & 1 != 0,
+                    no_sequences: flags & 2 != 0,
+                    no_keyboard_walks: flags & 4 != 0,
+                };
+                let classes = classes(1);
+                let sampler = SequenceSampler::new(&classes, length, &rules).unwrap();
+                assert_eq!(*sampler.total(), BigUint::from(brute_force(&classes, length, &rules)), "{:?}", rules);
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_entropy_matches_brute_force() {
+        let mut classes = classes(0);
+        classes[1].weight = 3;
+        let rules = RepetitionRules {
+            max_occurrences: 2,
+            no_adjacent_repeats: true,
+            no_sequences: true,
+            no_keyboard_walks: false,
+        };
+        let sampler = SequenceSampler::new(&classes, 3, &rules).unwrap();
+
+        let pool: Vec<(f64, &str)> =
+            classes.iter().flat_map(|c| c.chars.iter().map(|s| (c.weight as f64, s.as_str()))).collect();
+        let mut weights = Vec::new();
+        for (wa, a) in &pool {
+            for (wb, b) in &pool {
+                for (wc, c) in &pool {
+                    if !breaks_rules(&[a, b, c], &rules) {
+                        weights.push(wa * wb * wc);
+                    }
+                }
+            }
+        }
+        let total: f64 = weights.iter().sum();
+        let expected: f64 = weights.iter().map(|w| -(w / total) * (w / total).log2()).sum();
+        assert!((sampler.entropy_bits() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn samples_follow_every_rule() {
+        let rules = RepetitionRules {
+            max_occurrences: 2,
+            no_adjacent_repeats: true,
+            no_sequences: true,
+            no_keyboard_walks: true,
+        };
+        let sampler = SequenceSampler::new(&classes(2), 12, &rules).unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..200 {
+            let password = sampler.sample(&mut rng).unwrap();
+            let units: Vec<&str> = password.split("").filter(|s| !s.is_empty()).collect();
+            assert_eq!(units.len(), 12);
+            assert!(!breaks_rules(&units, &rules), "{}", password);
+        }
+    }
+
+    #[test]
+    fn tight_occurrence_limit_always_succeeds() {
+        // Every digit exactly twice and never twice in a row: rare enough that drawing
+        // and rejecting would fail.
+        let digits = ClassLimits { chars: (0..10).map(|d| d.to_string()).collect(), min: 0, max: 20, weight: 1 };
+        let rules = RepetitionRules {
+            max_occurrences: 2,
+            no_adjacent_repeats: true,
+            no_sequences: false,
+            no_keyboard_walks: false,
+        };
+        let sampler = SequenceSampler::new(&[digits], 20, &rules).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let password = sampler.sample(&mut rng).unwrap();
+            let units: Vec<&str> = password.split("").filter(|s| !s.is_empty()).collect();
+            assert!(!breaks_rules(&units, &rules), "{}", password);
+        }
+    }
+
+    #[test]
+    fn default_pool_with_sequence_rules() {
+        let mut rules = RepetitionRules {
+            no_adjacent_repeats: true,
+            no_sequences: true,
+            no_keyboard_walks: true,
+            ..RepetitionRules::default()
+        };
+        // Every default character can take part in a run or walk, so a binding limit is refused up front.
+        let error = build_char_sampler(16, &CharacterClass::defaults(), &rules).err().unwrap();
+        assert!(error.contains("cannot be counted together"), "{}", error);
+
+        rules.max_occurrences = 16;
+        let sampler = build_char_sampler(16, &CharacterClass::defaults(), &rules).unwrap();
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..50 {
+            let password = sampler.sample(&mut rng).unwrap();
+            let units: Vec<&str> = password.split("").filter(|s| !s.is_empty()).collect();
+            assert_eq!(units.len(), 16);
+            assert!(!breaks_rules(&units, &rules), "{}", password);
+        }
+    }
+
+    #[test]
+    fn limit_that_cannot_bind_is_not_tracked() {
+        // No class may hold more than two characters, so a limit of two never matters.
+        let mut classes = classes(0);
+        classes.iter_mut().for_each(|c| c.max = 2);
+        let rules = RepetitionRules {
+            max_occurrences: 2,
+            no_adjacent_repeats: false,
+            no_sequences: true,
+            no_keyboard_walks: true,
+        };
+        let sampler = SequenceSampler::new(&classes, 4, &rules).unwrap();
+        assert_eq!(sampler.levels, 0);
+        assert_eq!(*sampler.total(), BigUint::from(brute_force(&classes, 4, &rules)));
+    }
+
+    #[test]
+    fn spaced_counts_match_brute_force() {
+        let rules = |max_occurrences| RepetitionRules {
+            max_occurrences,
+            no_adjacent_repeats: true,
+            no_sequences: false,
+            no_keyboard_walks: false,
+        };
+        for length in 1..=6 {
+            for max_occurrences in 1..=3 {
+                for min in 0..=1 {
+                    let mut classes = classes(min);
+                    classes[1].max = 2;
+                    let sampler = SpacedSampler::new(classes.clone(), length, max_occurrences);
+                    let expected = brute_force(&classes, length, &rules(max_occurrences));
+                    assert_eq!(sampler.count(), BigUint::from(expected), "{} {} {}", length, max_occurrences, min);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spaced_weighted_entropy_matches_brute_force() {
+        let mut classes = classes(0);
+        classes[1].weight = 3;
+        let rules = RepetitionRules {
+            max_occurrences: 1,
+            no_adjacent_repeats: true,
+            no_sequences: false,
+            no_keyboard_walks: false,
+        };
+        let sampler = SpacedSampler::new(classes.clone(), 3, 1);
+
+        let pool: Vec<(f64, &str)> =
+            classes.iter().flat_map(|c| c.chars.iter().map(|s| (c.weight as f64, s.as_str()))).collect();
+        let mut weights = Vec::new();
+        for (wa, a) in &pool {
+            for (wb, b) in &pool {
+                for (wc, c) in &pool {
+                    if !breaks_rules(&[a, b, c], &rules) {
+                        weights.push(wa * wb * wc);
+                    }
+                }
+            }
+        }
+        let total: f64 = weights.iter().sum();
+        let expected: f64 = weights.iter().map(|w| -(w / total) * (w / total).log2()).sum();
+        assert!((sampler.entropy_bits() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn spaced_samples_are_uniform() {
+        // "aab" and its kin: 3 characters, each at most twice, no doubles, length 4.
+        let class = ClassLimits { chars: vec!["a".into(), "b".into(), "c".into()], min: 0, max: 4, weight: 1 };
+        let rules = RepetitionRules {
+            max_occurrences: 2,
+            no_adjacent_repeats: true,
+            no_sequences: false,
+            no_keyboard_walks: false,
+        };
+        let sampler = SpacedSampler::new(vec![class.clone()], 4, 2);
+        let valid = brute_force(&[class], 4, &rules);
+        assert_eq!(sampler.count(), BigUint::from(valid));
+
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut rng = StdRng::seed_from_u64(9);
+        let draws = 200 * valid;
+        for _ in 0..draws {
+            *seen.entry(sampler.sample(&mut rng).unwrap()).or_insert(0) += 1;
+        }
+        assert_eq!(seen.len(), valid);
+        for (password, &count) in &seen {
+            let units: Vec<&str> = password.split("").filter(|s| !s.is_empty()).collect();
+            assert!(!breaks_rules(&units, &rules), "{}", password);
+            assert!((120..=280).contains(&count), "{} drawn {} times", password, count);
+        }
+    }
+
+    #[test]
+    fn default_pool_without_doubles() {
+        let rules = RepetitionRules { no_adjacent_repeats: true, ..RepetitionRules::default() };
+        let sampler = build_char_sampler(24, &CharacterClass::defaults(), &rules).unwrap();
+        let mut rng = StdRng::seed_from_u64(13);
+        for _ in 0..20 {
+            let password = sampler.sample(&mut rng).unwrap();
+            let units: Vec<&str> = password.split("").filter(|s| !s.is_empty()).collect();
+            assert_eq!(units.len(), 24);
+            assert!(!breaks_rules(&units, &rules), "{}", password);
+        }
+    }
+}
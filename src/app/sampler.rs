@@ -0,0 +1,437 @@
+use num_bigint::{BigUint, RandBigInt};
+use num_traits::{One, Pow, ToPrimitive, Zero};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// One character class as seen by the sampler.
+#[derive(Clone, Debug)]
+pub struct ClassLimits {
+    /// Distinct characters (grapheme clusters) that belong to this class and no earlier one.
+    pub chars: Vec<String>,
+    /// The fewest positions this class must fill.
+    pub min: usize,
+    /// The most positions this class may fill.
+    pub max: usize,
+    /// Relative likelihood of each character in this class.
+    pub weight: u32,
+}
+
+/// Counts and samples strings of a fixed length built from disjoint character
+/// classes, where each class fills between its `min` and `max` positions and no
+/// character appears more than `max_repeats` times.
+///
+/// Every valid string is drawn with probability proportional to the product of
+/// its characters' weights, so with all weights at 1 sampling is exactly uniform.
+/// Counts are exact, so sampling always succeeds when a valid string exists.
+pub struct ClassSampler {
+    classes: Vec<ClassLimits>,
+    length: usize,
+    max_repeats: usize,
+    /// arrangements[s][l]: strings of length `l` over `s` symbols within the repeat limit.
+    arrangements: Vec<Vec<BigUint>>,
+    /// fills[a][l]: weighted strings class `a` can place in `l` positions, zero outside its limits.
+    fills: Vec<Vec<BigUint>>,
+    /// layers[a][t]: weighted ways for the first `a` classes to fill `t` positions.
+    layers: Vec<Vec<BigUint>>,
+    binomials: Vec<Vec<BigUint>>,
+}
+
+impl ClassSampler {
+    /// Builds a sampler for the given classes.
+    pub fn new(classes: Vec<ClassLimits>, length: usize, max_repeats: usize) -> Self {
+        let binomials = binomial_table(length);
+        let largest_class = classes.iter().map(|c| c.chars.len()).max().unwrap_or(0);
+        let arrangements = arrangement_table(largest_class, length, max_repeats, &binomials);
+
+        let fills: Vec<Vec<BigUint>> = classes
+            .iter()
+            .map(|class| {
+                let weight = BigUint::from(class.weight);
+                (0..=length)
+                    .map(|l| {
+                        if l < class.min || l > class.max {
+                            BigUint::zero()
+                        } else {
+                            &arrangements[class.chars.len()][l] * Pow::pow(&weight, l)
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let layers = forward_layers(fills.iter(), length, &binomials);
+        Self { classes, length, max_repeats, arrangements, fills, layers, binomials }
+    }
+
+    /// The total weight of all valid strings; the number of them when every weight is 1.
+    pub fn count(&self) -> &BigUint {
+        &self.layers[self.classes.len()][self.length]
+    }
+
+    /// Whether no string satisfies the classes and repeat limit.
+    pub fn is_empty(&self) -> bool {
+        self.count().is_zero()
+    }
+
+    /// The number of distinct characters across all classes.
+    pub fn pool_size(&self) -> usize {
+        self.classes.iter().map(|c| c.chars.len()).sum()
+    }
+
+    /// Exact Shannon entropy in bits of the strings this sampler draws.
+    pub fn entropy_bits(&self) -> f64 {
+        let total = self.count();
+        if total.is_zero() {
+            return 0.0;
+        }
+        let mut bits = log2_biguint(total);
+        if self.classes.iter().all(|c| c.weight == 1) {
+            return bits;
+        }
+
+        // H = log2(total) - sum over classes of E[positions filled] * log2(weight).
+        for (a, class) in self.classes.iter().enumerate().filter(|(_, c)| c.weight > 1) {
+            let others = forward_layers(
+                self.fills.iter().enumerate().filter(|(b, _)| *b != a).map(|(_, f)| f),
+                self.length,
+                &self.binomials,
+            );
+            let rest = others.last().unwrap();
+            let filled: BigUint = (1..=self.length)
+                .map(|l| &rest[self.length - l] * &self.binomials[self.length][l] * &self.fills[a][l] * l)
+                .sum();
+            if !filled.is_zero() {
+                let expected = (log2_biguint(&filled) - log2_biguint(total)).exp2();
+                bits -= expected * (class.weight as f64).log2();
+            }
+        }
+        bits
+    }
+
+    /// Draws one string, or `None` if there are none.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        // Walk the layers backwards to decide how many positions each class fills.
+        let mut lengths = vec![0; self.classes.len()];
+        let mut t = self.length;
+        for a in (0..self.classes.len()).rev() {
+            let candidates: Vec<(usize, BigUint)> = (0..=t)
+                .map(|l| (l, &self.layers[a][t - l] * &self.binomials[t][l] * &self.fills[a][l]))
+                .filter(|(_, weight)| !weight.is_zero())
+                .collect();
+            let l = pick_weighted(rng, &candidates);
+            lengths[a] = l;
+            t -= l;
+        }
+
+        // Spread the classes over the positions, then fill each class's positions.
+        let mut slots: Vec<usize> =
+            lengths.iter().enumerate().flat_map(|(a, &l)| std::iter::repeat_n(a, l)).collect();
+        slots.shuffle(rng);
+        let mut fills: Vec<std::vec::IntoIter<&str>> = self
+            .classes
+            .iter()
+            .zip(&lengths)
+            .map(|(class, &l)| self.sample_class(rng, &class.chars, l).into_iter())
+            .collect();
+        Some(slots.into_iter().map(|a| fills[a].next().unwrap()).collect())
+    }
+
+    /// Draws a uniform string of `length` over `chars` within the repeat limit.
+    fn sample_class<'a, R: Rng + ?Sized>(&self, rng: &mut R, chars: &'a [String], length: usize) -> Vec<&'a str> {
+        let mut result = Vec::with_capacity(length);
+        let mut remaining = length;
+        for (i, c) in chars.iter().enumerate() {
+            let rest = &self.arrangements[chars.len() - i - 1];
+            let candidates: Vec<(usize, BigUint)> = (0..=self.max_repeats.min(remaining))
+                .map(|k| (k, &self.binomials[remaining][k] * &rest[remaining - k]))
+                .filter(|(_, weight)| !weight.is_zero())
+                .collect();
+            let k = pick_weighted(rng, &candidates);
+            result.extend(std::iter::repeat_n(c.as_str(), k));
+            remaining -= k;
+        }
+        result.shuffle(rng);
+        result
+    }
+}
+
+/// Combines classes one at a time: layer `a` counts the ways the first `a`
+/// classes fill each number of positions, including where those positions go.
+fn forward_layers<'a>(
+    fills: impl Iterator<Item = &'a Vec<BigUint>>,
+    length: usize,
+    binomials: &[Vec<BigUint>],
+) -> Vec<Vec<BigUint>> {
+    let mut layer = vec![BigUint::zero(); length + 1];
+    layer[0] = BigUint::one();
+    let mut layers = vec![layer];
+    for fill in fills {
+        let previous = layers.last().unwrap();
+        let mut next = vec![BigUint::zero(); length + 1];
+        for (t, count) in previous.iter().enumerate().filter(|(_, c)| !c.is_zero()) {
+            for (l, ways) in fill.iter().enumerate().take(length - t + 1).filter(|(_, w)| !w.is_zero()) {
+                next[t + l] += count * &binomials[t + l][l] * ways;
+            }
+        }
+        layers.push(next);
+    }
+    layers
+}
+
+/// Picks one candidate with probability proportional to its weight.
+pub fn pick_weighted<R: Rng + ?Sized, T: Copy>(rng: &mut R, candidates: &[(T, BigUint)]) -> T {
+    let total: BigUint = candidates.iter().map(|(_, w)| w).sum();
+    let mut roll = rng.gen_biguint_below(&total);
+    for (value, weight) in candidates {
+        if roll < *weight {
+            return *value;
+        }
+        roll -= weight;
+    }
+    unreachable!("roll is always below the total weight")
+}
+
+/// Pascal's triangle up to `n`.
+pub fn binomial_table(n: usize) -> Vec<Vec<BigUint>> {
+    let mut table: Vec<Vec<BigUint>> = Vec::with_capacity(n + 1);
+    for i in 0..=n {
+        let mut row = vec![BigUint::one(); i + 1];
+        for j in 1..i {
+            row[j] = &table[i - 1][j - 1] + &table[i - 1][j];
+        }
+        table.push(row);
+    }
+    table
+}
+
+/// For every alphabet size up to `symbols`, the number of strings of each length
+/// up to `length` in which no symbol appears more than `max_repeats` times.
+fn arrangement_table(symbols: usize, length: usize, max_repeats: usize, binomials: &[Vec<BigUint>]) -> Vec<Vec<BigUint>> {
+    let mut row = vec![BigUint::zero(); length + 1];
+    row[0] = BigUint::one();
+    let mut table = vec![row];
+    for s in 1..=symbols {
+        let previous = &table[s - 1];
+        let next: Vec<BigUint> = (0..=length)
+            .map(|l| (0..=max_repeats.min(l)).map(|j| &binomials[l][j] * &previous[l - j]).sum())
+            .collect();
+        table.push(next);
+    }
+    table
+}
+
+/// Returns log2 of a large count, or 0 when it is zero.
+pub fn log2_biguint(n: &BigUint) -> f64 {
+    let bits = n.bits();
+    if bits <= 64 {
+        return n.to_u64().map_or(0.0, |v| if v == 0 { 0.0 } else { (v as f64).log2() });
+    }
+    let shift = bits - 64;
+    let top = (n >> shift).to_u64().unwrap_or(u64::MAX);
+    (top as f64).log2() + shift as f64
+}
+
+/// Picks `count` distinct wordlist entries whose lengths add up to between
+/// `min` and `max`, uniformly among every such choice.
+///
+/// Entries are grouped by length. A table counts the ways to take `j` entries
+/// with total length `t` from the first groups; a backward walk then decides how
+/// many entries each group gives, and those are picked uniformly within it.
+pub struct WordLengthSampler<'a> {
+    groups: Vec<(usize, Vec<&'a String>)>,
+    count: usize,
+    min: usize,
+    max: usize,
+    /// choose[g][c]: ways to take `c` entries from group `g`.
+    choose: Vec<Vec<BigUint>>,
+    /// layers[g][j][t]: ways to take `j` entries of total length `t` from the first `g` groups.
+    layers: Vec<Vec<Vec<BigUint>>>,
+}
+
+impl<'a> WordLengthSampler<'a> {
+    /// Builds a sampler over `(length, entry)` pairs.
+    pub fn new(entries: Vec<(usize, &'a String)>, count: usize, min: usize, max: usize) -> Self {
+        let mut by_length: std::collections::BTreeMap<usize, Vec<&'a String>> = std::collections::BTreeMap::new();
+        for (length, entry) in entries.into_iter().filter(|(length, _)| *length <= max) {
+            by_length.entry(length).or_default().push(entry);
+        }
+        let groups: Vec<(usize, Vec<&'a String>)> = by_length.into_iter().collect();
+        let choose: Vec<Vec<BigUint>> = groups.iter().map(|(_, group)| choose_row(group.len(), count)).collect();
+
+        let mut layer = vec![vec![BigUint::zero(); max + 1]; count + 1];
+        layer[0][0] = BigUint::one();
+        let mut layers = vec![layer];
+        for (g, (length, group)) in groups.iter().enumerate() {
+            let previous = layers.last().unwrap();
+            let mut next = vec![vec![BigUint::zero(); max + 1]; count + 1];
+            for j in 0..=count {
+                for t in (0..=max).filter(|&t| !previous[j][t].is_zero()) {
+                    for c in 0..=group.len().min(count - j) {
+                        if t + c * length > max {
+                            break;
+                        }
+                        next[j + c][t + c * length] += &previous[j][t] * &choose[g][c];
+                    }
+                }
+            }
+            layers.push(next);
+        }
+        Self { groups, count, min, max, choose, layers }
+    }
+
+    /// Draws the entries in a random order, or `None` if no choice fits the bounds.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Vec<&'a String>> {
+        let last = &self.layers[self.groups.len()][self.count];
+        let totals: Vec<(usize, BigUint)> =
+            (self.min..=self.max).map(|t| (t, last[t].clone())).filter(|(_, w)| !w.is_zero()).collect();
+        if totals.is_empty() {
+            return None;
+        }
+        let mut t = pick_weighted(rng, &totals);
+        let mut j = self.count;
+
+        let mut chosen = Vec::with_capacity(self.count);
+        for g in (0..self.groups.len()).rev() {
+            let (length, group) = &self.groups[g];
+            let candidates: Vec<(usize, BigUint)> = (0..=group.len().min(j))
+                .take_while(|c| c * length <= t)
+                .map(|c| (c, &self.layers[g][j - c][t - c * length] * &self.choose[g][c]))
+                .filter(|(_, w)| !w.is_zero())
+                .collect();
+            let c = pick_weighted(rng, &candidates);
+            chosen.extend(group.choose_multiple(rng, c).copied());
+            j -= c;
+            t -= c * length;
+        }
+        chosen.shuffle(rng);
+        Some(chosen)
+    }
+}
+
+/// C(n, c) for every `c` up to `k`.
+pub fn choose_row(n: usize, k: usize) -> Vec<BigUint> {
+    let mut row = vec![BigUint::one()];
+    for c in 1..=k.min(n) {
+        let next = row[c - 1].clone() * (n - c + 1) / c;
+        row.push(next);
+    }
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashMap;
+
+    fn class(chars: &str, min: usize, max: usize, weight: u32) -> ClassLimits {
+        ClassLimits { chars: chars.chars().map(String::from).collect(), min, max, weight }
+    }
+
+    /// Every string of `length` over the classes within their limits and the repeat limit.
+    fn brute_force(classes: &[ClassLimits], length: usize, max_repeats: usize) -> Vec<String> {
+        let pool: Vec<(usize, &str)> =
+            classes.iter().enumerate().flat_map(|(a, c)| c.chars.iter().map(move |s| (a, s.as_str()))).collect();
+        let mut valid = Vec::new();
+        for mut i in 0..pool.len().pow(length as u32) {
+            let mut string = Vec::new();
+            for _ in 0..length {
+                string.push(pool[i % pool.len()]);
+                i /= pool.len();
+            }
+            let within_classes = classes.iter().enumerate().all(|(a, c)| {
+                (c.min..=c.max).contains(&string.iter().filter(|&&(b, _)| a == b).count())
+            });
+            let within_repeats =
+                string.iter().all(|&(_, s)| string.iter().filter(|&&(_, t)| s == t).count() <= max_repeats);
+            if within_classes && within_repeats {
+                valid.push(string.iter().map(|&(_, s)| s).collect());
+            }
+        }
+        valid
+    }
+
+    #[test]
+    fn class_counts_match_brute_force() {
+        for length in 0..=5 {
+            for max_repeats in 1..=3 {
+                for (min, max) in [(0, 5), (1, 5), (1, 2), (2, 3)] {
+                    let classes = vec![class("abc", min, max, 1), class("12", 0, 5, 1), class("#", 0, 1, 1)];
+                    let sampler = ClassSampler::new(classes.clone(), length, max_repeats);
+                    let expected = brute_force(&classes, length, max_repeats).len();
+                    assert_eq!(*sampler.count(), BigUint::from(expected), "{} {} {}-{}", length, max_repeats, min, max);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_class_counts_match_brute_force() {
+        let classes = vec![class("ab", 1, 3, 2), class("xyz", 0, 3, 5)];
+        let weight_of = |s: &str| s.chars().map(|c| if "ab".contains(c) { 2u64 } else { 5 }).product::<u64>();
+        let expected: u64 = brute_force(&classes, 4, 2).iter().map(|s| weight_of(s)).sum();
+        assert_eq!(*ClassSampler::new(classes, 4, 2).count(), BigUint::from(expected));
+    }
+
+    #[test]
+    fn class_sampler_reaches_every_string_evenly() {
+        let classes = vec![class("abc", 1, 3, 1), class("12", 1, 3, 1)];
+        let sampler = ClassSampler::new(classes.clone(), 3, 2);
+        let valid = brute_force(&classes, 3, 2);
+
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut rng = StdRng::seed_from_u64(21);
+        for _ in 0..200 * valid.len() {
+            *seen.entry(sampler.sample(&mut rng).unwrap()).or_insert(0) += 1;
+        }
+        assert_eq!(seen.len(), valid.len());
+        for string in &valid {
+            let count = seen.get(string).copied().unwrap_or(0);
+            assert!((120..=280).contains(&count), "{} drawn {} times", string, count);
+        }
+    }
+
+    #[test]
+    fn word_length_counts_match_brute_force() {
+        let words: Vec<String> = ["a", "bb", "cc", "ddd", "eeee", "ff"].iter().map(|w| w.to_string()).collect();
+        let entries: Vec<(usize, &String)> = words.iter().map(|w| (w.len(), w)).collect();
+        for count in 1..=3 {
+            for (min, max) in [(0, 20), (4, 6), (5, 5), (9, 9)] {
+                let sampler = WordLengthSampler::new(entries.clone(), count, min, max);
+                let expected = (0..1usize << words.len())
+                    .filter(|mask| mask.count_ones() as usize == count)
+                    .filter(|mask| {
+                        let chosen = (0..words.len()).filter(|i| mask & (1 << i) != 0);
+                        (min..=max).contains(&chosen.map(|i| words[i].len()).sum())
+                    })
+                    .count();
+                let total: BigUint = (min..=max).map(|t| &sampler.layers[sampler.groups.len()][count][t]).sum();
+                assert_eq!(total, BigUint::from(expected), "{} words, {}-{}", count, min, max);
+                assert_eq!(sampler.sample(&mut StdRng::seed_from_u64(0)).is_some(), expected > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn word_length_sampler_reaches_every_choice_evenly() {
+        let words: Vec<String> = ["a", "bb", "cc", "ddd", "eeee"].iter().map(|w| w.to_string()).collect();
+        let entries: Vec<(usize, &String)> = words.iter().map(|w| (w.len(), w)).collect();
+        // Two words with 4 or 5 letters together: a+ddd, a+eeee, bb+cc, bb+ddd, cc+ddd.
+        let sampler = WordLengthSampler::new(entries, 2, 4, 5);
+
+        let mut seen: HashMap<Vec<String>, usize> = HashMap::new();
+        let mut rng = StdRng::seed_from_u64(34);
+        for _ in 0..1000 {
+            let mut chosen: Vec<String> = sampler.sample(&mut rng).unwrap().into_iter().cloned().collect();
+            chosen.sort();
+            *seen.entry(chosen).or_insert(0) += 1;
+        }
+        assert_eq!(seen.len(), 5);
+        assert!(seen.values().all(|&count| (140..=260).contains(&count)), "{:?}", seen);
+    }
+}
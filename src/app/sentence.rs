@@ -0,0 +1,330 @@
+use crate::app::unicode::nfc;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The grammar used when none is configured; about 40 bits with the built-in tagged wordlist.
+pub const DEFAULT_GRAMMAR: &str = "number adjective nouns adverb verb adjective nouns";
+
+/// Sentences below this many bits are weaker than the default word passphrase,
+/// three words from a 7776-word list, and get a warning.
+pub const WEAK_SENTENCE_BITS: f64 = 38.77;
+
+/// Explains the grammar syntax, for help texts.
+pub const GRAMMAR_HELP: &str = "Slots: number, adjective, noun, nouns (plural), verb (as in \"they juggle\"), \
+                                verbs (as in \"it juggles\"), adverb.";
+
+/// Counting words for the `number` slot; it pairs with plural nouns, so it starts at two.
+const NUMBER_WORDS: &[&str] = &[
+    "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve", "thirteen", "fourteen",
+    "fifteen", "sixteen", "seventeen", "eighteen", "nineteen", "twenty",
+];
+
+const DEFAULT_NOUNS: &[&str] = &[
+    "otter", "badger", "falcon", "walrus", "penguin", "turtle", "dolphin", "beaver", "hamster", "parrot", "lizard",
+    "rabbit", "squirrel", "tiger", "panda", "koala", "camel", "donkey", "monkey", "giraffe", "zebra", "llama", "fox",
+    "octopus", "pirate", "wizard", "robot", "farmer", "baker", "dancer", "teacher", "sailor", "captain", "doctor",
+    "painter", "banana", "pickle", "muffin", "teapot", "bicycle", "rocket", "lantern", "violin", "trumpet", "castle",
+    "garden", "pillow", "kitten", "puppy", "pony", "dragon", "goblin", "unicorn", "pelican", "hedgehog", "raccoon",
+    "mouse\tmice", "goose\tgeese", "wolf\twolves", "cactus\tcacti", "moose\tmoose", "sheep\tsheep", "elf\telves",
+    "child\tchildren",
+];
+
+const DEFAULT_VERBS: &[&str] = &[
+    "juggle", "dance", "whistle", "paint", "bake", "climb", "giggle", "wander", "sing", "swim", "jump", "skate", "knit",
+    "read", "cook", "hum", "wiggle", "dream", "sneeze", "tumble", "march", "splash", "fetch", "wash", "buzz", "fly",
+    "carry", "hurry", "study", "nap", "yawn", "whisper", "snore", "bounce", "gallop", "hop", "roll", "twirl",
+    "shuffle", "doodle", "gossip", "tiptoe", "sparkle", "ponder", "chuckle", "wobble", "stomp", "sprint", "travel",
+    "explore", "build", "sail", "paddle", "drum", "strum", "yodel", "munch", "slurp", "scribble", "rehearse",
+    "celebrate", "waltz", "go\tgoes", "echo\techoes",
+];
+
+const DEFAULT_ADJECTIVES: &[&str] = &[
+    "purple", "fuzzy", "brave", "sleepy", "clever", "jolly", "tiny", "giant", "shiny", "grumpy", "happy", "noisy",
+    "quiet", "silly", "gentle", "fancy", "sneaky", "bouncy", "curious", "proud", "lucky", "rusty", "golden", "silver",
+    "crimson", "orange", "green", "yellow", "frosty", "sunny", "windy", "cozy", "dizzy", "eager", "fierce", "funny",
+    "glad", "hungry", "jazzy", "kind", "lazy", "merry", "nimble", "odd", "polite", "quirky", "rapid", "spotted",
+    "striped", "tidy", "wild", "witty", "zany", "chubby", "fluffy", "humble", "mighty", "plucky", "rowdy", "spicy",
+    "sturdy", "wise", "young", "ancient",
+];
+
+const DEFAULT_ADVERBS: &[&str] = &[
+    "loudly", "quietly", "happily", "boldly", "slowly", "quickly", "gently", "wildly", "bravely", "calmly", "eagerly",
+    "gladly", "neatly", "proudly", "softly", "swiftly", "warmly", "wisely", "busily", "cheerfully", "clumsily", "daily",
+    "gracefully", "merrily", "nervously", "politely", "rarely", "sadly", "secretly", "sleepily", "smoothly", "sweetly",
+    "tightly", "wearily", "joyfully", "lazily", "madly", "nightly", "noisily", "often", "patiently", "playfully",
+    "rapidly", "bashfully", "briskly", "carefully", "dreamily", "elegantly", "fiercely", "grandly", "hastily",
+    "honestly", "kindly", "lightly", "loyally", "mightily", "openly", "quirkily", "roughly", "shyly", "silently",
+    "sternly", "truly", "vividly",
+];
+
+/// A part of speech a tagged wordlist can give a word.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PartOfSpeech {
+    Noun,
+    Verb,
+    Adjective,
+    Adverb,
+}
+
+impl FromStr for PartOfSpeech {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "noun" | "n" => Ok(PartOfSpeech::Noun),
+            "verb" | "v" => Ok(PartOfSpeech::Verb),
+            "adjective" | "adj" | "a" => Ok(PartOfSpeech::Adjective),
+            "adverb" | "adv" | "r" => Ok(PartOfSpeech::Adverb),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The words of a tagged wordlist, sorted by part of speech.
+///
+/// Nouns are kept with their plural and verbs with their "it ..." form, either
+/// given in the list or made by the usual English spelling rules.
+#[derive(Clone, Debug, Default)]
+pub struct TaggedWords {
+    pub nouns: Vec<(String, String)>,
+    pub verbs: Vec<(String, String)>,
+    pub adjectives: Vec<String>,
+    pub adverbs: Vec<String>,
+}
+
+/// A slot in a sentence grammar, filled with one word.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GrammarSlot {
+    Number,
+    Adjective,
+    Noun,
+    Nouns,
+    Verb,
+    Verbs,
+    Adverb,
+}
+
+impl FromStr for GrammarSlot {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "number" => Ok(GrammarSlot::Number),
+            "adjective" => Ok(GrammarSlot::Adjective),
+            "noun" => Ok(GrammarSlot::Noun),
+            "nouns" => Ok(GrammarSlot::Nouns),
+            "verb" => Ok(GrammarSlot::Verb),
+            "verbs" => Ok(GrammarSlot::Verbs),
+            "adverb" => Ok(GrammarSlot::Adverb),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for GrammarSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GrammarSlot::Number => "number",
+            GrammarSlot::Adjective => "adjective",
+            GrammarSlot::Noun => "noun",
+            GrammarSlot::Nouns => "nouns",
+            GrammarSlot::Verb => "verb",
+            GrammarSlot::Verbs => "verbs",
+            GrammarSlot::Adverb => "adverb",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Parses a grammar such as "number adjective nouns adverb verb" into its slots.
+pub fn parse_grammar(grammar: &str) -> Result<Vec<GrammarSlot>, String> {
+    let slots = grammar
+        .split_whitespace()
+        .map(|token| token.parse().map_err(|_| format!("Unknown grammar slot '{}'. {}", token, GRAMMAR_HELP)))
+        .collect::<Result<Vec<GrammarSlot>, String>>()?;
+    if slots.is_empty() {
+        return Err(format!("The grammar is empty. {}", GRAMMAR_HELP));
+    }
+    Ok(slots)
+}
+
+/// Adds the English "-s" ending: plurals of nouns and the "it ..." form of verbs.
+fn add_s(word: &str) -> String {
+    let consonant_y = word.len() > 1
+        && word.ends_with('y')
+        && !matches!(word.as_bytes()[word.len() - 2], b'a' | b'e' | b'i' | b'o' | b'u');
+    if consonant_y {
+        format!("{}ies", &word[..word.len() - 1])
+    } else if ["s", "x", "z", "ch", "sh"].iter().any(|ending| word.ends_with(ending)) {
+        format!("{}es", word)
+    } else {
+        format!("{}s", word)
+    }
+}
+
+/// Whether a wordlist is tagged with parts of speech: its first entry names a known tag after the word.
+pub fn is_tagged_list(content: &str) -> bool {
+    let first = content.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('#'));
+    first
+        .and_then(|line| line.split_whitespace().nth(1))
+        .is_some_and(|tags| tags.split(',').all(|tag| tag.parse::<PartOfSpeech>().is_ok()))
+}
+
+/// Parses a tagged wordlist.
+///
+/// Each line holds a word, its parts of speech separated by commas (noun,
+/// verb, adjective, adverb or n, v, adj, adv) and optionally the irregular
+/// plural or "it ..." form, separated by whitespace: `mouse noun mice`.
+/// Blank lines and lines starting with `#` are skipped.
+pub fn parse_tagged_words(content: &str) -> Result<TaggedWords, String> {
+    let mut tagged = TaggedWords::default();
+    let mut seen = HashSet::new();
+    for (number, line) in nfc(content).lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (word, tags, form) = match fields[..] {
+            [word, tags] => (word, tags, None),
+            [word, tags, form] => (word, tags, Some(form)),
+            _ => return Err(format!("Line {}: expected a word, its tags and an optional form.", number + 1)),
+        };
+        for tag in tags.split(',') {
+            let part: PartOfSpeech = tag.parse().map_err(|_| {
+                format!("Line {}: unknown tag '{}'; use noun, verb, adjective or adverb.", number + 1, tag)
+            })?;
+            if !seen.insert((word.to_string(), tag.to_lowercase())) {
+                continue;
+            }
+            let inflected = || form.map(str::to_string).unwrap_or_else(|| add_s(word));
+            match part {
+                PartOfSpeech::Noun => tagged.nouns.push((word.to_string(), inflected())),
+                PartOfSpeech::Verb => tagged.verbs.push((word.to_string(), inflected())),
+                PartOfSpeech::Adjective => tagged.adjectives.push(word.to_string()),
+                PartOfSpeech::Adverb => tagged.adverbs.push(word.to_string()),
+            }
+        }
+    }
+    Ok(tagged)
+}
+
+/// Loads a tagged wordlist, returning it with a status message.
+pub fn load_tagged_words(path: &Path) -> Result<(TaggedWords, String), String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read the tagged wordlist {}: {}", path.display(), e))?;
+    let tagged = parse_tagged_words(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let status = format!(
+        "Loaded {} nouns, {} verbs, {} adjectives and {} adverbs from {:?}",
+        tagged.nouns.len(),
+        tagged.verbs.len(),
+        tagged.adjectives.len(),
+        tagged.adverbs.len(),
+        path.file_name().unwrap_or_default()
+    );
+    Ok((tagged, status))
+}
+
+/// Warns when a sentence of `bits` is weaker than the default word passphrase.
+pub fn weak_sentence_warning(bits: f64) -> Option<String> {
+    (bits < WEAK_SENTENCE_BITS).then(|| {
+        format!(
+            "This grammar gives only {:.2} bits, less than the {:.2} of a default word passphrase; \
+             add slots or use a larger tagged wordlist.",
+            bits, WEAK_SENTENCE_BITS
+        )
+    })
+}
+
+/// The built-in tagged wordlist in the file format `parse_tagged_words` reads.
+fn default_tagged_words() -> String {
+    let mut content = String::from("# word, part of speech and, for irregular words, the plural or \"it ...\" form\n");
+    let lists = [(DEFAULT_NOUNS, "noun"), (DEFAULT_VERBS, "verb"), (DEFAULT_ADJECTIVES, "adjective")];
+    for (words, tag) in lists.into_iter().chain([(DEFAULT_ADVERBS, "adverb")]) {
+        for entry in words {
+            match entry.split_once('\t') {
+                Some((word, form)) => content.push_str(&format!("{}\t{}\t{}\n", word, tag, form)),
+                None => content.push_str(&format!("{}\t{}\n", entry, tag)),
+            }
+        }
+    }
+    content
+}
+
+/// Writes the built-in tagged wordlist to `path` if there is no file there yet.
+pub fn create_default_tagged_words_file(path: &Path) -> String {
+    if path.exists() {
+        return String::new();
+    }
+    match fs::write(path, default_tagged_words()) {
+        Ok(_) => format!("Created default {:?}", path.file_name().unwrap_or_default()),
+        Err(e) => format!("Failed to create the default tagged wordlist: {}", e),
+    }
+}
+
+impl TaggedWords {
+    /// The distinct words a slot can be filled with.
+    pub fn pool(&self, slot: GrammarSlot) -> Vec<&str> {
+        let words: Vec<&str> = match slot {
+            GrammarSlot::Number => NUMBER_WORDS.to_vec(),
+            GrammarSlot::Adjective => self.adjectives.iter().map(String::as_str).collect(),
+            GrammarSlot::Noun => self.nouns.iter().map(|(singular, _)| singular.as_str()).collect(),
+            GrammarSlot::Nouns => self.nouns.iter().map(|(_, plural)| plural.as_str()).collect(),
+            GrammarSlot::Verb => self.verbs.iter().map(|(base, _)| base.as_str()).collect(),
+            GrammarSlot::Verbs => self.verbs.iter().map(|(_, third)| third.as_str()).collect(),
+            GrammarSlot::Adverb => self.adverbs.iter().map(String::as_str).collect(),
+        };
+        let mut seen = HashSet::new();
+        words.into_iter().filter(|word| seen.insert(*word)).collect()
+    }
+
+    /// The pool of every slot in a grammar, failing if one is empty.
+    pub fn pools(&self, slots: &[GrammarSlot]) -> Result<Vec<Vec<&str>>, String> {
+        slots
+            .iter()
+            .map(|&slot| {
+                let pool = self.pool(slot);
+                match pool.is_empty() {
+                    true => Err(format!("The tagged wordlist has no words for the '{}' slot.", slot)),
+                    false => Ok(pool),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Generates a sentence passphrase such as "seven purple otters happily juggle fuzzy pickles".
+///
+/// Every slot is filled independently and uniformly from the distinct words
+/// for its part of speech, and the words are joined with `separator`.
+pub fn generate_sentence_passphrase<R: Rng + ?Sized>(
+    grammar: &str,
+    words: &TaggedWords,
+    separator: &str,
+    rng: &mut R,
+) -> Result<String, String> {
+    let slots = parse_grammar(grammar)?;
+    let pools = words.pools(&slots)?;
+    let chosen: Vec<&str> = pools.iter().map(|pool| *pool.choose(rng).unwrap()).collect();
+    Ok(chosen.join(separator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::entropy::sentence_passphrase_entropy;
+
+    #[test]
+    fn default_grammar_is_as_strong_as_the_default_word_passphrase() {
+        let words = parse_tagged_words(&default_tagged_words()).unwrap();
+        let report = sentence_passphrase_entropy(DEFAULT_GRAMMAR, &words, " ").unwrap();
+        assert!(report.bits >= WEAK_SENTENCE_BITS, "{} bits", report.bits);
+        assert_eq!(weak_sentence_warning(report.bits), None);
+
+        let short = sentence_passphrase_entropy("number adjective nouns verb adverb", &words, " ").unwrap();
+        assert!(weak_sentence_warning(short.bits).is_some_and(|warning| warning.contains("28.25 bits")));
+    }
+}
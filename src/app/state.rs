@@ -1,4 +1,9 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use crate::app::breach::PasswordScreen;
+use crate::app::password::MAX_CHAR_REPEATS;
+use crate::app::sentence::{TaggedWords, DEFAULT_GRAMMAR};
+use crate::app::wordlist::Wordlist;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::str::FromStr;
 
@@ -25,6 +30,12 @@ impl FromStr for Theme {
 pub enum Tab {
     Character,
     Word,
+    Template,
+    Pronounceable,
+    Sentence,
+    Pin,
+    Site,
+    Check,
 }
 
 impl FromStr for Tab {
@@ -33,11 +44,184 @@ impl FromStr for Tab {
         match s {
             "Character" => Ok(Tab::Character),
             "Word" => Ok(Tab::Word),
+            "Template" => Ok(Tab::Template),
+            "Pronounceable" => Ok(Tab::Pronounceable),
+            "Sentence" => Ok(Tab::Sentence),
+            "Pin" => Ok(Tab::Pin),
+            "Site" => Ok(Tab::Site),
+            "Check" => Ok(Tab::Check),
             _ => Err(()),
         }
     }
 }
 
+/// How the words of a passphrase are capitalized.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WordCase {
+    /// `apple tree`
+    Lower,
+    /// `Apple Tree`
+    Capitalized,
+    /// `APPLE TREE`
+    Upper,
+    /// Each word independently lowercase, Capitalized or UPPERCASE.
+    Random,
+    /// `appleTree`: the first word lowercase, the rest Capitalized.
+    Camel,
+}
+
+impl WordCase {
+    /// Every casing, in the order shown in the GUI.
+    pub const ALL: [WordCase; 5] =
+        [WordCase::Lower, WordCase::Capitalized, WordCase::Upper, WordCase::Random, WordCase::Camel];
+
+    /// A short name for menus.
+    pub fn label(&self) -> &'static str {
+        match self {
+            WordCase::Lower => "lowercase",
+            WordCase::Capitalized => "Capitalized",
+            WordCase::Upper => "ALL CAPS",
+            WordCase::Random => "Random per word",
+            WordCase::Camel => "camelCase",
+        }
+    }
+}
+
+impl FromStr for WordCase {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "lower" => Ok(WordCase::Lower),
+            "capitalized" => Ok(WordCase::Capitalized),
+            "upper" => Ok(WordCase::Upper),
+            "random" => Ok(WordCase::Random),
+            "camel" => Ok(WordCase::Camel),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Where random characters are inserted into a passphrase.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Insertion {
+    None,
+    /// Before, between or after the words.
+    Between,
+    /// Inside distinct words, never at their ends.
+    Inside,
+}
+
+impl FromStr for Insertion {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Insertion::None),
+            "between" => Ok(Insertion::Between),
+            "inside" => Ok(Insertion::Inside),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How password and passphrase lengths are counted.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LengthUnit {
+    /// User-perceived characters (grapheme clusters).
+    Characters,
+    /// UTF-8 bytes, for systems that limit passwords by storage size.
+    Bytes,
+}
+
+impl LengthUnit {
+    /// The plural noun for the unit, as used in messages.
+    pub fn label(&self) -> &'static str {
+        match self {
+            LengthUnit::Characters => "characters",
+            LengthUnit::Bytes => "bytes",
+        }
+    }
+}
+
+impl FromStr for LengthUnit {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "characters" => Ok(LengthUnit::Characters),
+            "bytes" => Ok(LengthUnit::Bytes),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How words are chosen and turned into a passphrase.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordStyle {
+    pub case: WordCase,
+    pub use_separator: bool,
+    /// The text between words; may be several characters.
+    pub separator: String,
+    /// Draws each gap's separator from the characters of `separator` instead of using it whole.
+    pub random_separator: bool,
+    pub insertion: Insertion,
+    /// The characters random insertions are drawn from.
+    pub insert_chars: String,
+    /// How many random characters to insert.
+    pub insert_count: usize,
+    /// The shortest passphrase allowed; 0 for no minimum.
+    pub min_length: usize,
+    /// The longest passphrase allowed; 0 picks a cap from the word count.
+    pub max_length: usize,
+    /// Whether `max_length` applies at all.
+    pub use_length_cap: bool,
+    /// What the length limits count.
+    pub length_unit: LengthUnit,
+    /// Also gives the passphrase with each word cut to this many letters, for typing; 0 for none.
+    pub abbreviate: usize,
+}
+
+impl Default for WordStyle {
+    fn default() -> Self {
+        Self {
+            case: WordCase::Capitalized,
+            use_separator: true,
+            separator: "-".to_string(),
+            random_separator: false,
+            insertion: Insertion::None,
+            insert_chars: "0123456789".to_string(),
+            insert_count: 1,
+            min_length: 0,
+            max_length: 0,
+            use_length_cap: true,
+            length_unit: LengthUnit::Characters,
+            abbreviate: 0,
+        }
+    }
+}
+
+/// Filters applied to the wordlist as it is loaded. All are off by default.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WordFilters {
+    /// The shortest word kept, in characters; 0 keeps all.
+    pub min_length: usize,
+    /// The longest word kept, in characters; 0 keeps all.
+    pub max_length: usize,
+    /// Replaces accented letters with plain ASCII ones, such as `é` with `e`, before the other filters.
+    pub fold_ascii: bool,
+    /// Drops words with characters outside ASCII.
+    pub ascii_only: bool,
+    /// Drops words with any uppercase letter, such as proper nouns.
+    pub lowercase_only: bool,
+    /// Drops words with anything other than letters and digits, such as `A-horizon`.
+    pub no_punctuation: bool,
+    /// Keeps only the first of words that differ just in case.
+    pub dedupe: bool,
+    /// Keeps only the first of words that share their first this many letters, so each word can be
+    /// typed as its prefix; 0 keeps all.
+    pub unique_prefix: usize,
+    /// A file of words to drop, one per line, compared without case; empty for none.
+    pub exclude_file: String,
+}
+
 /// Command-Line Argument Definitions
 #[derive(Parser, Debug)]
 #[command(
@@ -45,16 +229,236 @@ impl FromStr for Tab {
     about = "A versatile password and passphrase generator.",
     long_about = "Generates strong, random passwords or memorable passphrases.\n\n\
                   Run without arguments to launch the GUI.\n\
-                  Use either -n for character-based passwords or -w for word-based passphrases in CLI mode."
+                  Use -n for character-based passwords, -w for word-based passphrases,\n\
+                  -t for template-based passwords, -p for pronounceable passwords,\n\
+                  --sentence for grammar-based sentence passphrases, --pin for PINs,\n\
+                  --rules for passwords that follow a service's published password rules or\n\
+                  --derive for site passwords derived from a master passphrase in CLI mode.\n\
+                  Use the check subcommand to estimate the strength of an existing password.",
+    args_conflicts_with_subcommands = true
 )]
 pub struct CliArgs {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+
     /// Generates a character-based password of a specific length.
     #[arg(short, long, group = "mode")]
     pub n: Option<usize>,
 
-    /// Generates a word-based passphrase with a specific number of words (3, 4, or 5).
-    #[arg(short, long, group = "mode", value_name = "COUNT")]
+    /// Generates a word-based passphrase with a specific number of words.
+    #[arg(short, long, group = "mode", group = "separated", value_name = "COUNT")]
     pub w: Option<usize>,
+
+    /// Generates a password from a template, e.g. "Cvcc-d{4}-ssXX".
+    /// c/C consonant, v/V vowel, x/X letter, d digit, s special, * any; d{4} repeats a token; \ escapes.
+    #[arg(short, long, group = "mode", value_name = "TEMPLATE")]
+    pub template: Option<String>,
+
+    /// Generates a pronounceable password of a specific length, learned from words.txt.
+    #[arg(short, long, group = "mode", value_name = "LENGTH")]
+    pub pronounceable: Option<usize>,
+
+    /// Generates a sentence passphrase such as "seven purple otters happily juggle fuzzy
+    /// pickles" from a grammar of slots (number, adjective, noun, nouns, verb, verbs,
+    /// adverb), drawing from the part-of-speech tagged wordlist; without GRAMMAR, the one
+    /// in config.toml is used.
+    #[arg(
+        long,
+        group = "mode",
+        group = "separated",
+        value_name = "GRAMMAR",
+        num_args = 0..=1,
+        default_missing_value = ""
+    )]
+    pub sentence: Option<String>,
+
+    /// Generates a numeric PIN of 4 to 12 digits that avoids common PINs, runs,
+    /// repeats, keypad lines and dates.
+    #[arg(long, group = "mode", value_name = "DIGITS")]
+    pub pin: Option<usize>,
+
+    /// Derives the password for a site or account from a master passphrase read
+    /// without echo; the same inputs always give the same password, from a character
+    /// policy fixed by --derive-version.
+    #[arg(long, group = "mode", group = "sized", value_name = "SITE")]
+    pub derive: Option<String>,
+
+    /// Generates a character password that follows password rules, given as Apple
+    /// passwordrules ("minlength: 20; required: lower; required: [-_]; allowed: digit")
+    /// or as a HashiCorp Vault password policy. A max-consecutive above 1 is only
+    /// approximated, by limiting how often each character appears at all.
+    #[arg(long, group = "mode", group = "sized", value_name = "RULES")]
+    pub rules: Option<String>,
+
+    /// Like --rules, reading the rules from a file such as a Vault policy.hcl.
+    #[arg(long, group = "mode", group = "sized", value_name = "FILE")]
+    pub rules_file: Option<PathBuf>,
+
+    /// Length of the --derive or --rules password (default from config.toml, else 16;
+    /// --rules keeps it within the allowed lengths).
+    #[arg(long, requires = "sized", value_name = "LENGTH")]
+    pub length: Option<usize>,
+
+    /// Counter for --derive; raise it to rotate a site's password (default 1).
+    #[arg(long, requires = "derive", value_name = "N", default_value_t = 1)]
+    pub counter: u32,
+
+    /// Derivation algorithm version for --derive (default: the newest, v1).
+    #[arg(long, requires = "derive", value_name = "VERSION")]
+    pub derive_version: Option<String>,
+
+    /// Shortest -w passphrase allowed.
+    #[arg(long, requires = "w", value_name = "LENGTH")]
+    pub min_length: Option<usize>,
+
+    /// Longest -w passphrase allowed (default: 5 per word plus 15).
+    #[arg(long, requires = "w", value_name = "LENGTH", conflicts_with = "no_length_cap")]
+    pub max_length: Option<usize>,
+
+    /// Lets -w passphrases be any length.
+    #[arg(long, requires = "w")]
+    pub no_length_cap: bool,
+
+    /// Counts -n lengths and -w length limits in UTF-8 bytes instead of characters.
+    #[arg(long, conflicts_with_all = ["derive", "rules", "rules_file"])]
+    pub bytes: bool,
+
+    /// Casing of -w passphrase words: lower, capitalized, upper, random or camel.
+    #[arg(long, requires = "w", value_name = "CASE")]
+    pub case: Option<String>,
+
+    /// Text between -w passphrase or --sentence words; may be several characters, or "" for none.
+    #[arg(long, requires = "separated", value_name = "TEXT", allow_hyphen_values = true)]
+    pub separator: Option<String>,
+
+    /// Draws each gap's separator at random from the characters of --separator.
+    #[arg(long, requires = "w")]
+    pub random_separator: bool,
+
+    /// Inserts random characters into the -w passphrase: none, between or inside (words).
+    #[arg(long, requires = "w", value_name = "WHERE")]
+    pub insert: Option<String>,
+
+    /// Characters --insert draws from (default from config.toml, else digits).
+    #[arg(long, requires = "insert", value_name = "CHARS", allow_hyphen_values = true)]
+    pub insert_chars: Option<String>,
+
+    /// How many characters --insert adds (default 1).
+    #[arg(long, requires = "insert", value_name = "N")]
+    pub insert_count: Option<usize>,
+
+    /// Also prints the -w passphrase with each word cut to its first N letters, for typing on poor keyboards.
+    /// Use with --unique-prefix N so every abbreviation stands for one word.
+    #[arg(long, requires = "w", value_name = "N", conflicts_with = "dice")]
+    pub abbreviate: Option<usize>,
+
+    /// Chooses the -w passphrase words from physical dice rolls typed on stdin.
+    /// Requires a complete numbered Diceware wordlist (e.g. 7776 words for 5 dice).
+    #[arg(long, requires = "w")]
+    pub dice: bool,
+
+    /// Leaves out look-alike characters such as 0/O/o, 1/l/I/|, 5/S and quotes.
+    #[arg(long, conflicts_with = "derive")]
+    pub no_ambiguous: bool,
+
+    /// Characters to leave out of generated passwords, added to those in config.toml.
+    #[arg(long, value_name = "CHARS", conflicts_with = "derive")]
+    pub exclude: Option<String>,
+
+    /// The most times any one character may appear in a -n password (default 3).
+    #[arg(long, value_name = "N", conflicts_with = "derive")]
+    pub max_repeats: Option<usize>,
+
+    /// Forbids the same character twice in a row in -n passwords.
+    #[arg(long, conflicts_with = "derive")]
+    pub no_adjacent: bool,
+
+    /// Forbids runs such as "abc" or "321" in -n passwords. Over the default classes this needs
+    /// --max-repeats of at least the length.
+    #[arg(long, conflicts_with = "derive")]
+    pub no_sequences: bool,
+
+    /// Forbids keyboard walks such as "qwe" or "lkj" in -n passwords. Over the default classes this
+    /// needs --max-repeats of at least the length.
+    #[arg(long, conflicts_with = "derive")]
+    pub no_keyboard_walks: bool,
+
+    /// TESTS ONLY: draws from a ChaCha20 generator seeded with N, so the same seed
+    /// always gives the same output. Never use seeded passwords for real accounts.
+    #[arg(long, value_name = "N", requires = "mode", conflicts_with = "derive")]
+    pub seed: Option<u64>,
+
+    /// Uses the settings of a profile saved in config.toml; other flags still override them.
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Keeps only wordlist words told apart by their first N letters, so each can be typed as its prefix.
+    #[arg(long, value_name = "N", global = true)]
+    pub unique_prefix: Option<usize>,
+
+    /// Uses a wordlist registered in config.toml instead of words.txt, e.g. a list in another language.
+    #[arg(long, value_name = "NAME", global = true)]
+    pub wordlist: Option<String>,
+
+    /// Replaces accented letters in the wordlist with plain ASCII ones, such as "é" with "e".
+    #[arg(long, global = true)]
+    pub fold_ascii: bool,
+
+    /// Rejects passwords found in this Have I Been Pwned SHA-1 hash file or Bloom filter.
+    #[arg(long, value_name = "FILE", global = true)]
+    pub breached: Option<PathBuf>,
+
+    /// Rejects passwords containing any word from this file, one word per line.
+    #[arg(long, value_name = "FILE", global = true)]
+    pub banned: Option<PathBuf>,
+
+    /// Generates N distinct passwords at once, one per line, without touching the clipboard.
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "mode", conflicts_with_all = ["derive", "dice"])]
+    pub count: usize,
+
+    /// Prints the entropy of the generated password or passphrase to stderr.
+    #[arg(short, long)]
+    pub entropy: bool,
+}
+
+/// Subcommands that work on existing passwords and lists instead of generating new passwords.
+#[derive(Subcommand, Debug)]
+pub enum CliCommand {
+    /// Estimates how hard an existing password is to guess.
+    ///
+    /// The password is read without echo from the terminal, or as one line from
+    /// stdin, never from the command line.
+    Check,
+    /// Builds a compact Bloom filter from a Have I Been Pwned SHA-1 hash file.
+    ///
+    /// The filter can be passed to --breached in place of the much larger hash file.
+    BuildBloom {
+        /// The SHA-1 hash file, one "HASH:COUNT" line per password.
+        hash_file: PathBuf,
+        /// Where to write the filter.
+        output: PathBuf,
+        /// The share of unbreached passwords the filter may wrongly flag.
+        #[arg(long, value_name = "RATE", default_value_t = 0.001)]
+        false_positive_rate: f64,
+    },
+    /// Inspects wordlists.
+    Wordlist {
+        #[command(subcommand)]
+        command: WordlistCommand,
+    },
+}
+
+/// What `wordlist` can do.
+#[derive(Subcommand, Debug)]
+pub enum WordlistCommand {
+    /// Reports where a wordlist comes from, its SHA-256, size and bits per word, and any problems in it.
+    ///
+    /// Problems are empty lines, stray whitespace, duplicates and words differing only by case.
+    /// The filters from config.toml are applied, as they are for generation.
+    Info {
+        /// A wordlist registered in config.toml; without NAME, the one --wordlist or config.toml selects.
+        name: Option<String>,
+    },
 }
 
 /// Represents the character sets for password generation.
@@ -76,6 +480,88 @@ impl CharacterSets {
             special: "!@#$%^&*()-_=+[]{}|;:,.<>?".to_string(),
         }
     }
+
+    /// Takes the sets from the classes named Lowercase, Uppercase, Numbers and Special,
+    /// falling back to the defaults for any that are missing.
+    pub fn from_classes(classes: &[CharacterClass]) -> Self {
+        let defaults = Self::default();
+        let find = |name: &str, fallback: String| {
+            classes
+                .iter()
+                .find(|c| c.name.eq_ignore_ascii_case(name))
+                .map(|c| c.chars.clone())
+                .unwrap_or(fallback)
+        };
+        Self {
+            lowercase: find("Lowercase", defaults.lowercase),
+            uppercase: find("Uppercase", defaults.uppercase),
+            numbers: find("Numbers", defaults.numbers),
+            special: find("Special", defaults.special),
+        }
+    }
+}
+
+/// A named group of characters with limits on how often it appears in a password.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CharacterClass {
+    pub name: String,
+    pub chars: String,
+    pub enabled: bool,
+    /// The fewest characters from this class a password must contain.
+    pub min: usize,
+    /// The most characters from this class a password may contain, if limited.
+    pub max: Option<usize>,
+    /// How likely each character of this class is relative to a weight of 1.
+    pub weight: u32,
+}
+
+impl CharacterClass {
+    /// Creates an enabled class that must appear at least once.
+    pub fn new(name: &str, chars: &str) -> Self {
+        Self { name: name.to_string(), chars: chars.to_string(), enabled: true, min: 1, max: None, weight: 1 }
+    }
+
+    /// The classes matching the four default character sets.
+    pub fn defaults() -> Vec<Self> {
+        let sets = CharacterSets::default();
+        vec![
+            Self::new("Lowercase", &sets.lowercase),
+            Self::new("Uppercase", &sets.uppercase),
+            Self::new("Numbers", &sets.numbers),
+            Self::new("Special", &sets.special),
+        ]
+    }
+}
+
+/// Rules about repeated and sequential characters in character passwords.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RepetitionRules {
+    /// The most times any single character may appear.
+    pub max_occurrences: usize,
+    /// Forbids the same character twice in a row, as in `aa`.
+    pub no_adjacent_repeats: bool,
+    /// Forbids three letters or digits in a row in order, as in `abc` or `321`.
+    pub no_sequences: bool,
+    /// Forbids three neighbouring keys along a keyboard row, as in `qwe` or `lkj`.
+    pub no_keyboard_walks: bool,
+}
+
+impl Default for RepetitionRules {
+    fn default() -> Self {
+        Self {
+            max_occurrences: MAX_CHAR_REPEATS,
+            no_adjacent_repeats: false,
+            no_sequences: false,
+            no_keyboard_walks: false,
+        }
+    }
+}
+
+impl RepetitionRules {
+    /// Whether any rule depends on the order of the characters.
+    pub fn has_sequence_rules(&self) -> bool {
+        self.no_adjacent_repeats || self.no_sequences || self.no_keyboard_walks
+    }
 }
 
 /// The main application state for the GUI.
@@ -93,25 +579,77 @@ pub struct PassGenApp {
     // Settings
     pub theme: Theme,
     pub words_file_path_str: String,
+    pub breached_file: String,
+    pub banned_words_file: String,
+    pub password_screen: PasswordScreen,
+
+    // Profiles: name -> its generation settings as config lines
+    pub profiles: BTreeMap<String, String>,
+    pub selected_profile: String,
+    pub new_profile_name: String,
 
     // Character generator state
-    pub char_sets: CharacterSets,
-    pub use_lowercase: bool,
-    pub use_uppercase: bool,
-    pub use_numbers: bool,
-    pub use_special: bool,
+    pub char_classes: Vec<CharacterClass>,
+    pub exclude_ambiguous: bool,
+    pub excluded_chars: String,
+    pub repetition_rules: RepetitionRules,
     pub char_length: usize,
+    pub char_length_unit: LengthUnit,
     pub char_password_output: String,
     pub password_file_path: PathBuf,
+    pub show_rules_window: bool,
+    pub rules_text: String,
 
     // Word generator state
-    pub words: Vec<String>,
+    pub wordlist: Wordlist,
+    // Registered wordlists: name -> path; the selected one replaces words_file_path when set
+    pub wordlists: BTreeMap<String, String>,
+    pub selected_wordlist: String,
+    pub new_wordlist_name: String,
     pub word_count: usize,
     pub word_password_output: String,
+    pub word_abbreviated_output: String,
     pub words_file_path: PathBuf,
-    pub use_separator: bool,
-    pub separator_char: String,
-    pub use_uppercase_words: bool,
+    pub word_filters: WordFilters,
+    pub word_style: WordStyle,
+    pub use_dice_rolls: bool,
+    pub dice_rolls: String,
+
+    // Batch generation state
+    pub show_batch_window: bool,
+    pub batch_count: usize,
+    pub batch_output: Vec<String>,
+
+    // Template generator state
+    pub template: String,
+    pub template_password_output: String,
+
+    // Pronounceable generator state
+    pub pronounceable_length: usize,
+    pub pronounceable_password_output: String,
+
+    // Sentence generator state
+    pub tagged_words: TaggedWords,
+    pub tagged_words_file: String,
+    pub sentence_grammar: String,
+    pub sentence_separator: String,
+    pub sentence_output: String,
+
+    // PIN generator state
+    pub pin_length: usize,
+    pub pin_output: String,
+
+    // Site derivation state; the master passphrase is never saved
+    pub derive_site: String,
+    pub derive_counter: u32,
+    pub derive_length: usize,
+    pub derive_master: String,
+    pub derive_password_output: String,
+
+    // Strength check state; the password is never saved
+    pub check_password: String,
+    pub show_check_password: bool,
+    pub check_output: String,
 }
 
 impl Default for PassGenApp {
@@ -128,21 +666,56 @@ impl Default for PassGenApp {
             error_message: None,
             theme: Theme::Dark,
             words_file_path_str: String::new(),
-            char_sets: CharacterSets::default(),
-            use_lowercase: true,
-            use_uppercase: true,
-            use_numbers: true,
-            use_special: true,
+            breached_file: String::new(),
+            banned_words_file: String::new(),
+            password_screen: PasswordScreen::default(),
+            profiles: BTreeMap::new(),
+            selected_profile: String::new(),
+            new_profile_name: String::new(),
+            char_classes: CharacterClass::defaults(),
+            exclude_ambiguous: false,
+            excluded_chars: String::new(),
+            repetition_rules: RepetitionRules::default(),
             char_length: 16,
+            char_length_unit: LengthUnit::Characters,
             char_password_output: String::new(),
             password_file_path: PathBuf::new(),
-            words: Vec::new(),
+            show_rules_window: false,
+            rules_text: String::new(),
+            wordlist: Wordlist::default(),
+            wordlists: BTreeMap::new(),
+            selected_wordlist: String::new(),
+            new_wordlist_name: String::new(),
             word_count: 3,
             word_password_output: String::new(),
+            word_abbreviated_output: String::new(),
             words_file_path: PathBuf::new(),
-            use_separator: true,
-            separator_char: "-".to_string(),
-            use_uppercase_words: true,
+            word_filters: WordFilters::default(),
+            word_style: WordStyle::default(),
+            use_dice_rolls: false,
+            dice_rolls: String::new(),
+            show_batch_window: false,
+            batch_count: 10,
+            batch_output: Vec::new(),
+            template: "Cvcc-d{4}-ssXX".to_string(),
+            template_password_output: String::new(),
+            pronounceable_length: 12,
+            pronounceable_password_output: String::new(),
+            tagged_words: TaggedWords::default(),
+            tagged_words_file: String::new(),
+            sentence_grammar: DEFAULT_GRAMMAR.to_string(),
+            sentence_separator: " ".to_string(),
+            sentence_output: String::new(),
+            pin_length: 6,
+            pin_output: String::new(),
+            derive_site: String::new(),
+            derive_counter: 1,
+            derive_length: 16,
+            derive_master: String::new(),
+            derive_password_output: String::new(),
+            check_password: String::new(),
+            show_check_password: false,
+            check_output: String::new(),
         }
     }
 }
@@ -0,0 +1,932 @@
+use crate::app::entropy::{binomial, log2_factorial};
+use crate::app::rules::{key_position, KEYBOARD_ROWS};
+use crate::app::unicode::{graphemes, single_char};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Passwords are searched for patterns this many characters at a time.
+pub const MAX_CHECKED_LENGTH: usize = 100;
+
+/// A stretch past the first search that repeats the characters before it for at
+/// least this long counts as carrying on that repeat.
+const MIN_CONTINUATION: usize = 4;
+
+/// Guesses per character for stretches that match no pattern.
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+
+/// The fewest guesses a pattern inside a longer password counts for, by its length.
+const MIN_SINGLE_CHAR_GUESSES: f64 = 10.0;
+const MIN_MULTI_CHAR_GUESSES: f64 = 50.0;
+
+/// Extra guesses an attacker spends for every further pattern in a password.
+const GUESSES_PER_EXTRA_PATTERN: f64 = 10_000.0;
+
+/// Years this close to the current one all count as equally likely.
+const MIN_YEAR_SPACE: i64 = 20;
+
+/// The oldest and newest years that dates are recognized in.
+const DATE_MIN_YEAR: i64 = 1000;
+const DATE_MAX_YEAR: i64 = 2050;
+
+/// How many plain-letter readings of a token with ambiguous substitutions are tried.
+const MAX_L33T_READINGS: usize = 16;
+
+/// How far to the right each keyboard row starts, in key widths, on a staggered keyboard.
+const ROW_OFFSETS: [f64; 4] = [0.0, 1.5, 1.75, 2.25];
+
+/// The most common passwords in public breach lists, most common first.
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456", "password", "123456789", "12345678", "12345", "qwerty", "1234567", "111111", "123123", "abc123",
+    "1234567890", "password1", "iloveyou", "1q2w3e4r", "000000", "qwerty123", "zaq12wsx", "dragon", "sunshine",
+    "princess", "letmein", "654321", "monkey", "27653", "1qaz2wsx", "123321", "qwertyuiop", "superman", "asdfghjkl",
+    "football", "baseball", "welcome", "admin", "login", "master", "hello", "freedom", "whatever", "qazwsx",
+    "trustno1", "starwars", "passw0rd", "shadow", "michael", "jennifer", "jordan", "hunter", "ashley", "buster",
+    "soccer", "harley", "batman", "andrew", "tigger", "charlie", "robert", "thomas", "hockey", "ranger", "daniel",
+    "hannah", "maggie", "jessica", "pepper", "summer", "cheese", "computer", "corvette", "mercedes", "matrix",
+    "cookie", "secret", "flower", "chocolate", "internet", "google", "samsung", "killer", "pokemon", "naruto",
+    "access", "mustang", "696969", "121212", "555555", "7777777", "987654321", "159753", "112233", "666666",
+    "11111111", "123qwe", "1q2w3e", "zxcvbnm", "asdf", "qwer", "pass", "test", "guest", "changeme", "default",
+    "root", "toor", "love", "iloveu", "azerty", "solo", "loveme", "michelle", "nicole", "daniel1", "lovely",
+    "babygirl", "qwertyu", "12341234", "aa123456", "password123", "admin123", "welcome1", "p@ssw0rd", "abcd1234",
+];
+
+/// Symbols and digits commonly typed in place of letters, with the letters they stand for.
+pub const L33T_TABLE: &[(char, &str)] = &[
+    ('4', "a"), ('@', "a"), ('8', "b"), ('(', "c"), ('{', "c"), ('[', "c"), ('<', "c"), ('3', "e"), ('6', "g"),
+    ('9', "g"), ('1', "il"), ('!', "i"), ('|', "il"), ('0', "o"), ('$', "s"), ('5', "s"), ('+', "t"), ('7', "lt"),
+    ('%', "x"), ('2', "z"),
+];
+
+/// Guessing speeds for the crack time estimates, in guesses per second.
+const ATTACK_SCENARIOS: [(&str, f64); 4] = [
+    ("online, throttled (100 per hour)", 100.0 / 3600.0),
+    ("online, unthrottled (10 per second)", 10.0),
+    ("offline, slow hash (10k per second)", 1e4),
+    ("offline, fast hash (10 billion per second)", 1e10),
+];
+
+/// The kind of pattern part of a password matched.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    /// A word from the wordlist or, with its rank, one of the most common passwords.
+    Dictionary { common_rank: Option<usize>, l33t: bool, reversed: bool, uppercase: bool },
+    /// Neighbouring keys, with the number of changes of direction and shifted keys.
+    Keyboard { turns: usize, shifted: usize },
+    /// The same characters several times over, like `aaa` or `abcabc`.
+    Repeat { times: usize },
+    /// Characters at even steps, like `abc`, `2468` or `zyx`.
+    Sequence { ascending: bool },
+    /// A year close to the current one.
+    Year,
+    /// A day, month and year, with or without separators.
+    Date,
+    /// Characters that match no pattern.
+    Bruteforce,
+}
+
+impl Pattern {
+    /// A short description for reports.
+    fn describe(&self) -> String {
+        match self {
+            Pattern::Dictionary { common_rank, l33t, reversed, uppercase } => {
+                let mut text = match common_rank {
+                    Some(rank) => format!("common password (#{})", rank),
+                    None => "dictionary word".to_string(),
+                };
+                for (applies, detail) in [(*l33t, "substitutions"), (*reversed, "reversed"), (*uppercase, "capitals")] {
+                    if applies {
+                        text.push_str(", ");
+                        text.push_str(detail);
+                    }
+                }
+                text
+            }
+            Pattern::Keyboard { turns, .. } if *turns > 1 => format!("keyboard pattern with {} turns", turns - 1),
+            Pattern::Keyboard { .. } => "keyboard pattern".to_string(),
+            Pattern::Repeat { times } => format!("repeated {} times", times),
+            Pattern::Sequence { ascending: true } => "ascending sequence".to_string(),
+            Pattern::Sequence { ascending: false } => "descending sequence".to_string(),
+            Pattern::Year => "recent year".to_string(),
+            Pattern::Date => "date".to_string(),
+            Pattern::Bruteforce => "no pattern".to_string(),
+        }
+    }
+}
+
+/// A pattern found at characters `start..=end` of the password.
+#[derive(Clone, Debug)]
+pub struct PatternMatch {
+    pub start: usize,
+    pub end: usize,
+    pub pattern: Pattern,
+    /// log2 of the guesses needed to find this part on its own.
+    pub bits: f64,
+}
+
+/// The estimated strength of a password chosen by a person.
+#[derive(Clone, Debug)]
+pub struct StrengthReport {
+    /// The number of characters in the password.
+    pub length: usize,
+    /// log2 of the estimated number of guesses to find the password.
+    pub bits: f64,
+    /// 0 (very weak) to 4 (strong).
+    pub score: usize,
+    /// The patterns the estimate is built from, in order.
+    pub matches: Vec<PatternMatch>,
+    pub warnings: Vec<String>,
+    pub suggestions: Vec<String>,
+}
+
+impl StrengthReport {
+    /// A word for the score.
+    pub fn score_label(&self) -> &'static str {
+        ["very weak", "weak", "fair", "good", "strong"][self.score]
+    }
+}
+
+impl fmt::Display for StrengthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Strength: {} of 4 ({}) for {} characters, about 10^{:.1} guesses ({:.1} bits)",
+            self.score,
+            self.score_label(),
+            self.length,
+            self.bits * 2f64.log10(),
+            self.bits
+        )?;
+        write!(f, "\nTime to crack:")?;
+        for (scenario, rate) in ATTACK_SCENARIOS {
+            write!(f, "\n  - {}: {}", scenario, describe_duration(self.bits - rate.log2()))?;
+        }
+        if !self.matches.is_empty() {
+            write!(f, "\nPatterns:")?;
+        }
+        for m in &self.matches {
+            let place = match m.end > m.start {
+                true => format!("characters {}-{}", m.start + 1, m.end + 1),
+                false => format!("character {}", m.start + 1),
+            };
+            write!(f, "\n  - {}: {} ({:.1} bits)", place, m.pattern.describe(), m.bits)?;
+        }
+        for warning in &self.warnings {
+            write!(f, "\nWarning: {}", warning)?;
+        }
+        for suggestion in &self.suggestions {
+            write!(f, "\nSuggestion: {}", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describes how long `2^bits` seconds is.
+fn describe_duration(bits: f64) -> String {
+    const UNITS: [(&str, f64); 6] = [
+        ("second", 1.0),
+        ("minute", 60.0),
+        ("hour", 3600.0),
+        ("day", 86_400.0),
+        ("month", 86_400.0 * 31.0),
+        ("year", 86_400.0 * 31.0 * 12.0),
+    ];
+    if bits >= (UNITS[5].1 * 100.0).log2() {
+        return "centuries".to_string();
+    }
+    let seconds = bits.exp2();
+    if seconds < 1.0 {
+        return "less than a second".to_string();
+    }
+    let (unit, size) = UNITS.iter().rev().find(|(_, size)| seconds >= *size).unwrap();
+    let count = (seconds / size).round();
+    format!("{} {}{}", count, unit, if count == 1.0 { "" } else { "s" })
+}
+
+/// The words an attacker tries first: the most common passwords, then the wordlist.
+struct Dictionary {
+    common: HashMap<&'static str, usize>,
+    words: HashSet<String>,
+    /// The longest entry, in characters.
+    longest: usize,
+}
+
+impl Dictionary {
+    fn new(words: &[String]) -> Self {
+        let common: HashMap<&'static str, usize> =
+            COMMON_PASSWORDS.iter().enumerate().map(|(i, p)| (*p, i + 1)).collect();
+        let words: HashSet<String> = words.iter().map(|w| w.to_lowercase()).collect();
+        let longest = common.keys().map(|p| p.len()).chain(words.iter().map(|w| w.chars().count())).max().unwrap_or(0);
+        Self { common, words, longest }
+    }
+
+    /// The guesses to reach a lowercase token, and its rank if it is a common password.
+    fn lookup(&self, token: &str) -> Option<(f64, Option<usize>)> {
+        match self.common.get(token) {
+            Some(&rank) => Some((rank as f64, Some(rank))),
+            None if self.words.contains(token) => Some((self.words.len() as f64, None)),
+            None => None,
+        }
+    }
+}
+
+/// Estimates how many guesses an attacker needs to find a password, in the
+/// manner of zxcvbn: the password is split into the sequence of patterns
+/// (dictionary words, keyboard walks, repeats, sequences, dates and random
+/// stretches) that is cheapest to guess, with words taken from `words`. Years
+/// and dates are judged by how far they are from `year`, usually `current_year()`.
+pub fn check_password_strength(password: &str, words: &[String], year: i64) -> StrengthReport {
+    let dictionary = Dictionary::new(words);
+    let units = graphemes(password);
+    let (bits, matches) = guesses_by_stretch(&units, &dictionary, year);
+
+    let log10 = bits * 2f64.log10();
+    let score = [3.0, 6.0, 8.0, 10.0].iter().filter(|&&limit| log10 >= limit).count();
+    let (warnings, suggestions) = feedback(&matches, units.len(), score);
+    StrengthReport { length: units.len(), bits, score, matches, warnings, suggestions }
+}
+
+/// Adds up the guesses for a password of any length, searching it for patterns
+/// `MAX_CHECKED_LENGTH` characters at a time. Where a stretch carries on
+/// repeating the characters before it, only its period and length are guessed.
+fn guesses_by_stretch(units: &[String], dictionary: &Dictionary, year: i64) -> (f64, Vec<PatternMatch>) {
+    let (mut bits, mut matches) = (0.0, Vec::new());
+    let mut start = 0;
+    while start < units.len() {
+        if let Some((period, length)) = continuation(units, start) {
+            let end = start + length - 1;
+            let repeat_bits = (period as f64).log2() + (length as f64).log2();
+            let pattern = Pattern::Repeat { times: length.div_ceil(period) };
+            matches.push(PatternMatch { start, end, pattern, bits: repeat_bits });
+            bits += repeat_bits;
+            start = end + 1;
+            continue;
+        }
+        let mut end = units.len().min(start + MAX_CHECKED_LENGTH);
+        // A repeat that runs on past the search is cut off at its start and counted as one continuation.
+        if end < units.len() {
+            let runs_on = |cut: usize| {
+                (1..=cut.min(MAX_CHECKED_LENGTH)).any(|period| (cut..=end).all(|i| units[i] == units[i - period]))
+            };
+            end = (start + 1..end).find(|&cut| runs_on(cut)).unwrap_or(end);
+        }
+        let (stretch_bits, stretch) = most_guessable(&units[start..end], dictionary, year);
+        matches.extend(stretch.into_iter().map(|m| PatternMatch { start: m.start + start, end: m.end + start, ..m }));
+        bits += stretch_bits;
+        start = end;
+    }
+    (bits, matches)
+}
+
+/// The period and length of the longest stretch from `start` that repeats one of
+/// the `MAX_CHECKED_LENGTH` characters before it, if it is long enough to count.
+fn continuation(units: &[String], start: usize) -> Option<(usize, usize)> {
+    (1..=start.min(MAX_CHECKED_LENGTH))
+        .map(|period| {
+            let length = (start..units.len()).take_while(|&i| units[i] == units[i - period]).count();
+            (period, length)
+        })
+        .filter(|&(_, length)| length >= MIN_CONTINUATION)
+        .max_by_key(|&(period, length)| (length, Reverse(period)))
+}
+
+/// Explains the weaknesses of a password that scores 2 or less.
+fn feedback(matches: &[PatternMatch], length: usize, score: usize) -> (Vec<String>, Vec<String>) {
+    if length == 0 {
+        return (vec!["The password is empty.".to_string()], Vec::new());
+    }
+    if score > 2 {
+        return (Vec::new(), Vec::new());
+    }
+    let mut warnings: Vec<String> = Vec::new();
+    let mut warn = |warning: &str| {
+        if !warnings.iter().any(|w| w == warning) {
+            warnings.push(warning.to_string());
+        }
+    };
+    for m in matches {
+        let whole = m.start == 0 && m.end + 1 == length;
+        match &m.pattern {
+            Pattern::Dictionary { common_rank, l33t, reversed, uppercase } => {
+                match (common_rank.is_some(), whole) {
+                    (true, true) => warn("This is one of the most common passwords."),
+                    (true, false) => warn("It contains one of the most common passwords."),
+                    (false, true) => warn("A single dictionary word is easy to guess."),
+                    (false, false) => warn("Dictionary words are easy to guess, especially short ones."),
+                }
+                if *l33t {
+                    warn("Predictable substitutions like '@' for 'a' don't help much.");
+                }
+                if *reversed {
+                    warn("Reversed words aren't much harder to guess.");
+                }
+                if *uppercase {
+                    warn("Capital letters in predictable places don't help much.");
+                }
+            }
+            Pattern::Keyboard { .. } => warn("Keyboard patterns like qwerty or 1qaz are easy to guess."),
+            Pattern::Repeat { .. } => warn("Repeats like aaa or abcabc are easy to guess."),
+            Pattern::Sequence { .. } => warn("Sequences like abc or 6543 are easy to guess."),
+            Pattern::Year | Pattern::Date => warn("Dates and recent years are easy to guess."),
+            Pattern::Bruteforce => {}
+        }
+    }
+    let suggestions = vec![
+        "Use a generated password, or a passphrase of four or more random words.".to_string(),
+        "Add characters or words that are not part of a pattern.".to_string(),
+    ];
+    (warnings, suggestions)
+}
+
+/// One entry of the search for the cheapest sequence of patterns.
+struct Step {
+    /// log2 of the full guess estimate for the sequence ending with `last`.
+    total: f64,
+    /// log2 of the product of the patterns' guesses.
+    product: f64,
+    last: PatternMatch,
+}
+
+/// Finds the sequence of patterns covering the password that needs the fewest
+/// guesses, returning log2 of that number and the patterns.
+fn most_guessable(units: &[String], dictionary: &Dictionary, year: i64) -> (f64, Vec<PatternMatch>) {
+    let n = units.len();
+    if n == 0 {
+        return (0.0, Vec::new());
+    }
+    let mut by_end: Vec<Vec<PatternMatch>> = vec![Vec::new(); n];
+    for mut m in find_matches(units, dictionary, year) {
+        m.bits = with_minimum(m.bits, m.start, m.end, n);
+        by_end[m.end].push(m);
+    }
+
+    // optimal[k][l]: the best sequence of l patterns covering characters 0..=k.
+    let mut optimal: Vec<HashMap<usize, Step>> = (0..n).map(|_| HashMap::new()).collect();
+    for (k, ending) in by_end.iter().enumerate() {
+        for m in ending {
+            if m.start == 0 {
+                consider(&mut optimal, m.clone(), 1);
+            } else {
+                let counts: Vec<usize> = optimal[m.start - 1].keys().copied().collect();
+                for l in counts {
+                    consider(&mut optimal, m.clone(), l + 1);
+                }
+            }
+        }
+        // Random stretches never follow each other; one longer stretch is always cheaper.
+        consider(&mut optimal, bruteforce_match(0, k, n), 1);
+        for i in 1..=k {
+            let counts: Vec<usize> = optimal[i - 1]
+                .iter()
+                .filter(|(_, step)| step.last.pattern != Pattern::Bruteforce)
+                .map(|(&l, _)| l)
+                .collect();
+            for l in counts {
+                consider(&mut optimal, bruteforce_match(i, k, n), l + 1);
+            }
+        }
+    }
+
+    let (mut l, best) = optimal[n - 1]
+        .iter()
+        .min_by(|a, b| a.1.total.total_cmp(&b.1.total))
+        .map(|(&l, step)| (l, step.total))
+        .unwrap();
+    let mut sequence = Vec::new();
+    let mut k = n - 1;
+    while l > 0 {
+        let m = optimal[k][&l].last.clone();
+        l -= 1;
+        let start = m.start;
+        sequence.push(m);
+        if start == 0 {
+            break;
+        }
+        k = start - 1;
+    }
+    sequence.reverse();
+    (best, sequence)
+}
+
+/// Records `m` as the last of `l` patterns if that beats what is known for its end.
+fn consider(optimal: &mut [HashMap<usize, Step>], m: PatternMatch, l: usize) {
+    let product = match l {
+        1 => m.bits,
+        _ => m.bits + optimal[m.start - 1][&(l - 1)].product,
+    };
+    // l! orderings of the patterns, plus a minimum cost for each pattern beyond the first.
+    let total = log2_sum(log2_factorial(l) + product, (l - 1) as f64 * GUESSES_PER_EXTRA_PATTERN.log2());
+    if optimal[m.end].iter().any(|(&other, step)| other <= l && step.total <= total) {
+        return;
+    }
+    let end = m.end;
+    optimal[end].insert(l, Step { total, product, last: m });
+}
+
+/// log2 of `2^a + 2^b`.
+fn log2_sum(a: f64, b: f64) -> f64 {
+    let (high, low) = if a > b { (a, b) } else { (b, a) };
+    high + (1.0 + (low - high).exp2()).log2()
+}
+
+/// Raises the guesses of a pattern inside a longer password to the minimum for its length.
+fn with_minimum(bits: f64, start: usize, end: usize, length: usize) -> f64 {
+    if start == 0 && end + 1 == length {
+        return bits;
+    }
+    let minimum = if start == end { MIN_SINGLE_CHAR_GUESSES } else { MIN_MULTI_CHAR_GUESSES };
+    bits.max(minimum.log2())
+}
+
+/// A random stretch of characters `start..=end`.
+fn bruteforce_match(start: usize, end: usize, length: usize) -> PatternMatch {
+    let bits = (end - start + 1) as f64 * BRUTEFORCE_CARDINALITY.log2();
+    // A little above the minimum, so a pattern of equal cost is preferred.
+    let bits = with_minimum(bits, start, end, length).max(bits + if start == end { 0.1 } else { 0.0 });
+    PatternMatch { start, end, pattern: Pattern::Bruteforce, bits }
+}
+
+/// Every pattern found anywhere in the password.
+fn find_matches(units: &[String], dictionary: &Dictionary, year: i64) -> Vec<PatternMatch> {
+    let mut matches = dictionary_matches(units, dictionary);
+    matches.extend(keyboard_matches(units));
+    matches.extend(repeat_matches(units, dictionary, year));
+    matches.extend(sequence_matches(units));
+    matches.extend(date_matches(units, year));
+    matches
+}
+
+/// Words and common passwords, as typed, reversed or with l33t substitutions.
+fn dictionary_matches(units: &[String], dictionary: &Dictionary) -> Vec<PatternMatch> {
+    let n = units.len();
+    let mut matches = Vec::new();
+    for start in 0..n {
+        for end in start..n.min(start + dictionary.longest) {
+            let token = units[start..=end].concat();
+            let lower = token.to_lowercase();
+            let uppercase = lower != token;
+            let case_bits = uppercase_variations(&token).log2();
+            let mut push = |guesses: f64, common_rank: Option<usize>, l33t: bool, reversed: bool, extra: f64| {
+                let pattern = Pattern::Dictionary { common_rank, l33t, reversed, uppercase };
+                matches.push(PatternMatch { start, end, pattern, bits: guesses.log2() + case_bits + extra });
+            };
+            if let Some((guesses, rank)) = dictionary.lookup(&lower) {
+                push(guesses, rank, false, false, 0.0);
+            }
+            let reversed: String = units[start..=end].iter().rev().map(|u| u.to_lowercase()).collect();
+            if reversed != lower {
+                if let Some((guesses, rank)) = dictionary.lookup(&reversed) {
+                    push(guesses, rank, false, true, 1.0);
+                }
+            }
+            for (plain, substitutions) in l33t_readings(&units[start..=end]) {
+                if let Some((guesses, rank)) = dictionary.lookup(&plain) {
+                    push(guesses, rank, true, false, l33t_variations(&lower, &substitutions).log2());
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// How many ways the letters of a token could have been capitalized, as zxcvbn counts them:
+/// one for all lowercase, two for a capital at either end or all capitals.
+fn uppercase_variations(token: &str) -> f64 {
+    let upper = token.chars().filter(|c| c.is_uppercase()).count();
+    let lower = token.chars().filter(|c| c.is_lowercase()).count();
+    let first_upper = token.chars().next().is_some_and(char::is_uppercase);
+    let last_upper = token.chars().last().is_some_and(char::is_uppercase);
+    if upper == 0 {
+        return 1.0;
+    }
+    if lower == 0 || (upper == 1 && (first_upper || last_upper)) {
+        return 2.0;
+    }
+    (1..=upper.min(lower)).map(|i| binomial(upper + lower, i)).sum()
+}
+
+/// The plain-letter readings of a token that contains l33t substitutions, with
+/// the substitutions made as (symbol, letter) pairs.
+fn l33t_readings(units: &[String]) -> Vec<(String, Vec<(char, char)>)> {
+    let letters_for = |unit: &String| {
+        single_char(unit).and_then(|c| L33T_TABLE.iter().find(|(symbol, _)| *symbol == c)).map(|(c, l)| (*c, *l))
+    };
+    if !units.iter().any(|u| letters_for(u).is_some()) {
+        return Vec::new();
+    }
+    let mut readings: Vec<(String, Vec<(char, char)>)> = vec![(String::new(), Vec::new())];
+    for unit in units {
+        readings = match letters_for(unit) {
+            Some((symbol, letters)) => readings
+                .iter()
+                .flat_map(|(text, subs)| {
+                    letters.chars().map(move |letter| {
+                        let mut subs = subs.clone();
+                        if !subs.contains(&(symbol, letter)) {
+                            subs.push((symbol, letter));
+                        }
+                        (format!("{}{}", text, letter), subs)
+                    })
+                })
+                .take(MAX_L33T_READINGS)
+                .collect(),
+            None => readings.into_iter().map(|(text, subs)| (text + &unit.to_lowercase(), subs)).collect(),
+        };
+    }
+    readings
+}
+
+/// How many ways the substitutions could have been applied, as zxcvbn counts them.
+fn l33t_variations(token: &str, substitutions: &[(char, char)]) -> f64 {
+    let mut variations = 1.0;
+    for &(symbol, letter) in substitutions {
+        let substituted = token.chars().filter(|&c| c == symbol).count();
+        let plain = token.chars().filter(|&c| c == letter).count();
+        variations *= match substituted.min(plain) {
+            0 => 2.0,
+            most => (1..=most).map(|i| binomial(substituted + plain, i)).sum(),
+        };
+    }
+    variations
+}
+
+/// The keyboard row, horizontal position and shift state of a key.
+fn key_coordinates(unit: &str) -> Option<(usize, f64, bool)> {
+    let c = single_char(unit)?;
+    let (row, col) = key_position(c)?;
+    Some((row, col as f64 + ROW_OFFSETS[row], KEYBOARD_ROWS[row + 4].contains(c)))
+}
+
+/// The direction from one key to a neighbouring key, or `None` if they are not neighbours.
+fn key_direction(a: &str, b: &str) -> Option<(i32, i32)> {
+    let (row_a, x_a, _) = key_coordinates(a)?;
+    let (row_b, x_b, _) = key_coordinates(b)?;
+    let rows = row_b as i32 - row_a as i32;
+    let dx = x_b - x_a;
+    let neighbours = match rows {
+        0 => dx.abs() == 1.0,
+        -1 | 1 => dx.abs() < 1.0,
+        _ => false,
+    };
+    neighbours.then_some((rows, dx.signum() as i32))
+}
+
+/// The number of unshifted keys and the average number of neighbours per key.
+fn keyboard_shape() -> (f64, f64) {
+    let keys: Vec<String> = KEYBOARD_ROWS[..4].iter().flat_map(|row| row.chars().map(String::from)).collect();
+    let links: usize = keys.iter().map(|a| keys.iter().filter(|b| key_direction(a, b).is_some()).count()).sum();
+    (keys.len() as f64, links as f64 / keys.len() as f64)
+}
+
+/// Runs of three or more neighbouring keys, like `qwerty`, `1qaz` or `zxcdsa`.
+fn keyboard_matches(units: &[String]) -> Vec<PatternMatch> {
+    let (starts, degree) = keyboard_shape();
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + 2 < units.len() {
+        let mut end = start;
+        let mut turns = 0;
+        let mut last_direction = None;
+        while end + 1 < units.len() {
+            let Some(direction) = key_direction(&units[end], &units[end + 1]) else {
+                break;
+            };
+            if last_direction != Some(direction) {
+                turns += 1;
+                last_direction = Some(direction);
+            }
+            end += 1;
+        }
+        if end - start >= 2 {
+            let length = end - start + 1;
+            let mut guesses: f64 = (2..=length)
+                .flat_map(|i| (1..=turns.min(i - 1)).map(move |j| (i, j)))
+                .map(|(i, j)| binomial(i - 1, j - 1) * starts * degree.powi(j as i32))
+                .sum();
+            let shifted = units[start..=end].iter().filter(|u| key_coordinates(u).is_some_and(|k| k.2)).count();
+            let unshifted = length - shifted;
+            if shifted > 0 {
+                guesses *= match unshifted {
+                    0 => 2.0,
+                    _ => (1..=shifted.min(unshifted)).map(|i| binomial(length, i)).sum(),
+                };
+            }
+            let pattern = Pattern::Keyboard { turns, shifted };
+            matches.push(PatternMatch { start, end, pattern, bits: guesses.log2() });
+            start = end;
+        } else {
+            start += 1;
+        }
+    }
+    matches
+}
+
+/// Runs of a repeated base, like `aaaa` or `abcabc`, priced as the base times the repeats.
+fn repeat_matches(units: &[String], dictionary: &Dictionary, year: i64) -> Vec<PatternMatch> {
+    let n = units.len();
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + 1 < n {
+        // The longest run from here, with the shortest base that gives it.
+        let mut best: Option<(usize, usize)> = None;
+        for base in 1..=(n - start) / 2 {
+            let pattern = &units[start..start + base];
+            let times = (1..)
+                .take_while(|&k| (k + 1) * base <= n - start && &units[start + k * base..][..base] == pattern)
+                .count()
+                + 1;
+            if times >= 2 && best.is_none_or(|(b, t)| times * base > b * t) {
+                best = Some((base, times));
+            }
+        }
+        match best {
+            Some((base, times)) => {
+                let (base_bits, _) = most_guessable(&units[start..start + base], dictionary, year);
+                let end = start + base * times - 1;
+                let bits = base_bits + (times as f64).log2();
+                matches.push(PatternMatch { start, end, pattern: Pattern::Repeat { times }, bits });
+                start = end + 1;
+            }
+            None => start += 1,
+        }
+    }
+    matches
+}
+
+/// The kind of character a sequence may run through: lowercase, uppercase, digit or other.
+fn sequence_kind(c: char) -> u8 {
+    match c {
+        'a'..='z' => 0,
+        'A'..='Z' => 1,
+        '0'..='9' => 2,
+        _ => 3,
+    }
+}
+
+/// Runs of three or more characters with the same small step, like `abc`, `2468` or `zyx`.
+fn sequence_matches(units: &[String]) -> Vec<PatternMatch> {
+    let chars: Vec<Option<char>> = units.iter().map(|u| single_char(u)).collect();
+    let step = |i: usize| match (chars[i], chars[i + 1]) {
+        (Some(a), Some(b)) if sequence_kind(a) == sequence_kind(b) => Some(b as i64 - a as i64),
+        _ => None,
+    };
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + 2 < units.len() {
+        let delta = match step(start) {
+            Some(delta) if delta != 0 && delta.abs() <= 5 => delta,
+            _ => {
+                start += 1;
+                continue;
+            }
+        };
+        let mut end = start + 1;
+        while end + 1 < units.len() && step(end) == Some(delta) {
+            end += 1;
+        }
+        if end - start < 2 {
+            start += 1;
+            continue;
+        }
+        let first = chars[start].unwrap();
+        let base: f64 = match first {
+            'a' | 'A' | 'z' | 'Z' | '0' | '1' | '9' => 4.0,
+            c if c.is_ascii_digit() => 10.0,
+            _ => 26.0,
+        };
+        let direction = if delta > 0 { 1.0 } else { 2.0 };
+        let bits = (base * direction * (end - start + 1) as f64).log2();
+        matches.push(PatternMatch { start, end, pattern: Pattern::Sequence { ascending: delta > 0 }, bits });
+        start = end;
+    }
+    matches
+}
+
+/// The current year, for judging how guessable years and dates are.
+pub fn current_year() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| 1970 + d.as_secs() as i64 / 31_556_952).unwrap_or(2025)
+}
+
+/// The guesses for a year seen in `now`: every year within `MIN_YEAR_SPACE` is as likely as the next.
+fn year_space(year: i64, now: i64) -> f64 {
+    (year - now).abs().max(MIN_YEAR_SPACE) as f64
+}
+
+/// Recent years (`19xx` and `20xx`) and dates such as `1987-05-13`, `13.5.87` or `051387`,
+/// judged from the point of view of the year `now`.
+fn date_matches(units: &[String], now: i64) -> Vec<PatternMatch> {
+    let chars: Vec<char> = units.iter().map(|u| single_char(u).unwrap_or('\0')).collect();
+    let n = chars.len();
+    let digits = |a: usize, b: usize| chars[a..b].iter().all(char::is_ascii_digit);
+    let number = |a: usize, b: usize| chars[a..b].iter().collect::<String>().parse::<i64>().unwrap_or(-1);
+    let mut matches = Vec::new();
+
+    for start in 0..n {
+        if start + 4 <= n && digits(start, start + 4) && matches!(&chars[start..start + 2], ['1', '9'] | ['2', '0']) {
+            let bits = year_space(number(start, start + 4), now).log2();
+            matches.push(PatternMatch { start, end: start + 3, pattern: Pattern::Year, bits });
+        }
+
+        // Dates written without separators: split the digits into day, month and year.
+        for length in 4..=8 {
+            if start + length > n || !digits(start, start + length) {
+                break;
+            }
+            let splits: &[(usize, usize)] = match length {
+                4 => &[(1, 2), (2, 3)],
+                5 => &[(1, 3), (2, 3)],
+                6 => &[(1, 2), (2, 4), (4, 5)],
+                7 => &[(1, 3), (2, 3), (4, 5), (4, 6)],
+                _ => &[(2, 4), (4, 6)],
+            };
+            let best = splits
+                .iter()
+                .filter_map(|&(k, l)| {
+                    let end = start + length;
+                    date_year([number(start, start + k), number(start + k, start + l), number(start + l, end)])
+                })
+                .min_by_key(|year| (year - now).abs());
+            if let Some(year) = best {
+                let bits = (year_space(year, now) * 365.0).log2();
+                matches.push(PatternMatch { start, end: start + length - 1, pattern: Pattern::Date, bits });
+            }
+        }
+
+        // Dates with the same separator twice, like 13.5.1987 or 1987/05/13.
+        for length in 6..=10 {
+            if start + length > n {
+                break;
+            }
+            let token = &chars[start..start + length];
+            let separators: Vec<usize> = (0..length).filter(|&i| !token[i].is_ascii_digit()).collect();
+            let [first, second] = separators[..] else {
+                continue;
+            };
+            let separator = token[first];
+            if token[second] != separator || !" /\\_.-".contains(separator) {
+                continue;
+            }
+            let parts = [(0, first), (first + 1, second), (second + 1, length)];
+            if parts.iter().any(|&(a, b)| b == a || b - a > 4) || second - first - 1 > 2 {
+                continue;
+            }
+            let values = parts.map(|(a, b)| number(start + a, start + b));
+            if let Some(year) = date_year(values) {
+                let bits = (year_space(year, now) * 365.0 * 4.0).log2();
+                matches.push(PatternMatch { start, end: start + length - 1, pattern: Pattern::Date, bits });
+            }
+        }
+    }
+    matches
+}
+
+/// Reads three numbers as a day, month and year in any common order, returning
+/// the four-digit year if they make a plausible date.
+fn date_year(parts: [i64; 3]) -> Option<i64> {
+    if parts[1] > 31 || parts[1] <= 0 {
+        return None;
+    }
+    let over_31 = parts.iter().filter(|&&p| p > 31).count();
+    let over_12 = parts.iter().filter(|&&p| p > 12).count();
+    let under_1 = parts.iter().filter(|&&p| p <= 0).count();
+    let out_of_range = parts.iter().any(|&p| (99 < p && p < DATE_MIN_YEAR) || p > DATE_MAX_YEAR);
+    if out_of_range || over_31 >= 2 || over_12 == 3 || under_1 >= 2 {
+        return None;
+    }
+    let day_month = |a: i64, b: i64| {
+        ((1..=31).contains(&a) && (1..=12).contains(&b)) || ((1..=31).contains(&b) && (1..=12).contains(&a))
+    };
+    let candidates = [(parts[2], parts[0], parts[1]), (parts[0], parts[1], parts[2])];
+    for (year, a, b) in candidates {
+        if (DATE_MIN_YEAR..=DATE_MAX_YEAR).contains(&year) {
+            return day_month(a, b).then_some(year);
+        }
+    }
+    for (year, a, b) in candidates {
+        if day_month(a, b) {
+            return Some(match year {
+                0..=50 => 2000 + year,
+                51..=99 => 1900 + year,
+                _ => year,
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    const YEAR: i64 = 2026;
+
+    /// The patterns of the cheapest reading of a password, with their bits.
+    fn reading(password: &str, words: &[&str]) -> Vec<(Pattern, f64)> {
+        let words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        check_password_strength(password, &words, YEAR).matches.into_iter().map(|m| (m.pattern, m.bits)).collect()
+    }
+
+    fn patterns(password: &str, words: &[&str]) -> Vec<Pattern> {
+        reading(password, words).into_iter().map(|(pattern, _)| pattern).collect()
+    }
+
+    fn word(common_rank: Option<usize>, l33t: bool, reversed: bool, uppercase: bool) -> Pattern {
+        Pattern::Dictionary { common_rank, l33t, reversed, uppercase }
+    }
+
+    #[test]
+    fn dictionary_words() {
+        let words = ["correct", "horse", "battery", "staple"];
+        let plain = word(None, false, false, false);
+        assert_eq!(patterns("correcthorse", &words), [plain.clone(), plain]);
+        assert_eq!(patterns("Battery", &words), [word(None, false, false, true)]);
+        assert_eq!(patterns("elpats", &words), [word(None, false, true, false)]);
+        // One of four words, then twice that for the capital.
+        assert_eq!(reading("Staple", &words)[0].1, 3.0);
+    }
+
+    #[test]
+    fn common_passwords() {
+        assert_eq!(reading("password", &[]), [(word(Some(2), false, false, false), 1.0)]);
+        assert_eq!(patterns("dragon", &[]), [word(Some(18), false, false, false)]);
+        assert_eq!(check_password_strength("password", &[], YEAR).score, 0);
+    }
+
+    #[test]
+    fn l33t_substitutions() {
+        assert_eq!(patterns("dr4g0n", &[]), [word(Some(18), true, false, false)]);
+        assert_eq!(patterns("h0r$e", &["horse"]), [word(None, true, false, false)]);
+    }
+
+    #[test]
+    fn keyboard_walks() {
+        assert_eq!(patterns("sdfghj", &[]), [Pattern::Keyboard { turns: 1, shifted: 0 }]);
+        // Right, up and left: three directions.
+        assert_eq!(patterns("zxcdsa", &[]), [Pattern::Keyboard { turns: 3, shifted: 0 }]);
+        assert_eq!(patterns("SDFGHJ", &[]), [Pattern::Keyboard { turns: 1, shifted: 6 }]);
+    }
+
+    #[test]
+    fn repeats() {
+        assert_eq!(patterns("xkcdxkcdxkcd", &[]), [Pattern::Repeat { times: 3 }]);
+        assert_eq!(patterns("zzzzzz", &[]), [Pattern::Repeat { times: 6 }]);
+    }
+
+    #[test]
+    fn sequences() {
+        assert_eq!(patterns("lmnop", &[]), [Pattern::Sequence { ascending: true }]);
+        assert_eq!(patterns("97531", &[]), [Pattern::Sequence { ascending: false }]);
+    }
+
+    #[test]
+    fn years_depend_on_the_current_year() {
+        let bits = |password: &str, year: i64| {
+            let report = check_password_strength(password, &[], year);
+            assert_eq!(report.matches.iter().map(|m| &m.pattern).collect::<Vec<_>>(), [&Pattern::Year]);
+            report.matches[0].bits
+        };
+        assert_eq!(bits("1999", YEAR), 27f64.log2());
+        assert_eq!(bits("1999", 2000), 20f64.log2());
+        assert_eq!(bits("2031", YEAR), 20f64.log2());
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(reading("13.05.1987", &[]), [(Pattern::Date, (39.0 * 365.0 * 4.0f64).log2())]);
+        assert_eq!(patterns("051387", &[]), [Pattern::Date]);
+        assert_eq!(patterns("1987-05-13", &[]), [Pattern::Date]);
+        assert_eq!(patterns("45.67.8910", &[]).last(), Some(&Pattern::Bruteforce));
+    }
+
+    #[test]
+    fn long_repeats_stay_weak() {
+        for password in ["a".repeat(300), "abc".repeat(100), "Passw0rd!".repeat(40), "x".repeat(5000)] {
+            let report = check_password_strength(&password, &[], 2026);
+            assert!(report.score <= 2, "{} scored {}", &password[..12], report.score);
+        }
+    }
+
+    #[test]
+    fn repeat_after_random_start_adds_little() {
+        let pool: Vec<char> = ('!'..='~').collect();
+        let mut rng = StdRng::seed_from_u64(3);
+        let random: String = (0..120).map(|_| *pool.choose(&mut rng).unwrap()).collect();
+        let alone = check_password_strength(&random, &[], 2026).bits;
+        let padded = check_password_strength(&(random.clone() + &"x".repeat(200)), &[], 2026).bits;
+        assert!(padded - alone < 20.0, "{} bits added", padded - alone);
+    }
+
+    #[test]
+    fn long_random_passwords_stay_strong() {
+        let pool: Vec<char> = ('!'..='~').collect();
+        let mut rng = StdRng::seed_from_u64(5);
+        let password: String = (0..300).map(|_| *pool.choose(&mut rng).unwrap()).collect();
+        let report = check_password_strength(&password, &[], 2026);
+        assert_eq!(report.score, 4);
+        assert!(report.bits > 600.0);
+    }
+}
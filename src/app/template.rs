@@ -0,0 +1,194 @@
+use crate::app::state::CharacterSets;
+use crate::app::unicode::graphemes;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use unicode_normalization::UnicodeNormalization;
+
+/// The longest password a template may expand to.
+pub const MAX_TEMPLATE_LENGTH: usize = 512;
+
+/// Short reference of the template syntax, shown in the GUI and CLI help.
+pub const TEMPLATE_HELP: &str = "c/C consonant, v/V vowel, x/X letter (lower/upper), d digit, s special, \
+                                 * any character, {n} repeats the previous token, \\ escapes a literal.";
+
+const VOWELS: &str = "aeiouAEIOU";
+
+/// One position of a parsed template.
+#[derive(Clone, Debug)]
+pub enum TemplateSlot {
+    /// A fixed character copied into the password as-is.
+    Literal(char),
+    /// A character drawn uniformly from a pool of distinct characters (grapheme clusters).
+    Pool { token: char, chars: Vec<String> },
+}
+
+/// Returns the characters a template token draws from, or `None` if `token` is a literal.
+fn token_pool(token: char, sets: &CharacterSets) -> Option<Vec<String>> {
+    // Accented letters are sorted by their base letter, so `é` is a vowel.
+    let letters = |set: &str, vowels: bool| -> Vec<String> {
+        graphemes(set)
+            .into_iter()
+            .filter(|g| g.nfd().next().is_some_and(|c| c.is_alphabetic() && VOWELS.contains(c) == vowels))
+            .collect()
+    };
+    let mut chars: Vec<String> = match token {
+        'c' => letters(&sets.lowercase, false),
+        'C' => letters(&sets.uppercase, false),
+        'v' => letters(&sets.lowercase, true),
+        'V' => letters(&sets.uppercase, true),
+        'x' => graphemes(&sets.lowercase),
+        'X' => graphemes(&sets.uppercase),
+        'd' => graphemes(&sets.numbers),
+        's' => graphemes(&sets.special),
+        '*' => [&sets.lowercase, &sets.uppercase, &sets.numbers, &sets.special]
+            .iter()
+            .flat_map(|set| graphemes(set))
+            .collect(),
+        _ => return None,
+    };
+    chars.sort_unstable();
+    chars.dedup();
+    Some(chars)
+}
+
+/// Parses a template such as `Cvcc-dddd-ssXX` or `Cvd{4}` into slots.
+pub fn parse_template(template: &str, sets: &CharacterSets) -> Result<Vec<TemplateSlot>, String> {
+    let mut slots: Vec<TemplateSlot> = Vec::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(literal) => slots.push(TemplateSlot::Literal(literal)),
+                None => return Err("Template ends with an unfinished '\\' escape.".to_string()),
+            },
+            '{' => {
+                let mut digits = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(d) if d.is_ascii_digit() => digits.push(d),
+                        _ => return Err("Template repeat must look like {4}.".to_string()),
+                    }
+                }
+                let times: usize = digits.parse().map_err(|_| "Template repeat must look like {4}.".to_string())?;
+                let previous = slots
+                    .last()
+                    .cloned()
+                    .ok_or_else(|| "Template repeat {n} must follow a token.".to_string())?;
+                if times == 0 {
+                    slots.pop();
+                } else if times > MAX_TEMPLATE_LENGTH {
+                    return Err(format!("Templates may not be longer than {} characters.", MAX_TEMPLATE_LENGTH));
+                } else {
+                    slots.extend(std::iter::repeat_n(previous, times - 1));
+                }
+            }
+            _ => match token_pool(c, sets) {
+                Some(pool) if pool.is_empty() => {
+                    return Err(format!("Template token '{}' has no characters to draw from.", c));
+                }
+                Some(pool) => slots.push(TemplateSlot::Pool { token: c, chars: pool }),
+                None => slots.push(TemplateSlot::Literal(c)),
+            },
+        }
+        if slots.len() > MAX_TEMPLATE_LENGTH {
+            return Err(format!("Templates may not be longer than {} characters.", MAX_TEMPLATE_LENGTH));
+        }
+    }
+
+    if slots.is_empty() {
+        return Err("The template is empty.".to_string());
+    }
+    Ok(slots)
+}
+
+/// Generates a password by filling every token of a template from the character sets.
+pub fn generate_template_password<R: Rng + ?Sized>(
+    template: &str,
+    sets: &CharacterSets,
+    rng: &mut R,
+) -> Result<String, String> {
+    let slots = parse_template(template, sets)?;
+    let mut password = String::new();
+    for slot in &slots {
+        match slot {
+            TemplateSlot::Literal(c) => password.push(*c),
+            TemplateSlot::Pool { chars, .. } => password.push_str(chars.choose(rng).unwrap()),
+        }
+    }
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The tokens and literals of a parsed template, one character per slot.
+    fn shape(template: &str) -> Result<String, String> {
+        let slots = parse_template(template, &CharacterSets::default())?;
+        Ok(slots
+            .iter()
+            .map(|slot| match slot {
+                TemplateSlot::Literal(c) => *c,
+                TemplateSlot::Pool { token, .. } => *token,
+            })
+            .collect())
+    }
+
+    #[test]
+    fn repeats() {
+        assert_eq!(shape("Cvd{4}-s").unwrap(), "Cvdddd-s");
+        assert_eq!(shape("d{1}").unwrap(), "d");
+        assert_eq!(shape("-{3}").unwrap(), "---");
+        assert_eq!(shape("cd{0}s").unwrap(), "cs");
+        assert_eq!(shape("d{0}").unwrap_err(), "The template is empty.");
+        assert_eq!(shape("d{x}").unwrap_err(), "Template repeat must look like {4}.");
+        assert_eq!(shape("d{4").unwrap_err(), "Template repeat must look like {4}.");
+        assert_eq!(shape("d{}").unwrap_err(), "Template repeat must look like {4}.");
+    }
+
+    #[test]
+    fn repeat_needs_a_token() {
+        assert_eq!(shape("{4}d").unwrap_err(), "Template repeat {n} must follow a token.");
+        assert_eq!(shape("d{0}{2}").unwrap_err(), "Template repeat {n} must follow a token.");
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(shape("\\d\\{d").unwrap(), "d{d");
+        assert!(matches!(parse_template("\\d", &CharacterSets::default()).unwrap()[0], TemplateSlot::Literal('d')));
+        assert_eq!(shape("dd\\").unwrap_err(), "Template ends with an unfinished '\\' escape.");
+    }
+
+    #[test]
+    fn length_limit() {
+        let too_long = format!("Templates may not be longer than {} characters.", MAX_TEMPLATE_LENGTH);
+        assert_eq!(shape(&"d".repeat(MAX_TEMPLATE_LENGTH)).unwrap().len(), MAX_TEMPLATE_LENGTH);
+        assert_eq!(shape(&"d".repeat(MAX_TEMPLATE_LENGTH + 1)).unwrap_err(), too_long);
+        assert_eq!(shape(&format!("d{{{}}}", MAX_TEMPLATE_LENGTH)).unwrap().len(), MAX_TEMPLATE_LENGTH);
+        assert_eq!(shape(&format!("dd{{{}}}", MAX_TEMPLATE_LENGTH)).unwrap_err(), too_long);
+        assert_eq!(shape("d{99999999999999999999999}").unwrap_err(), "Template repeat must look like {4}.");
+    }
+
+    #[test]
+    fn accented_vowels_are_vowels() {
+        let sets = CharacterSets {
+            // A decomposed é, normalized to the precomposed one.
+            lowercase: "bcdaee\u{301}ñü".to_string(),
+            uppercase: "ÉBÑ".to_string(),
+            ..CharacterSets::default()
+        };
+        let pool = |token| token_pool(token, &sets).unwrap();
+        assert_eq!(pool('v'), ["a", "e", "é", "ü"]);
+        assert_eq!(pool('c'), ["b", "c", "d", "ñ"]);
+        assert_eq!(pool('V'), ["É"]);
+        assert_eq!(pool('C'), ["B", "Ñ"]);
+    }
+
+    #[test]
+    fn empty_pools_are_rejected() {
+        let sets = CharacterSets { numbers: String::new(), ..CharacterSets::default() };
+        assert_eq!(parse_template("cd", &sets).unwrap_err(), "Template token 'd' has no characters to draw from.");
+    }
+}
@@ -1,6 +1,25 @@
-use crate::app::password::{generate_char_password, generate_word_password};
-use crate::app::state::{PassGenApp, Tab, Theme};
-use crate::app::utils::{load_icon, load_words_from_file, save_password_to_file};
+use crate::app::batch::{export_batch, generate_batch, MAX_BATCH};
+use crate::app::breach::generate_screened;
+use crate::app::config::DEFAULT_WORDLIST;
+use crate::app::derive::{derive_password, CURRENT_DERIVATION_VERSION};
+use crate::app::diceware::generate_dice_passphrase;
+use crate::app::entropy::{
+    char_password_entropy, pin_entropy, pronounceable_password_entropy, sentence_passphrase_entropy,
+    template_password_entropy,
+};
+use crate::app::password::{
+    build_char_sampler, char_password_units, excluded_characters, generate_char_password, generate_word_password,
+    remove_excluded_from_classes, remove_excluded_from_sets, WordSampler, AMBIGUOUS_CHARS,
+};
+use crate::app::pin::{generate_pin, MAX_PIN_LENGTH, MIN_PIN_LENGTH};
+use crate::app::policy::{parse_password_rules, RULES_HELP};
+use crate::app::pronounceable::PronounceableSampler;
+use crate::app::rng::system_rng;
+use crate::app::sentence::{generate_sentence_passphrase, weak_sentence_warning, GRAMMAR_HELP};
+use crate::app::state::{CharacterClass, CharacterSets, Insertion, LengthUnit, PassGenApp, Tab, Theme, WordCase};
+use crate::app::strength::{check_password_strength, current_year};
+use crate::app::template::{generate_template_password, TEMPLATE_HELP};
+use crate::app::utils::{load_icon, save_password_to_file};
 use eframe::{egui, NativeOptions};
 use rfd::FileDialog;
 use std::path::PathBuf;
@@ -17,11 +36,12 @@ impl eframe::App for PassGenApp {
         });
 
         // --- Handle Close Request ---
-        if ctx.input(|i| i.viewport().close_requested()) {
-            if self.get_current_config_as_string() != self.saved_config_state && !self.force_close {
-                self.show_exit_confirmation = true;
-                ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
-            }
+        if ctx.input(|i| i.viewport().close_requested())
+            && self.get_current_config_as_string() != self.saved_config_state
+            && !self.force_close
+        {
+            self.show_exit_confirmation = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
         }
 
         // --- Top Menu Bar ---
@@ -40,17 +60,59 @@ impl eframe::App for PassGenApp {
                     if ui.button("Load Defaults").clicked() {
                         let defaults = Self::default();
                         self.active_tab = defaults.active_tab;
-                        self.char_sets = defaults.char_sets;
-                        self.use_lowercase = defaults.use_lowercase;
-                        self.use_uppercase = defaults.use_uppercase;
-                        self.use_numbers = defaults.use_numbers;
-                        self.use_special = defaults.use_special;
+                        self.char_classes = defaults.char_classes;
+                        self.exclude_ambiguous = defaults.exclude_ambiguous;
+                        self.excluded_chars = defaults.excluded_chars;
+                        self.repetition_rules = defaults.repetition_rules;
                         self.char_length = defaults.char_length;
+                        self.char_length_unit = defaults.char_length_unit;
                         self.word_count = defaults.word_count;
-                        self.use_separator = defaults.use_separator;
-                        self.separator_char = defaults.separator_char;
-                        self.use_uppercase_words = defaults.use_uppercase_words;
+                        self.word_style = defaults.word_style;
+                        self.use_dice_rolls = defaults.use_dice_rolls;
+                        self.template = defaults.template;
+                        self.pronounceable_length = defaults.pronounceable_length;
+                        self.sentence_grammar = defaults.sentence_grammar;
+                        self.sentence_separator = defaults.sentence_separator;
+                        self.pin_length = defaults.pin_length;
+                        self.derive_length = defaults.derive_length;
+                        self.selected_profile.clear();
                         self.status_message = "Defaults loaded. Save to make permanent.".to_string();
+                        if !self.selected_wordlist.is_empty() {
+                            self.selected_wordlist.clear();
+                            let status = self.reload_words();
+                            self.status_message = format!("{} {}", self.status_message, status);
+                        }
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("Profiles", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut self.new_profile_name);
+                    });
+                    if ui.button("Save Current as Profile").clicked() {
+                        match self.save_current_as_profile(&self.new_profile_name.clone()) {
+                            Ok(status) => {
+                                self.status_message = status;
+                                self.new_profile_name.clear();
+                            }
+                            Err(e) => self.error_message = Some(e),
+                        }
+                        ui.close_menu();
+                    }
+                    let selected = self.selected_profile.clone();
+                    let delete = ui.add_enabled(!selected.is_empty(), egui::Button::new("Delete Selected Profile"));
+                    if delete.clicked() {
+                        self.profiles.remove(&selected);
+                        self.selected_profile.clear();
+                        self.status_message =
+                            format!("Profile '{}' deleted. Save the configuration to keep it.", selected);
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("Batch", |ui| {
+                    if ui.button("Generate Many...").clicked() {
+                        self.show_batch_window = true;
                         ui.close_menu();
                     }
                 });
@@ -60,6 +122,23 @@ impl eframe::App for PassGenApp {
                         ui.close_menu();
                     }
                 });
+                ui.separator();
+                ui.label("Profile:");
+                let current = if self.selected_profile.is_empty() { "(none)" } else { &self.selected_profile };
+                let mut chosen = None;
+                egui::ComboBox::from_id_source("profile").selected_text(current.to_string()).show_ui(ui, |ui| {
+                    if self.profiles.is_empty() {
+                        ui.label("No profiles saved yet.");
+                    }
+                    for name in self.profiles.keys() {
+                        if ui.selectable_label(*name == self.selected_profile, name).clicked() {
+                            chosen = Some(name.clone());
+                        }
+                    }
+                });
+                if let Some(name) = chosen {
+                    self.status_message = self.apply_profile(&name);
+                }
             });
         });
 
@@ -68,6 +147,12 @@ impl eframe::App for PassGenApp {
             ui.horizontal(|ui| {
                 ui.selectable_value(&mut self.active_tab, Tab::Character, "Characters");
                 ui.selectable_value(&mut self.active_tab, Tab::Word, "Words");
+                ui.selectable_value(&mut self.active_tab, Tab::Template, "Template");
+                ui.selectable_value(&mut self.active_tab, Tab::Pronounceable, "Pronounceable");
+                ui.selectable_value(&mut self.active_tab, Tab::Sentence, "Sentence");
+                ui.selectable_value(&mut self.active_tab, Tab::Pin, "PIN");
+                ui.selectable_value(&mut self.active_tab, Tab::Site, "Site");
+                ui.selectable_value(&mut self.active_tab, Tab::Check, "Check");
             });
             ui.separator();
 
@@ -75,6 +160,12 @@ impl eframe::App for PassGenApp {
             match self.active_tab {
                 Tab::Character => self.draw_character_tab(ui),
                 Tab::Word => self.draw_word_tab(ui),
+                Tab::Template => self.draw_template_tab(ui),
+                Tab::Pronounceable => self.draw_pronounceable_tab(ui),
+                Tab::Sentence => self.draw_sentence_tab(ui),
+                Tab::Pin => self.draw_pin_tab(ui),
+                Tab::Site => self.draw_site_tab(ui),
+                Tab::Check => self.draw_check_tab(ui),
             }
         });
 
@@ -88,31 +179,98 @@ impl eframe::App for PassGenApp {
 
         // --- Draw Windows ---
         self.draw_settings_window(ctx);
+        self.draw_batch_window(ctx);
+        self.draw_rules_window(ctx);
         self.draw_exit_confirmation_window(ctx);
         self.draw_error_dialog(ctx);
     }
 }
 
 impl PassGenApp {
+    /// The character classes used for generation, without the excluded characters.
+    fn generation_classes(&self) -> Vec<CharacterClass> {
+        let excluded = excluded_characters(self.exclude_ambiguous, &self.excluded_chars);
+        remove_excluded_from_classes(&self.char_classes, &excluded)
+    }
+
+    /// The character sets used by templates, without the excluded characters.
+    fn generation_sets(&self) -> CharacterSets {
+        let excluded = excluded_characters(self.exclude_ambiguous, &self.excluded_chars);
+        remove_excluded_from_sets(&CharacterSets::from_classes(&self.char_classes), &excluded)
+    }
+
     /// Draws the UI for the "Characters" tab.
     fn draw_character_tab(&mut self, ui: &mut egui::Ui) {
-        egui::Grid::new("char_sets_grid")
-            .num_columns(2)
-            .spacing([PADDING, PADDING])
-            .show(ui, |ui| {
-                ui.checkbox(&mut self.use_lowercase, "Lowercase");
-                ui.add(egui::TextEdit::singleline(&mut self.char_sets.lowercase).desired_width(f32::INFINITY));
-                ui.end_row();
-                ui.checkbox(&mut self.use_uppercase, "Uppercase");
-                ui.add(egui::TextEdit::singleline(&mut self.char_sets.uppercase).desired_width(f32::INFINITY));
-                ui.end_row();
-                ui.checkbox(&mut self.use_numbers, "Numbers");
-                ui.add(egui::TextEdit::singleline(&mut self.char_sets.numbers).desired_width(f32::INFINITY));
-                ui.end_row();
-                ui.checkbox(&mut self.use_special, "Special");
-                ui.add(egui::TextEdit::singleline(&mut self.char_sets.special).desired_width(f32::INFINITY));
-                ui.end_row();
-            });
+        let mut removed = None;
+        egui::ScrollArea::vertical().max_height(170.0).show(ui, |ui| {
+            egui::Grid::new("char_classes_grid")
+                .num_columns(7)
+                .spacing([PADDING / 2.0, PADDING / 2.0])
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.label("Class");
+                    ui.label("Characters");
+                    ui.label("Min");
+                    ui.label("Max");
+                    ui.label("Weight");
+                    ui.end_row();
+                    for (i, class) in self.char_classes.iter_mut().enumerate() {
+                        ui.checkbox(&mut class.enabled, "");
+                        ui.add(egui::TextEdit::singleline(&mut class.name).desired_width(80.0));
+                        ui.add(egui::TextEdit::singleline(&mut class.chars).desired_width(190.0));
+                        ui.add(egui::DragValue::new(&mut class.min).clamp_range(0..=128));
+                        let mut max = class.max.unwrap_or(0);
+                        ui.add(
+                            egui::DragValue::new(&mut max)
+                                .clamp_range(0..=128)
+                                .custom_formatter(|v, _| if v == 0.0 { "any".to_string() } else { v.to_string() }),
+                        )
+                        .on_hover_text("0 means no limit");
+                        class.max = if max == 0 { None } else { Some(max) };
+                        ui.add(egui::DragValue::new(&mut class.weight).clamp_range(1..=10))
+                            .on_hover_text("How likely each character of this class is compared to weight 1");
+                        if ui.small_button("x").on_hover_text("Remove class").clicked() {
+                            removed = Some(i);
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+        if let Some(i) = removed {
+            self.char_classes.remove(i);
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Add Class").clicked() {
+                let mut class = CharacterClass::new(&format!("Class {}", self.char_classes.len() + 1), "");
+                class.min = 0;
+                self.char_classes.push(class);
+            }
+            if ui.button("Import Rules...").on_hover_text("Use a service's published password rules").clicked() {
+                self.show_rules_window = true;
+            }
+        });
+
+        ui.add_space(PADDING);
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.exclude_ambiguous, "Exclude look-alikes")
+                .on_hover_text(format!("Leaves out {}", AMBIGUOUS_CHARS));
+            ui.label("Exclude:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.excluded_chars)
+                    .hint_text("characters to leave out")
+                    .desired_width(f32::INFINITY),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Max uses per character:");
+            ui.add(egui::DragValue::new(&mut self.repetition_rules.max_occurrences).clamp_range(1..=128));
+            ui.checkbox(&mut self.repetition_rules.no_adjacent_repeats, "No doubles")
+                .on_hover_text("No character twice in a row, like aa");
+            ui.checkbox(&mut self.repetition_rules.no_sequences, "No sequences")
+                .on_hover_text("No runs like abc or 321");
+            ui.checkbox(&mut self.repetition_rules.no_keyboard_walks, "No keyboard walks")
+                .on_hover_text("No neighbouring keys like qwe or lkj");
+        });
 
         ui.add_space(PADDING);
         ui.separator();
@@ -121,19 +279,21 @@ impl PassGenApp {
         ui.horizontal(|ui| {
             ui.label("Password Length:");
             ui.add(egui::DragValue::new(&mut self.char_length).clamp_range(12..=128));
+            ui.radio_value(&mut self.char_length_unit, LengthUnit::Characters, "characters");
+            ui.radio_value(&mut self.char_length_unit, LengthUnit::Bytes, "bytes");
         });
 
         ui.add_space(PADDING);
 
         if ui.add_sized([ui.available_width(), 40.0], egui::Button::new("Generate Password")).clicked() {
-            match generate_char_password(
-                self.char_length,
-                &self.char_sets,
-                self.use_lowercase,
-                self.use_uppercase,
-                self.use_numbers,
-                self.use_special,
-            ) {
+            let classes = self.generation_classes();
+            match char_password_units(self.char_length, self.char_length_unit, &classes)
+                .and_then(|length| {
+                    generate_screened(&self.password_screen, &mut *system_rng(), |rng| {
+                        generate_char_password(length, &classes, &self.repetition_rules, rng)
+                    })
+                })
+            {
                 Ok(password) => {
                     self.char_password_output = password;
                     self.status_message = save_password_to_file(&self.char_password_output, &self.password_file_path);
@@ -153,11 +313,9 @@ impl PassGenApp {
                     .font(egui::FontId::monospace(20.0))
                     .desired_width(ui.available_width() - 60.0),
             );
-            if ui.button("Copy").clicked() {
-                if !self.char_password_output.is_empty() {
-                    ui.output_mut(|o| o.copied_text = self.char_password_output.clone());
-                    self.status_message = "Password copied to clipboard!".to_string();
-                }
+            if ui.button("Copy").clicked() && !self.char_password_output.is_empty() {
+                ui.output_mut(|o| o.copied_text = self.char_password_output.clone());
+                self.status_message = "Password copied to clipboard!".to_string();
             }
         });
     }
@@ -168,40 +326,128 @@ impl PassGenApp {
         ui.label("Generate a memorable passphrase from a list of words.");
         ui.add_space(PADDING);
 
+        self.draw_wordlist_picker(ui);
+
         ui.horizontal(|ui| {
             ui.label("Number of words:");
-            ui.radio_value(&mut self.word_count, 3, "3");
-            ui.radio_value(&mut self.word_count, 4, "4");
-            ui.radio_value(&mut self.word_count, 5, "5");
+            ui.add(egui::DragValue::new(&mut self.word_count).clamp_range(1..=32));
+        });
+        let style = &mut self.word_style;
+        ui.horizontal(|ui| {
+            ui.label("Length at least:");
+            ui.add(egui::DragValue::new(&mut style.min_length).clamp_range(0..=512));
+            ui.checkbox(&mut style.use_length_cap, "At most:");
+            ui.add_enabled_ui(style.use_length_cap, |ui| {
+                let auto = format!("auto ({})", 5 * self.word_count + 15);
+                ui.add(
+                    egui::DragValue::new(&mut style.max_length)
+                        .clamp_range(0..=512)
+                        .custom_formatter(move |n, _| if n == 0.0 { auto.clone() } else { n.to_string() }),
+                );
+            });
+            ui.radio_value(&mut style.length_unit, LengthUnit::Characters, "characters");
+            ui.radio_value(&mut style.length_unit, LengthUnit::Bytes, "bytes");
         });
         ui.add_space(PADDING);
 
-        ui.checkbox(&mut self.use_uppercase_words, "Uppercase first character of words");
         ui.horizontal(|ui| {
-            ui.checkbox(&mut self.use_separator, "Separator character:");
-            ui.add_enabled_ui(self.use_separator, |ui| {
-                let response = ui.add(egui::TextEdit::singleline(&mut self.separator_char).desired_width(30.0));
-                if response.changed() {
-                    if self.separator_char.chars().count() > 1 {
-                        self.separator_char = self.separator_char.chars().next().unwrap_or_default().to_string();
+            ui.label("Word casing:");
+            egui::ComboBox::from_id_source("word_case")
+                .selected_text(style.case.label())
+                .show_ui(ui, |ui| {
+                    for case in WordCase::ALL {
+                        ui.selectable_value(&mut style.case, case, case.label());
                     }
-                }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Abbreviated form, first");
+            ui.add(
+                egui::DragValue::new(&mut style.abbreviate)
+                    .clamp_range(0..=16)
+                    .custom_formatter(|n, _| if n == 0.0 { "off".to_string() } else { n.to_string() }),
+            );
+            ui.label("letters of each word, for typing (pair with a unique-prefix wordlist)");
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut style.use_separator, "Separator:");
+            ui.add_enabled_ui(style.use_separator, |ui| {
+                ui.add(egui::TextEdit::singleline(&mut style.separator).desired_width(80.0));
+                ui.checkbox(&mut style.random_separator, "Pick one of these characters per gap");
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Insert random characters:");
+            egui::ComboBox::from_id_source("word_insertion")
+                .selected_text(match style.insertion {
+                    Insertion::None => "No",
+                    Insertion::Between => "Between words",
+                    Insertion::Inside => "Inside words",
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut style.insertion, Insertion::None, "No");
+                    ui.selectable_value(&mut style.insertion, Insertion::Between, "Between words");
+                    ui.selectable_value(&mut style.insertion, Insertion::Inside, "Inside words");
+                });
+            ui.add_enabled_ui(style.insertion != Insertion::None, |ui| {
+                ui.add(egui::DragValue::new(&mut style.insert_count).clamp_range(1..=10));
+                ui.label("from");
+                ui.add(egui::TextEdit::singleline(&mut style.insert_chars).desired_width(f32::INFINITY));
             });
         });
 
+        let dice = self.wordlist.dice;
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(dice.is_some(), |ui| {
+                ui.checkbox(&mut self.use_dice_rolls, "Use physical dice rolls:");
+            });
+            match dice {
+                Some(dice) => {
+                    ui.add_enabled_ui(self.use_dice_rolls, |ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.dice_rolls)
+                                .hint_text(format!("{} digits 1-6 per word", dice))
+                                .password(true)
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                }
+                None => {
+                    ui.label("(needs a complete Diceware wordlist)");
+                }
+            }
+        });
+        let use_dice_rolls = self.use_dice_rolls && dice.is_some();
+
         ui.add_space(PADDING);
 
         if ui.add_sized([ui.available_width(), 40.0], egui::Button::new("Generate Passphrase")).clicked() {
-            match generate_word_password(
-                self.word_count,
-                &self.words,
-                self.use_separator,
-                &self.separator_char,
-                self.use_uppercase_words,
-            ) {
+            // Dice passphrases have no abbreviated form; otherwise the last draw's is the screened passphrase's.
+            let mut abbreviated = None;
+            let result = if use_dice_rolls {
+                generate_dice_passphrase(&self.dice_rolls, self.word_count, &self.wordlist, &self.word_style)
+            } else {
+                generate_screened(&self.password_screen, &mut *system_rng(), |rng| {
+                    let (passphrase, short) =
+                        generate_word_password(self.word_count, &self.wordlist.words, &self.word_style, rng)?;
+                    abbreviated = short;
+                    Ok(passphrase)
+                })
+            };
+            match result {
                 Ok(password) => {
                     self.word_password_output = password;
+                    self.word_abbreviated_output = abbreviated.unwrap_or_default();
+                    // The rolls are as secret as the passphrase itself.
+                    self.dice_rolls.clear();
                     self.status_message = save_password_to_file(&self.word_password_output, &self.password_file_path);
+                    // Dice passphrases cannot be drawn again behind the user's back, so only warn.
+                    if let Some(reason) = self.password_screen.rejection(&self.word_password_output) {
+                        self.status_message = format!("Warning: Do not use this passphrase: {}. Roll again.", reason);
+                    }
+                    if let Some(reason) = self.password_screen.rejection(&self.word_abbreviated_output) {
+                        self.status_message = format!("Warning: Do not use the abbreviated form: {}.", reason);
+                    }
                 }
                 Err(e) => self.error_message = Some(e),
             }
@@ -218,17 +464,401 @@ impl PassGenApp {
                     .font(egui::FontId::monospace(20.0))
                     .desired_width(ui.available_width() - 60.0),
             );
-            if ui.button("Copy").clicked() {
-                if !self.word_password_output.is_empty() {
-                    ui.output_mut(|o| o.copied_text = self.word_password_output.clone());
-                    self.status_message = "Passphrase copied to clipboard!".to_string();
+            if ui.button("Copy").clicked() && !self.word_password_output.is_empty() {
+                ui.output_mut(|o| o.copied_text = self.word_password_output.clone());
+                self.status_message = "Passphrase copied to clipboard!".to_string();
+            }
+        });
+        if !self.word_abbreviated_output.is_empty() {
+            ui.label("Abbreviated Form (type this where typing is hard):");
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.word_abbreviated_output.as_str())
+                        .font(egui::FontId::monospace(20.0))
+                        .desired_width(ui.available_width() - 60.0),
+                );
+                if ui.button("Copy").clicked() {
+                    ui.output_mut(|o| o.copied_text = self.word_abbreviated_output.clone());
+                    self.status_message = "Abbreviated passphrase copied to clipboard!".to_string();
+                }
+            });
+        }
+    }
+
+    /// Draws the dropdown that picks which registered wordlist the word-based tabs draw from.
+    fn draw_wordlist_picker(&mut self, ui: &mut egui::Ui) {
+        let current = match self.selected_wordlist.as_str() {
+            "" => DEFAULT_WORDLIST,
+            name => name,
+        };
+        let mut chosen = None;
+        ui.horizontal(|ui| {
+            ui.label("Wordlist:");
+            egui::ComboBox::from_id_source("wordlist").selected_text(current.to_string()).show_ui(ui, |ui| {
+                for name in std::iter::once(DEFAULT_WORDLIST).chain(self.wordlists.keys().map(String::as_str)) {
+                    if ui.selectable_label(name == current, name).clicked() && name != current {
+                        chosen = Some(name.to_string());
+                    }
+                }
+            });
+            ui.label(format!("({} words)", self.wordlist.words.len()));
+        });
+        if let Some(name) = chosen {
+            self.selected_wordlist = if name == DEFAULT_WORDLIST { String::new() } else { name };
+            self.status_message = self.reload_words();
+        }
+        ui.add_space(PADDING);
+    }
+
+    /// Draws the UI for the "Template" tab.
+    fn draw_template_tab(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(PADDING);
+        ui.label("Generate a password with a fixed shape, using the Lowercase, Uppercase, Numbers and Special classes.");
+        ui.add_space(PADDING);
+
+        ui.horizontal(|ui| {
+            ui.label("Template:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.template)
+                    .font(egui::FontId::monospace(14.0))
+                    .desired_width(f32::INFINITY),
+            );
+        });
+        ui.label(egui::RichText::new(TEMPLATE_HELP).small());
+        ui.add_space(PADDING);
+
+        let sets = self.generation_sets();
+        match template_password_entropy(&self.template, &sets) {
+            Ok(report) => ui.label(format!("Entropy: {:.2} bits", report.bits)),
+            Err(e) => ui.label(egui::RichText::new(e).color(egui::Color32::from_rgb(255, 100, 100))),
+        };
+
+        ui.add_space(PADDING);
+
+        if ui.add_sized([ui.available_width(), 40.0], egui::Button::new("Generate Password")).clicked() {
+            let result = generate_screened(&self.password_screen, &mut *system_rng(), |rng| {
+                generate_template_password(&self.template, &sets, rng)
+            });
+            match result {
+                Ok(password) => {
+                    self.template_password_output = password;
+                    self.status_message =
+                        save_password_to_file(&self.template_password_output, &self.password_file_path);
+                }
+                Err(e) => self.error_message = Some(e),
+            }
+        }
+
+        ui.add_space(PADDING);
+        ui.separator();
+        ui.add_space(PADDING);
+
+        ui.label("Generated Password:");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.template_password_output.as_str())
+                    .font(egui::FontId::monospace(20.0))
+                    .desired_width(ui.available_width() - 60.0),
+            );
+            if ui.button("Copy").clicked() && !self.template_password_output.is_empty() {
+                ui.output_mut(|o| o.copied_text = self.template_password_output.clone());
+                self.status_message = "Password copied to clipboard!".to_string();
+            }
+        });
+    }
+
+    /// Draws the UI for the "Pronounceable" tab.
+    fn draw_pronounceable_tab(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(PADDING);
+        ui.label("Generate a random password that is easy to read aloud, learned from the wordlist.");
+        ui.add_space(PADDING);
+
+        self.draw_wordlist_picker(ui);
+
+        ui.horizontal(|ui| {
+            ui.label("Password Length:");
+            ui.add(egui::DragValue::new(&mut self.pronounceable_length).clamp_range(8..=64));
+        });
+
+        ui.add_space(PADDING);
+
+        if ui.add_sized([ui.available_width(), 40.0], egui::Button::new("Generate Password")).clicked() {
+            let result = PronounceableSampler::new(self.pronounceable_length, &self.wordlist.words).and_then(|sampler| {
+                let mut rng = system_rng();
+                let password = generate_screened(&self.password_screen, &mut *rng, |rng| Ok(sampler.sample(rng)))?;
+                Ok((password, sampler))
+            });
+            match result {
+                Ok((password, sampler)) => {
+                    self.pronounceable_password_output = password;
+                    let saved = save_password_to_file(&self.pronounceable_password_output, &self.password_file_path);
+                    let random = char_password_entropy(
+                        self.pronounceable_length,
+                        &self.generation_classes(),
+                        &self.repetition_rules,
+                    );
+                    let report = pronounceable_password_entropy(&sampler);
+                    self.status_message = match random {
+                        Ok(random) => format!(
+                            "{}\nEntropy: {:.2} bits (the Characters tab gives {:.2} bits at this length).",
+                            saved, report.bits, random.bits
+                        ),
+                        Err(_) => format!("{}\nEntropy: {:.2} bits.", saved, report.bits),
+                    };
+                }
+                Err(e) => self.error_message = Some(e),
+            }
+        }
+
+        ui.add_space(PADDING);
+        ui.separator();
+        ui.add_space(PADDING);
+
+        ui.label("Generated Password:");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.pronounceable_password_output.as_str())
+                    .font(egui::FontId::monospace(20.0))
+                    .desired_width(ui.available_width() - 60.0),
+            );
+            if ui.button("Copy").clicked() && !self.pronounceable_password_output.is_empty() {
+                ui.output_mut(|o| o.copied_text = self.pronounceable_password_output.clone());
+                self.status_message = "Password copied to clipboard!".to_string();
+            }
+        });
+    }
+
+    /// Draws the UI for the "Sentence" tab.
+    fn draw_sentence_tab(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(PADDING);
+        ui.label("Generate a memorable sentence such as \"seven purple otters happily juggle fuzzy pickles\"");
+        ui.label("from a grammar, drawing each word from the tagged wordlist.");
+        ui.add_space(PADDING);
+
+        egui::Grid::new("sentence_grid").num_columns(2).spacing([10.0, 8.0]).show(ui, |ui| {
+            ui.label("Grammar:");
+            ui.add(egui::TextEdit::singleline(&mut self.sentence_grammar).desired_width(f32::INFINITY));
+            ui.end_row();
+            ui.label("Separator:");
+            ui.add(egui::TextEdit::singleline(&mut self.sentence_separator).desired_width(60.0));
+            ui.end_row();
+        });
+        ui.label(egui::RichText::new(GRAMMAR_HELP).small());
+
+        ui.add_space(PADDING);
+
+        match sentence_passphrase_entropy(&self.sentence_grammar, &self.tagged_words, &self.sentence_separator) {
+            Ok(report) => {
+                ui.label(format!("Entropy: {:.2} bits ({})", report.bits, report.notes.join(", ")));
+                if let Some(warning) = weak_sentence_warning(report.bits) {
+                    ui.label(egui::RichText::new(warning).color(egui::Color32::from_rgb(255, 100, 100)));
+                }
+            }
+            Err(e) => {
+                ui.label(egui::RichText::new(e).color(egui::Color32::from_rgb(255, 100, 100)));
+            }
+        };
+
+        ui.add_space(PADDING);
+
+        if ui.add_sized([ui.available_width(), 40.0], egui::Button::new("Generate Sentence")).clicked() {
+            let result = generate_screened(&self.password_screen, &mut *system_rng(), |rng| {
+                generate_sentence_passphrase(&self.sentence_grammar, &self.tagged_words, &self.sentence_separator, rng)
+            });
+            match result {
+                Ok(sentence) => {
+                    self.sentence_output = sentence;
+                    self.status_message = save_password_to_file(&self.sentence_output, &self.password_file_path);
+                }
+                Err(e) => self.error_message = Some(e),
+            }
+        }
+
+        ui.add_space(PADDING);
+        ui.separator();
+        ui.add_space(PADDING);
+
+        ui.label("Generated Passphrase:");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.sentence_output.as_str())
+                    .font(egui::FontId::monospace(20.0))
+                    .desired_width(ui.available_width() - 60.0),
+            );
+            if ui.button("Copy").clicked() && !self.sentence_output.is_empty() {
+                ui.output_mut(|o| o.copied_text = self.sentence_output.clone());
+                self.status_message = "Passphrase copied to clipboard!".to_string();
+            }
+        });
+    }
+
+    /// Draws the UI for the "PIN" tab.
+    fn draw_pin_tab(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(PADDING);
+        ui.label("Generate a random numeric PIN for door codes, phones and SIM cards. Common PINs, runs,");
+        ui.label("repeated digits, keypad lines, mirrored PINs and dates are left out.");
+        ui.add_space(PADDING);
+
+        ui.horizontal(|ui| {
+            ui.label("PIN Length:");
+            ui.add(egui::DragValue::new(&mut self.pin_length).clamp_range(MIN_PIN_LENGTH..=MAX_PIN_LENGTH));
+            ui.label("digits");
+        });
+
+        ui.add_space(PADDING);
+
+        if ui.add_sized([ui.available_width(), 40.0], egui::Button::new("Generate PIN")).clicked() {
+            let result =
+                generate_screened(&self.password_screen, &mut *system_rng(), |rng| generate_pin(self.pin_length, rng));
+            match result {
+                Ok(pin) => {
+                    self.pin_output = pin;
+                    let saved = save_password_to_file(&self.pin_output, &self.password_file_path);
+                    self.status_message = match pin_entropy(self.pin_length) {
+                        Ok(report) => format!("{}\nEntropy: {:.2} bits.", saved, report.bits),
+                        Err(_) => saved,
+                    };
                 }
+                Err(e) => self.error_message = Some(e),
+            }
+        }
+
+        ui.add_space(PADDING);
+        ui.separator();
+        ui.add_space(PADDING);
+
+        ui.label("Generated PIN:");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.pin_output.as_str())
+                    .font(egui::FontId::monospace(20.0))
+                    .desired_width(ui.available_width() - 60.0),
+            );
+            if ui.button("Copy").clicked() && !self.pin_output.is_empty() {
+                ui.output_mut(|o| o.copied_text = self.pin_output.clone());
+                self.status_message = "PIN copied to clipboard!".to_string();
             }
         });
     }
 
+    /// Draws the UI for the "Site" tab.
+    fn draw_site_tab(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(PADDING);
+        ui.label("Derive a site's password from a master passphrase. The same inputs give the same password");
+        ui.label("on any machine, so nothing needs to be stored. Its character policy is fixed by the version.");
+        ui.add_space(PADDING);
+
+        egui::Grid::new("derive_grid").num_columns(2).spacing([PADDING, PADDING / 2.0]).show(ui, |ui| {
+            ui.label("Master passphrase:");
+            ui.add(egui::TextEdit::singleline(&mut self.derive_master).password(true).desired_width(f32::INFINITY));
+            ui.end_row();
+            ui.label("Site or account:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.derive_site)
+                    .hint_text("e.g. github.com")
+                    .desired_width(f32::INFINITY),
+            );
+            ui.end_row();
+            ui.label("Counter:");
+            ui.add(egui::DragValue::new(&mut self.derive_counter).clamp_range(1..=9999))
+                .on_hover_text("Raise to rotate this site's password");
+            ui.end_row();
+            ui.label("Password Length:");
+            ui.add(egui::DragValue::new(&mut self.derive_length).clamp_range(12..=128));
+            ui.end_row();
+        });
+
+        ui.add_space(PADDING);
+
+        if ui.add_sized([ui.available_width(), 40.0], egui::Button::new("Derive Password")).clicked() {
+            match derive_password(
+                CURRENT_DERIVATION_VERSION,
+                &self.derive_master,
+                &self.derive_site,
+                self.derive_counter,
+                self.derive_length,
+            ) {
+                Ok(password) => {
+                    self.derive_password_output = password;
+                    self.derive_master.clear();
+                    self.status_message = format!(
+                        "Derived with {} for '{}', counter {}.",
+                        CURRENT_DERIVATION_VERSION,
+                        self.derive_site.trim(),
+                        self.derive_counter
+                    );
+                    if let Some(reason) = self.password_screen.rejection(&self.derive_password_output) {
+                        self.status_message = format!(
+                            "Warning: Do not use this password: {}. Raise the counter to derive another.",
+                            reason
+                        );
+                    }
+                }
+                Err(e) => self.error_message = Some(e),
+            }
+        }
+
+        ui.add_space(PADDING);
+        ui.separator();
+        ui.add_space(PADDING);
+
+        ui.label("Derived Password:");
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.derive_password_output.as_str())
+                    .font(egui::FontId::monospace(20.0))
+                    .desired_width(ui.available_width() - 60.0),
+            );
+            if ui.button("Copy").clicked() && !self.derive_password_output.is_empty() {
+                ui.output_mut(|o| o.copied_text = self.derive_password_output.clone());
+                self.status_message = "Password copied to clipboard!".to_string();
+            }
+        });
+    }
+
+    /// Draws the UI for the "Check" tab.
+    fn draw_check_tab(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(PADDING);
+        ui.label("Estimate how hard a password you chose yourself is to guess. It is checked against");
+        ui.label("common passwords, the wordlist, keyboard patterns, repeats, sequences and dates, and never saved.");
+        ui.add_space(PADDING);
+
+        ui.horizontal(|ui| {
+            ui.label("Password:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.check_password)
+                    .password(!self.show_check_password)
+                    .desired_width(ui.available_width() - 70.0),
+            );
+            ui.checkbox(&mut self.show_check_password, "Show");
+        });
+
+        ui.add_space(PADDING);
+
+        if ui.add_sized([ui.available_width(), 40.0], egui::Button::new("Check Strength")).clicked() {
+            let report = check_password_strength(&self.check_password, &self.wordlist.words, current_year());
+            self.status_message = format!("Strength: {} of 4 ({}).", report.score, report.score_label());
+            self.check_output = report.to_string();
+            if let Some(screen_report) = self.password_screen.report(&self.check_password) {
+                self.check_output = format!("{}\n{}", self.check_output, screen_report);
+            }
+        }
+
+        ui.add_space(PADDING);
+        ui.separator();
+        ui.add_space(PADDING);
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.monospace(&self.check_output);
+        });
+    }
+
     /// Draws the settings window when it is open.
     fn draw_settings_window(&mut self, ctx: &egui::Context) {
+        let mut reload_lists = false;
+        let mut reload_tagged = false;
+        let mut reload_words = false;
+        let mut added_wordlist = None;
         egui::Window::new("Settings")
             .open(&mut self.show_settings_window)
             .collapsible(false)
@@ -254,9 +884,7 @@ impl PassGenApp {
                     let new_path = PathBuf::from(&self.words_file_path_str);
                     if new_path != self.words_file_path {
                         self.words_file_path = new_path;
-                        let (words, status) = load_words_from_file(&self.words_file_path);
-                        self.words = words;
-                        self.status_message = status;
+                        reload_words = true;
                     }
                 }
 
@@ -264,12 +892,280 @@ impl PassGenApp {
                     if let Some(path) = FileDialog::new().add_filter("Text File", &["txt"]).pick_file() {
                         self.words_file_path_str = path.to_string_lossy().to_string();
                         self.words_file_path = path;
-                        let (words, status) = load_words_from_file(&self.words_file_path);
-                        self.words = words;
-                        self.status_message = status;
+                        reload_words = true;
+                    }
+                }
+
+                ui.add_space(PADDING);
+                ui.label("Wordlist Filters:");
+                let filters = &mut self.word_filters;
+                ui.horizontal(|ui| {
+                    ui.label("Word length from");
+                    ui.add(egui::DragValue::new(&mut filters.min_length).clamp_range(0..=64));
+                    ui.label("to");
+                    ui.add(
+                        egui::DragValue::new(&mut filters.max_length)
+                            .clamp_range(0..=64)
+                            .custom_formatter(|n, _| if n == 0.0 { "any".to_string() } else { n.to_string() }),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut filters.fold_ascii, "Fold accents to ASCII");
+                    ui.checkbox(&mut filters.ascii_only, "ASCII only");
+                    ui.checkbox(&mut filters.lowercase_only, "Lowercase only");
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut filters.no_punctuation, "Letters and digits only");
+                    ui.checkbox(&mut filters.dedupe, "Drop case-insensitive duplicates");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Keep only words told apart by their first");
+                    ui.add(
+                        egui::DragValue::new(&mut filters.unique_prefix)
+                            .clamp_range(0..=16)
+                            .custom_formatter(|n, _| if n == 0.0 { "any".to_string() } else { n.to_string() }),
+                    );
+                    ui.label("letters");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Exclude words listed in:");
+                    if ui.button("Browse").clicked() {
+                        if let Some(path) = FileDialog::new().add_filter("Text File", &["txt"]).pick_file() {
+                            filters.exclude_file = path.to_string_lossy().to_string();
+                        }
+                    }
+                });
+                ui.add(egui::TextEdit::singleline(&mut filters.exclude_file).desired_width(f32::INFINITY));
+
+                reload_words |= ui.button("Reload Wordlist").clicked();
+                ui.collapsing("Wordlist Report", |ui| {
+                    ui.label(egui::RichText::new(self.wordlist.info()).monospace());
+                });
+
+                ui.add_space(PADDING);
+                ui.label("More Wordlists (picked per passphrase on the Word and Pronounceable tabs):");
+                let mut removed = None;
+                for (name, path) in &self.wordlists {
+                    ui.horizontal(|ui| {
+                        if ui.button("Remove").clicked() {
+                            removed = Some(name.clone());
+                        }
+                        ui.label(format!("{}: {}", name, path));
+                    });
+                }
+                if let Some(name) = removed {
+                    self.wordlists.remove(&name);
+                    if self.selected_wordlist == name {
+                        self.selected_wordlist.clear();
+                        reload_words = true;
                     }
                 }
+                ui.horizontal(|ui| {
+                    ui.label("Name:");
+                    ui.add(egui::TextEdit::singleline(&mut self.new_wordlist_name).desired_width(120.0));
+                    if ui.button("Add Wordlist...").clicked() {
+                        added_wordlist = FileDialog::new().add_filter("Text File", &["txt"]).pick_file();
+                    }
+                });
+
+                ui.add_space(PADDING);
+                ui.label("Tagged Wordlist for Sentences (word, part of speech, optional irregular form):");
+                ui.horizontal(|ui| {
+                    let width = ui.available_width() - 70.0;
+                    ui.add(egui::TextEdit::singleline(&mut self.tagged_words_file).desired_width(width));
+                    if ui.button("Browse").clicked() {
+                        if let Some(path) = FileDialog::new().add_filter("Text File", &["txt"]).pick_file() {
+                            self.tagged_words_file = path.to_string_lossy().to_string();
+                            reload_tagged = true;
+                        }
+                    }
+                });
+                reload_tagged |= ui.button("Reload Tagged Wordlist").clicked();
+
+                ui.add_space(PADDING);
+                ui.separator();
+                ui.add_space(PADDING);
+
+                ui.heading("Breached and Banned Passwords");
+                ui.label("Generated passwords found in these lists are drawn again; checked ones are flagged.");
+                ui.horizontal(|ui| {
+                    ui.label("Breached password list (Have I Been Pwned SHA-1 file or Bloom filter):");
+                    if ui.button("Browse").clicked() {
+                        if let Some(path) = FileDialog::new().pick_file() {
+                            self.breached_file = path.to_string_lossy().to_string();
+                        }
+                    }
+                });
+                ui.add(egui::TextEdit::singleline(&mut self.breached_file).desired_width(f32::INFINITY));
+                ui.horizontal(|ui| {
+                    ui.label("Banned words, one per line:");
+                    if ui.button("Browse").clicked() {
+                        if let Some(path) = FileDialog::new().add_filter("Text File", &["txt"]).pick_file() {
+                            self.banned_words_file = path.to_string_lossy().to_string();
+                        }
+                    }
+                });
+                ui.add(egui::TextEdit::singleline(&mut self.banned_words_file).desired_width(f32::INFINITY));
+
+                reload_lists = ui.button("Reload Lists").clicked();
             });
+        if reload_lists {
+            self.status_message = self.reload_password_screen();
+        }
+        if reload_tagged {
+            self.status_message = self.reload_tagged_words();
+        }
+        if let Some(path) = added_wordlist {
+            let name = self.new_wordlist_name.clone();
+            self.status_message = match self.add_wordlist(&name, &path.to_string_lossy()) {
+                Ok(status) => {
+                    self.new_wordlist_name.clear();
+                    status
+                }
+                Err(e) => e,
+            };
+        }
+        if reload_words {
+            self.status_message = self.reload_words();
+        }
+    }
+
+    /// Generates a batch of distinct passwords with the active tab's settings.
+    fn generate_batch_for_tab(&self) -> Result<Vec<String>, String> {
+        let mut rng = system_rng();
+        let screen = &self.password_screen;
+        match self.active_tab {
+            Tab::Character => {
+                let classes = self.generation_classes();
+                let length = char_password_units(self.char_length, self.char_length_unit, &classes)?;
+                let sampler = build_char_sampler(length, &classes, &self.repetition_rules)?;
+                generate_batch(self.batch_count, &mut rng, |rng| {
+                    generate_screened(screen, rng, |rng| sampler.sample(rng))
+                })
+            }
+            Tab::Word if self.use_dice_rolls && self.wordlist.dice.is_some() => {
+                Err("Batches use the computer's random generator; turn off dice rolls first.".to_string())
+            }
+            Tab::Word => {
+                let sampler = WordSampler::new(self.word_count, &self.wordlist.words, &self.word_style)?;
+                generate_batch(self.batch_count, &mut rng, |rng| {
+                    generate_screened(screen, rng, |rng| sampler.sample(rng))
+                })
+            }
+            Tab::Template => {
+                let sets = self.generation_sets();
+                generate_batch(self.batch_count, &mut rng, |rng| {
+                    generate_screened(screen, rng, |rng| generate_template_password(&self.template, &sets, rng))
+                })
+            }
+            Tab::Pronounceable => {
+                let sampler = PronounceableSampler::new(self.pronounceable_length, &self.wordlist.words)?;
+                generate_batch(self.batch_count, &mut rng, |rng| {
+                    generate_screened(screen, rng, |rng| Ok(sampler.sample(rng)))
+                })
+            }
+            Tab::Sentence => generate_batch(self.batch_count, &mut rng, |rng| {
+                let (grammar, separator) = (&self.sentence_grammar, &self.sentence_separator);
+                let words = &self.tagged_words;
+                generate_screened(screen, rng, |rng| generate_sentence_passphrase(grammar, words, separator, rng))
+            }),
+            Tab::Pin => generate_batch(self.batch_count, &mut rng, |rng| {
+                generate_screened(screen, rng, |rng| generate_pin(self.pin_length, rng))
+            }),
+            Tab::Site => Err("Site passwords are derived one at a time.".to_string()),
+            Tab::Check => Err("The Check tab does not generate passwords.".to_string()),
+        }
+    }
+
+    /// Draws the batch window when it is open: many distinct passwords from the active tab's settings.
+    fn draw_batch_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_batch_window;
+        egui::Window::new("Batch Generation").open(&mut open).collapsible(false).show(ctx, |ui| {
+            ui.label(format!(
+                "Uses the settings of the {:?} tab. No two passwords in a batch are the same.",
+                self.active_tab
+            ));
+            ui.horizontal(|ui| {
+                ui.label("How many:");
+                ui.add(egui::DragValue::new(&mut self.batch_count).clamp_range(2..=MAX_BATCH));
+                if ui.button("Generate").clicked() {
+                    match self.generate_batch_for_tab() {
+                        Ok(batch) => {
+                            self.status_message = format!("Generated {} passwords.", batch.len());
+                            self.batch_output = batch;
+                        }
+                        Err(e) => self.error_message = Some(e),
+                    }
+                }
+            });
+
+            ui.add_space(PADDING);
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for password in &self.batch_output {
+                    ui.monospace(password);
+                }
+            });
+            ui.add_space(PADDING);
+
+            ui.add_enabled_ui(!self.batch_output.is_empty(), |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Copy All").clicked() {
+                        ui.output_mut(|o| o.copied_text = self.batch_output.join("\n"));
+                        self.status_message = format!("{} passwords copied to clipboard!", self.batch_output.len());
+                    }
+                    if ui.button("Export...").clicked() {
+                        let dialog = FileDialog::new().add_filter("Text File", &["txt"]).set_file_name("passwords.txt");
+                        if let Some(path) = dialog.save_file() {
+                            self.status_message = export_batch(&self.batch_output, &path);
+                        }
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.batch_output.clear();
+                    }
+                });
+            });
+        });
+        // The batch is as sensitive as any password; drop it when the window closes.
+        if !open {
+            self.batch_output.clear();
+        }
+        self.show_batch_window = open;
+    }
+
+    /// Draws the window for importing password rules into the character settings.
+    fn draw_rules_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_rules_window;
+        egui::Window::new("Import Password Rules").open(&mut open).collapsible(false).show(ctx, |ui| {
+            ui.label("Paste the rules a service publishes for its passwords:");
+            ui.label(egui::RichText::new(RULES_HELP).small());
+            ui.add_space(PADDING);
+            ui.add(
+                egui::TextEdit::multiline(&mut self.rules_text)
+                    .font(egui::FontId::monospace(14.0))
+                    .desired_rows(8)
+                    .desired_width(f32::INFINITY),
+            );
+            ui.add_space(PADDING);
+            ui.horizontal(|ui| {
+                if ui.button("Import").clicked() {
+                    match parse_password_rules(&self.rules_text) {
+                        Ok(imported) => {
+                            self.char_classes = imported.classes.clone();
+                            imported.apply_to_rules(&mut self.repetition_rules);
+                            self.char_length = imported.fit_length(self.char_length);
+                            self.char_length_unit = LengthUnit::Characters;
+                            self.status_message = format!("Imported password rules: {}", imported.describe());
+                            self.show_rules_window = false;
+                        }
+                        Err(e) => self.error_message = Some(e),
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    self.show_rules_window = false;
+                }
+            });
+        });
+        self.show_rules_window &= open;
     }
 
     /// Draws the exit confirmation dialog when needed.
@@ -0,0 +1,117 @@
+use crate::app::state::LengthUnit;
+use std::collections::HashSet;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Puts text in Unicode Normalization Form C, so `é` typed as `e` plus a combining
+/// accent and `é` typed as one code point are the same character.
+pub fn nfc(text: &str) -> String {
+    text.nfc().collect()
+}
+
+/// Splits text into user-perceived characters (extended grapheme clusters) after
+/// NFC normalization. These are the units passwords are built from and counted in,
+/// so a combining sequence or an emoji is never split.
+pub fn graphemes(text: &str) -> Vec<String> {
+    nfc(text).graphemes(true).map(String::from).collect()
+}
+
+/// The distinct characters of `text`, in order of first appearance.
+pub fn distinct_graphemes(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    graphemes(text).into_iter().filter(|g| seen.insert(g.clone())).collect()
+}
+
+/// The number of user-perceived characters in `text`.
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+/// The length of `text` in the given unit.
+pub fn measure(text: &str, unit: LengthUnit) -> usize {
+    match unit {
+        LengthUnit::Characters => grapheme_count(text),
+        LengthUnit::Bytes => text.len(),
+    }
+}
+
+/// The single code point a character consists of, if it is just one.
+pub fn single_char(grapheme: &str) -> Option<char> {
+    let mut chars = grapheme.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Letters that do not decompose into a base letter and an accent, with their usual ASCII spelling.
+const ASCII_SPELLINGS: &[(char, &str)] = &[
+    ('ß', "ss"), ('ẞ', "SS"), ('æ', "ae"), ('Æ', "AE"), ('œ', "oe"), ('Œ', "OE"), ('ø', "o"), ('Ø', "O"), ('đ', "d"),
+    ('Đ', "D"), ('ð', "d"), ('Ð', "D"), ('þ', "th"), ('Þ', "TH"), ('ł', "l"), ('Ł', "L"), ('ı', "i"), ('ħ', "h"),
+    ('Ħ', "H"),
+];
+
+/// Folds accented letters to plain ASCII, so `Müsli` becomes `Musli` and `straße` becomes `strasse`.
+///
+/// Accents are removed by decomposing each character and dropping its combining
+/// marks; characters with no ASCII spelling are kept as they are.
+pub fn fold_to_ascii(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.nfd().filter(|&c| !unicode_normalization::char::is_combining_mark(c)) {
+        match ASCII_SPELLINGS.iter().find(|(letter, _)| *letter == c) {
+            Some((_, spelling)) => folded.push_str(spelling),
+            None => folded.push(c),
+        }
+    }
+    nfc(&folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::password::class_limits;
+    use crate::app::state::CharacterClass;
+
+    #[test]
+    fn combining_sequences_are_one_character() {
+        assert_eq!(graphemes("cafe\u{301}"), ["c", "a", "f", "\u{e9}"]);
+        assert_eq!(graphemes("n\u{303}o\u{308}\u{301}"), ["\u{f1}", "\u{f6}\u{301}"]);
+        assert_eq!(grapheme_count("cafe\u{301}"), 4);
+        assert_eq!(measure("cafe\u{301}", LengthUnit::Bytes), 6);
+    }
+
+    #[test]
+    fn emoji_sequences_are_one_character() {
+        let coder = "\u{1f469}\u{200d}\u{1f4bb}";
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        let flag = "\u{1f1e9}\u{1f1ea}";
+        let thumb = "\u{1f44d}\u{1f3fd}";
+        for emoji in [coder, family, flag, thumb] {
+            assert_eq!(graphemes(emoji), [emoji]);
+            assert_eq!(grapheme_count(emoji), 1);
+            assert_eq!(single_char(emoji), None);
+        }
+        assert_eq!(graphemes(&format!("a{}b{}", family, flag)), ["a", family, "b", flag]);
+    }
+
+    #[test]
+    fn nfc_deduplicates_sets() {
+        // The same letter typed precomposed and decomposed counts once.
+        assert_eq!(distinct_graphemes("\u{e9}e\u{301}ee\u{301}"), ["\u{e9}", "e"]);
+        assert_eq!(distinct_graphemes("\u{212b}\u{c5}A\u{30a}"), ["\u{c5}"]);
+        assert_eq!(nfc("e\u{301}"), "\u{e9}");
+
+        // So does a character class, and a letter already in an earlier class.
+        let classes = [CharacterClass::new("Accents", "\u{e9}e\u{301}"), CharacterClass::new("More", "e\u{301}\u{e8}")];
+        let limits = class_limits(&classes, 4).unwrap();
+        assert_eq!(limits[0].chars, ["\u{e9}"]);
+        assert_eq!(limits[1].chars, ["\u{e8}"]);
+    }
+
+    #[test]
+    fn folding_to_ascii() {
+        assert_eq!(fold_to_ascii("M\u{fc}sli stra\u{df}e \u{c6}sir"), "Musli strasse AEsir");
+        assert_eq!(fold_to_ascii("cafe\u{301}"), "cafe");
+        assert_eq!(fold_to_ascii("\u{65e5}\u{672c}"), "\u{65e5}\u{672c}");
+    }
+}
@@ -1,14 +1,12 @@
+use crate::app::password::abbreviate_word;
+use crate::app::state::WordFilters;
+use crate::app::unicode::{fold_to_ascii, grapheme_count, nfc};
+use std::collections::HashSet;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 
-const DEFAULT_WORDS: &[&str] = &[
-    "apple", "banana", "cherry", "dragon", "eagle", "forest", "guitar", "harmony", "island", "journey",
-    "kinetic", "lantern", "mountain", "nebula", "ocean", "puzzle", "quantum", "river", "sunrise", "telescope",
-    "universe", "vortex", "waterfall", "xenon", "yellow", "zeppelin",
-];
-
 /// Gets the directory where the application executable is located.
 /// Falls back to the current working directory if the executable path cannot be determined.
 pub fn get_app_directory() -> PathBuf {
@@ -34,19 +32,73 @@ pub fn load_icon() -> egui::IconData {
     }
 }
 
-/// Loads a list of words from a specified text file.
-pub fn load_words_from_file(path: &PathBuf) -> (Vec<String>, String) {
-    if !path.exists() {
-        return (Vec::new(), format!("Wordlist not found at: {:?}", path));
+/// Applies the wordlist filters in turn.
+///
+/// Returns the surviving words and, for each filter that is on, its name and how
+/// many words were left after it.
+pub fn filter_words(mut words: Vec<String>, filters: &WordFilters) -> (Vec<String>, Vec<(String, usize)>) {
+    let mut steps = Vec::new();
+    if filters.fold_ascii {
+        // Folding can make two words the same, and a repeated word would count twice.
+        let mut seen = HashSet::new();
+        words = words.iter().map(|word| fold_to_ascii(word)).filter(|word| seen.insert(word.clone())).collect();
+        steps.push(("folded to ASCII".to_string(), words.len()));
+    }
+    if filters.min_length > 0 || filters.max_length > 0 {
+        let max = if filters.max_length == 0 { usize::MAX } else { filters.max_length };
+        let name = match filters.max_length {
+            0 => format!("at least {} characters", filters.min_length),
+            max => format!("{}-{} characters", filters.min_length, max),
+        };
+        keep_words(&mut words, &mut steps, &name, |word| (filters.min_length..=max).contains(&grapheme_count(word)));
+    }
+    if filters.ascii_only {
+        keep_words(&mut words, &mut steps, "ASCII only", |word| word.is_ascii());
+    }
+    if filters.lowercase_only {
+        keep_words(&mut words, &mut steps, "lowercase only", |word| !word.chars().any(char::is_uppercase));
     }
-    match fs::read_to_string(path) {
-        Ok(content) => {
-            let words: Vec<String> = content.lines().map(String::from).collect();
-            let status = format!("Successfully loaded {} words from {:?}", words.len(), path.file_name().unwrap_or_default());
-            (words, status)
+    if filters.no_punctuation {
+        keep_words(&mut words, &mut steps, "letters and digits only", |word| word.chars().all(char::is_alphanumeric));
+    }
+    if !filters.exclude_file.trim().is_empty() {
+        let path = filters.exclude_file.trim();
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                let excluded: HashSet<String> = content
+                    .lines()
+                    .map(|line| nfc(line.trim()).to_lowercase())
+                    .filter(|line| !line.is_empty())
+                    .collect();
+                let name = format!("minus {} excluded", excluded.len());
+                keep_words(&mut words, &mut steps, &name, |word| !excluded.contains(&word.to_lowercase()));
+            }
+            Err(e) => steps.push((format!("exclude list {:?} unreadable ({})", path, e), words.len())),
         }
-        Err(e) => (Vec::new(), format!("Failed to read wordlist: {}", e)),
     }
+    if filters.dedupe {
+        let mut seen = HashSet::new();
+        keep_words(&mut words, &mut steps, "deduplicated", |word| seen.insert(word.to_lowercase()));
+    }
+    if filters.unique_prefix > 0 {
+        let mut seen = HashSet::new();
+        let name = format!("unique {}-letter prefixes", filters.unique_prefix);
+        keep_words(&mut words, &mut steps, &name, |word| {
+            seen.insert(abbreviate_word(&word.to_lowercase(), filters.unique_prefix))
+        });
+    }
+    (words, steps)
+}
+
+/// Keeps the words that pass `test` and records how many are left.
+fn keep_words(
+    words: &mut Vec<String>,
+    steps: &mut Vec<(String, usize)>,
+    name: &str,
+    mut test: impl FnMut(&str) -> bool,
+) {
+    words.retain(|word| test(word));
+    steps.push((name.to_string(), words.len()));
 }
 
 /// Saves the generated password to password.txt
@@ -60,19 +112,86 @@ pub fn save_password_to_file(password: &str, path: &PathBuf) -> String {
     }
 }
 
-/// Creates the default words.txt file if it doesn't exist.
-pub fn create_default_words_file(path: &PathBuf) -> String {
-    if !path.exists() {
-        let default_content = DEFAULT_WORDS.join("\n");
-        match File::create(path) {
-            Ok(mut file) => match file.write_all(default_content.as_bytes()) {
-                Ok(_) => "Created default words.txt".to_string(),
-                Err(e) => format!("Failed to create default words.txt: {}", e),
-            },
-            Err(e) => format!("Failed to create default words.txt: {}", e),
-        }
-    } else {
-        String::new() // No message if file already exists
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(list: &[&str], filters: WordFilters) -> (Vec<String>, Vec<(String, usize)>) {
+        filter_words(list.iter().map(|w| w.to_string()).collect(), &filters)
+    }
+
+    fn kept(list: &[&str], filters: WordFilters) -> Vec<String> {
+        filter(list, filters).0
     }
-}
 
+    #[test]
+    fn no_filters_keep_everything() {
+        let (words, steps) = filter(&["a", "B-c", "é"], WordFilters::default());
+        assert_eq!(words, ["a", "B-c", "é"]);
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn length_bounds() {
+        // "cafe\u{301}" is four characters, though five code points.
+        let list = ["to", "tea", "cafe\u{301}", "toast", "teapots"];
+        let (words, steps) = filter(&list, WordFilters { min_length: 3, max_length: 5, ..WordFilters::default() });
+        assert_eq!(words, ["tea", "cafe\u{301}", "toast"]);
+        assert_eq!(steps, [("3-5 characters".to_string(), 3)]);
+        let (words, steps) = filter(&list, WordFilters { min_length: 5, ..WordFilters::default() });
+        assert_eq!(words, ["toast", "teapots"]);
+        assert_eq!(steps, [("at least 5 characters".to_string(), 2)]);
+        assert_eq!(kept(&list, WordFilters { max_length: 3, ..WordFilters::default() }), ["to", "tea"]);
+    }
+
+    #[test]
+    fn character_filters() {
+        let list = ["apple", "Paris", "A-horizon", "naïve", "it's", "r2d2"];
+        assert_eq!(
+            kept(&list, WordFilters { ascii_only: true, ..WordFilters::default() }),
+            ["apple", "Paris", "A-horizon", "it's", "r2d2"]
+        );
+        assert_eq!(
+            kept(&list, WordFilters { lowercase_only: true, ..WordFilters::default() }),
+            ["apple", "naïve", "it's", "r2d2"]
+        );
+        assert_eq!(
+            kept(&list, WordFilters { no_punctuation: true, ..WordFilters::default() }),
+            ["apple", "Paris", "naïve", "r2d2"]
+        );
+    }
+
+    #[test]
+    fn folding_merges_words() {
+        let filters = WordFilters { fold_ascii: true, ..WordFilters::default() };
+        let (words, steps) = filter(&["naïve", "naive", "café"], filters);
+        assert_eq!(words, ["naive", "cafe"]);
+        assert_eq!(steps, [("folded to ASCII".to_string(), 2)]);
+    }
+
+    #[test]
+    fn duplicates_and_prefixes() {
+        let list = ["Apple", "apple", "apply", "banana"];
+        assert_eq!(kept(&list, WordFilters { dedupe: true, ..WordFilters::default() }), ["Apple", "apply", "banana"]);
+        assert_eq!(kept(&list, WordFilters { unique_prefix: 4, ..WordFilters::default() }), ["Apple", "banana"]);
+    }
+
+    #[test]
+    fn excluded_words() {
+        let path = std::env::temp_dir().join(format!("passgen-test-{}-exclude.txt", std::process::id()));
+        fs::write(&path, "APPLE\n\n  cherry \n").unwrap();
+        let filters = WordFilters { exclude_file: path.display().to_string(), ..WordFilters::default() };
+        let (words, steps) = filter(&["apple", "banana", "Cherry"], filters);
+        assert_eq!(words, ["banana"]);
+        assert_eq!(steps, [("minus 2 excluded".to_string(), 1)]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_filter_can_remove_every_word() {
+        let filters = WordFilters { min_length: 4, ascii_only: true, ..WordFilters::default() };
+        let (words, steps) = filter(&["ab", "abc", "naïve"], filters);
+        assert!(words.is_empty());
+        assert_eq!(steps, [("at least 4 characters".to_string(), 1), ("ASCII only".to_string(), 0)]);
+    }
+}
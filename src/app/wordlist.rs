@@ -0,0 +1,425 @@
+use crate::app::diceware::{diceware_list_size, parse_diceware_list};
+use crate::app::sentence::is_tagged_list;
+use crate::app::state::WordFilters;
+use crate::app::unicode::{grapheme_count, graphemes, nfc};
+use crate::app::utils::filter_words;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A numbered five-dice list of 7776 common English words (about 12.9 bits each),
+/// deflated by build.rs from `wordlists/passgen-7776.txt`.
+const BUILT_IN_WORDS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/default_words.deflate"));
+
+/// What the built-in wordlist is called in status messages.
+pub const BUILT_IN_WORDLIST_NAME: &str = "built-in 7776-word list";
+
+/// How many example words the report shows for each kind of problem.
+const REPORT_EXAMPLES: usize = 5;
+
+/// A loaded wordlist: the words passphrases are drawn from, where they came from and what was found in them.
+#[derive(Clone, Debug, Default)]
+pub struct Wordlist {
+    /// The file name, or the name of the built-in list.
+    pub name: String,
+    /// The file the list was read from; `None` for the built-in list.
+    pub source: Option<PathBuf>,
+    /// The SHA-256 of the list as stored, in hex, to tell copies of a list apart.
+    pub sha256: String,
+    /// The words in use, after cleaning and the filters.
+    pub words: Vec<String>,
+    /// How many dice select a word, for a complete numbered Diceware list; `None` for any other list.
+    pub dice: Option<usize>,
+    /// How many distinct words `words` holds.
+    pub unique_count: usize,
+    /// How many words there are of each length, in characters.
+    pub lengths: BTreeMap<usize, usize>,
+    /// The fewest first letters that tell every word apart, ignoring case; `None` if some words differ only by case.
+    pub unique_prefix: Option<usize>,
+    /// What was wrong with the entries as read, before the filters.
+    pub report: ValidationReport,
+    /// One line on what was loaded, for the status bar.
+    pub status: String,
+}
+
+/// Problems found in the entries of a wordlist.
+///
+/// Empty lines, surrounding whitespace and repeated entries are cleaned up as the
+/// list loads; case collisions are only reported, since the Dedupe filter can drop them.
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    /// How many entries were read, counting empty lines.
+    pub entries: usize,
+    /// How many entries were empty or only whitespace.
+    pub empty_lines: usize,
+    /// Entries with whitespace before or after the word.
+    pub whitespace: Vec<String>,
+    /// Entries with whitespace inside, which a space separator would make ambiguous.
+    pub inner_spaces: Vec<String>,
+    /// Words listed more than once, with how many times they appear.
+    pub duplicates: Vec<(String, usize)>,
+    /// Groups of different entries that differ only by case, like "Bank" and "bank".
+    pub case_collisions: Vec<Vec<String>>,
+}
+
+impl ValidationReport {
+    /// Checks `entries` as read, returning the report and the entries with whitespace
+    /// trimmed and empty and repeated entries dropped.
+    fn check(entries: &[&str]) -> (Self, Vec<String>) {
+        let mut report = ValidationReport { entries: entries.len(), ..Default::default() };
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let mut words = Vec::new();
+        for &entry in entries {
+            let word = entry.trim();
+            if word.is_empty() {
+                report.empty_lines += 1;
+                continue;
+            }
+            if word.len() != entry.len() {
+                report.whitespace.push(entry.to_string());
+            }
+            if word.contains(char::is_whitespace) {
+                report.inner_spaces.push(word.to_string());
+            }
+            let count = counts.entry(word).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                words.push(word.to_string());
+            }
+        }
+        report.duplicates = words
+            .iter()
+            .filter_map(|word| Some((word.clone(), counts[word.as_str()])).filter(|&(_, count)| count > 1))
+            .collect();
+
+        let mut by_case: HashMap<String, Vec<String>> = HashMap::new();
+        for word in &words {
+            by_case.entry(word.to_lowercase()).or_default().push(word.clone());
+        }
+        report.case_collisions = by_case.into_values().filter(|group| group.len() > 1).collect();
+        report.case_collisions.sort();
+        (report, words)
+    }
+
+    /// Whether nothing was found.
+    pub fn is_clean(&self) -> bool {
+        self.empty_lines == 0
+            && self.whitespace.is_empty()
+            && self.inner_spaces.is_empty()
+            && self.duplicates.is_empty()
+            && self.case_collisions.is_empty()
+    }
+
+    /// A short summary for the status bar, such as "2 empty lines and 3 repeated words dropped".
+    pub fn brief(&self) -> String {
+        let mut dropped = Vec::new();
+        if self.empty_lines > 0 {
+            dropped.push(plural(self.empty_lines, "empty line"));
+        }
+        if !self.duplicates.is_empty() {
+            dropped.push(plural(self.duplicates.len(), "repeated word"));
+        }
+        let mut parts = Vec::new();
+        if !dropped.is_empty() {
+            parts.push(format!("{} dropped", dropped.join(" and ")));
+        }
+        if !self.whitespace.is_empty() {
+            parts.push(format!("{} trimmed", plural(self.whitespace.len(), "padded entry")));
+        }
+        if !self.inner_spaces.is_empty() {
+            parts.push(format!("{} with spaces inside", plural(self.inner_spaces.len(), "word")));
+        }
+        if !self.case_collisions.is_empty() {
+            parts.push(plural(self.case_collisions.len(), "case collision"));
+        }
+        parts.join(", ")
+    }
+
+    /// One line per problem found, each with a few examples.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.empty_lines > 0 {
+            lines.push(format!("{} dropped.", plural(self.empty_lines, "empty line")));
+        }
+        if !self.whitespace.is_empty() {
+            let examples = self.whitespace.iter().map(|entry| format!("{:?}", entry));
+            lines.push(format!(
+                "{} with whitespace around the word, trimmed, e.g. {}.",
+                plural(self.whitespace.len(), "entry"),
+                examples_of(examples)
+            ));
+        }
+        if !self.inner_spaces.is_empty() {
+            let examples = self.inner_spaces.iter().map(|word| format!("{:?}", word));
+            lines.push(format!(
+                "{} with spaces inside, which a space separator would make ambiguous, e.g. {}.",
+                plural(self.inner_spaces.len(), "word"),
+                examples_of(examples)
+            ));
+        }
+        if !self.duplicates.is_empty() {
+            let examples = self.duplicates.iter().map(|(word, count)| format!("{} ({}x)", word, count));
+            lines.push(format!(
+                "{} listed more than once, kept once, e.g. {}.",
+                plural(self.duplicates.len(), "word"),
+                examples_of(examples)
+            ));
+        }
+        if !self.case_collisions.is_empty() {
+            let examples = self.case_collisions.iter().map(|group| group.join("/"));
+            lines.push(format!(
+                "{} of words differing only by case, which count as one word once casing is applied, e.g. {}.",
+                plural(self.case_collisions.len(), "group"),
+                examples_of(examples)
+            ));
+        }
+        lines
+    }
+}
+
+impl Wordlist {
+    /// Entropy of one word drawn uniformly from the distinct words.
+    pub fn bits_per_word(&self) -> f64 {
+        (self.unique_count.max(1) as f64).log2()
+    }
+
+    /// A full report on the list: where it came from, its statistics and what validation found.
+    pub fn info(&self) -> String {
+        let source = match &self.source {
+            Some(path) => path.display().to_string(),
+            None => "built into Passgen".to_string(),
+        };
+        let lengths: Vec<String> =
+            self.lengths.iter().map(|(length, count)| format!("{}: {}", length, count)).collect();
+        let mut lines = vec![
+            format!("Wordlist: {}", self.name),
+            format!("Source: {}", source),
+            format!("SHA-256: {}", self.sha256),
+            format!("Entries read: {}", self.report.entries),
+            format!(
+                "Words in use: {} ({} unique), {:.2} bits per word",
+                self.words.len(),
+                self.unique_count,
+                self.bits_per_word()
+            ),
+            format!("Word lengths (characters: words): {}", lengths.join(", ")),
+            match self.unique_prefix {
+                Some(letters) => format!("Unique prefixes: every word is told apart by its first {} letters.", letters),
+                None => "Unique prefixes: none, as some words differ only by case.".to_string(),
+            },
+        ];
+        match self.report.is_clean() {
+            true => lines.push("Validation: no problems found.".to_string()),
+            false => {
+                lines.push("Validation:".to_string());
+                lines.extend(self.report.lines().into_iter().map(|line| format!("  - {}", line)));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Loads a list of words from a specified text file.
+/// Words are NFC-normalized so the same word always has the same characters.
+/// Numbered Diceware lists are detected, stripped of their dice numbers and checked for completeness;
+/// other lists are cleaned and go through the filters, and the status says how many words each one kept.
+pub fn load_words_from_file(path: &Path, filters: &WordFilters) -> Result<Wordlist, String> {
+    if !path.exists() {
+        return Err(format!("Wordlist not found at: {:?}", path));
+    }
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read wordlist: {}", e))?;
+    let content = String::from_utf8(bytes).map_err(|e| format!("Failed to read wordlist: {}", e))?;
+    let name = format!("{:?}", path.file_name().unwrap_or_default());
+    let mut wordlist = load_words_from_str(&content, &name, filters);
+    wordlist.source = Some(path.to_path_buf());
+    Ok(wordlist)
+}
+
+/// Loads the default wordlist: the file at `path` if there is one, otherwise the built-in list.
+pub fn load_default_words(path: &Path, filters: &WordFilters) -> Result<Wordlist, String> {
+    if path.exists() {
+        return load_words_from_file(path, filters);
+    }
+    let mut wordlist = load_words_from_str(&built_in_words()?, BUILT_IN_WORDLIST_NAME, filters);
+    wordlist.status = format!("No wordlist at {:?}. {}", path, wordlist.status);
+    Ok(wordlist)
+}
+
+/// Unpacks the built-in wordlist.
+pub fn built_in_words() -> Result<String, String> {
+    let bytes = miniz_oxide::inflate::decompress_to_vec(BUILT_IN_WORDS)
+        .map_err(|e| format!("Failed to unpack the {}: {}", BUILT_IN_WORDLIST_NAME, e))?;
+    String::from_utf8(bytes).map_err(|e| format!("Failed to unpack the {}: {}", BUILT_IN_WORDLIST_NAME, e))
+}
+
+/// Parses the text of a wordlist, which may be a plain, Diceware or tagged list, checks it and applies the filters.
+///
+/// `name` says where the list came from in the status message.
+pub fn load_words_from_str(content: &str, name: &str, filters: &WordFilters) -> Wordlist {
+    let sha256 = Sha256::digest(content.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+    let content = nfc(content);
+    let (words, dice, report, mut status) = if let Some(list) = parse_diceware_list(&content) {
+        let status = if list.is_complete() {
+            format!("Loaded complete {}-dice Diceware list ({} words) from {}", list.dice, list.words.len(), name)
+        } else {
+            format!(
+                "Warning: {}-dice Diceware list {} is incomplete ({} of {} numbers missing, {} duplicated)",
+                list.dice,
+                name,
+                list.missing.len(),
+                diceware_list_size(list.dice),
+                list.duplicates.len()
+            )
+        };
+        // Dropping words would break the mapping from dice rolls to words, so problems are only reported.
+        let status = match *filters == WordFilters::default() {
+            true => status,
+            false => format!("{} (wordlist filters are not applied to Diceware lists)", status),
+        };
+        let (report, _) = ValidationReport::check(&list.words.iter().map(String::as_str).collect::<Vec<_>>());
+        let dice = Some(list.dice).filter(|_| list.is_complete());
+        (list.words, dice, report, status)
+    } else {
+        // A tagged list for sentence passphrases also works as a plain wordlist.
+        let entries: Vec<&str> = match is_tagged_list(&content) {
+            true => {
+                let entries = content.lines().filter(|line| !line.trim_start().starts_with('#'));
+                entries.map(|line| line.split_whitespace().next().unwrap_or_default()).collect()
+            }
+            false => content.lines().collect(),
+        };
+        let (report, words) = ValidationReport::check(&entries);
+        let loaded = words.len();
+        let (words, steps) = filter_words(words, filters);
+        let mut status = format!("Successfully loaded {} words from {}", loaded, name);
+        if !steps.is_empty() {
+            let steps: Vec<String> = steps.iter().map(|(name, left)| format!("{}: {}", name, left)).collect();
+            status.push_str(&format!(", {} after filters ({})", words.len(), steps.join(", ")));
+        }
+        (words, None, report, status)
+    };
+    if !report.is_clean() {
+        status.push_str(&format!("; {}", report.brief()));
+    }
+    let unique_count = words.iter().collect::<HashSet<_>>().len();
+    let mut lengths = BTreeMap::new();
+    for word in &words {
+        *lengths.entry(grapheme_count(word)).or_insert(0) += 1;
+    }
+    let unique_prefix = unique_prefix_length(&words);
+    let name = name.to_string();
+    Wordlist { name, source: None, sha256, words, dice, unique_count, lengths, unique_prefix, report, status }
+}
+
+/// The fewest first letters that tell every word apart, ignoring case, like 4 for "abandon" and "ability";
+/// `None` if two words differ only by case.
+///
+/// A word shorter than that is typed in full, so "cat" and "cats" are told apart by 4 letters.
+pub fn unique_prefix_length(words: &[String]) -> Option<usize> {
+    let mut sorted: Vec<Vec<String>> = words.iter().map(|word| graphemes(&word.to_lowercase())).collect();
+    sorted.sort();
+    // The longest prefix a word shares with any other is the one it shares with a neighbour in sorted order.
+    let mut letters = 1;
+    for pair in sorted.windows(2) {
+        if pair[0] == pair[1] {
+            return None;
+        }
+        let shared = pair[0].iter().zip(&pair[1]).take_while(|(a, b)| a == b).count();
+        letters = letters.max(shared + 1);
+    }
+    Some(letters)
+}
+
+/// Says "1 word" or "3 words", and "1 entry" or "3 entries".
+fn plural(count: usize, noun: &str) -> String {
+    match (count, noun.strip_suffix('y')) {
+        (1, _) => format!("1 {}", noun),
+        (_, Some(stem)) => format!("{} {}ies", count, stem),
+        (_, None) => format!("{} {}s", count, noun),
+    }
+}
+
+/// Joins the first few examples, noting when there are more.
+fn examples_of(examples: impl ExactSizeIterator<Item = String>) -> String {
+    let more = examples.len().saturating_sub(REPORT_EXAMPLES);
+    let shown: Vec<String> = examples.take(REPORT_EXAMPLES).collect();
+    match more {
+        0 => shown.join(", "),
+        more => format!("{} and {} more", shown.join(", "), more),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::password::WordSampler;
+    use crate::app::state::WordStyle;
+
+    fn load(content: &str) -> Wordlist {
+        load_words_from_str(content, "test", &WordFilters::default())
+    }
+
+    #[test]
+    fn clean_lists_report_nothing() {
+        let wordlist = load("apple\nbanana\ncherry\n");
+        assert_eq!(wordlist.words, ["apple", "banana", "cherry"]);
+        assert!(wordlist.report.is_clean());
+        assert_eq!(wordlist.status, "Successfully loaded 3 words from test");
+    }
+
+    #[test]
+    fn duplicates_are_kept_once() {
+        let wordlist = load("apple\nbanana\napple\napple\ncherry\nbanana\n");
+        assert_eq!(wordlist.words, ["apple", "banana", "cherry"]);
+        let expected = "2 words listed more than once, kept once, e.g. apple (3x), banana (2x).";
+        assert_eq!(wordlist.report.lines(), [expected]);
+        assert!(wordlist.status.ends_with("; 2 repeated words dropped"), "{}", wordlist.status);
+    }
+
+    #[test]
+    fn empty_lines_are_dropped() {
+        let wordlist = load("apple\n\n   \nbanana\n\n");
+        assert_eq!(wordlist.words, ["apple", "banana"]);
+        assert_eq!(wordlist.report.lines(), ["3 empty lines dropped."]);
+        assert_eq!(wordlist.report.brief(), "3 empty lines dropped");
+    }
+
+    #[test]
+    fn spacing_and_case_problems() {
+        let wordlist = load(" apple\nice cream\nApple\n");
+        assert_eq!(wordlist.words, ["apple", "ice cream", "Apple"]);
+        assert_eq!(
+            wordlist.report.lines(),
+            [
+                "1 entry with whitespace around the word, trimmed, e.g. \" apple\".",
+                "1 word with spaces inside, which a space separator would make ambiguous, e.g. \"ice cream\".",
+                "1 group of words differing only by case, which count as one word once casing is applied, \
+                 e.g. apple/Apple.",
+            ]
+        );
+    }
+
+    #[test]
+    fn incomplete_diceware_lists_cannot_be_rolled() {
+        let complete = load("1 one\n2 two\n3 three\n4 four\n5 five\n6 six\n");
+        assert_eq!(complete.dice, Some(1));
+
+        let short = load("1 one\n2 two\n3 three\n4 four\n5 five\n");
+        assert_eq!(short.dice, None);
+        let expected = "Warning: 1-dice Diceware list test is incomplete (1 of 6 numbers missing, 0 duplicated)";
+        assert_eq!(short.status, expected);
+
+        let doubled = load("1 one\n1 uno\n2 two\n3 three\n4 four\n5 five\n6 six\n");
+        assert_eq!(doubled.dice, None);
+        assert!(doubled.status.contains("(0 of 6 numbers missing, 1 duplicated)"), "{}", doubled.status);
+    }
+
+    #[test]
+    fn short_lists_cannot_fill_a_passphrase() {
+        let wordlist = load("apple\nbanana\napple\n");
+        let style = WordStyle::default();
+        let error = WordSampler::new(3, &wordlist.words, &style).err().unwrap();
+        assert_eq!(error, "Not enough words in words.txt (found 2, need at least 3).");
+    }
+}
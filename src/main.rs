@@ -6,19 +6,49 @@
 
 mod app;
 
-use crate::app::cli::{run_char_cli_mode, run_word_cli_mode};
-use crate::app::state::CliArgs;
+use crate::app::cli::{
+    check_cli_profile, run_build_bloom_cli_mode, run_char_cli_mode, run_check_cli_mode, run_derive_cli_mode,
+    run_dice_cli_mode, run_pin_cli_mode, run_pronounceable_cli_mode, run_rules_cli_mode, run_sentence_cli_mode,
+    run_template_cli_mode, run_word_cli_mode, run_wordlist_info_cli_mode,
+};
+use crate::app::state::{CliArgs, CliCommand, WordlistCommand};
 use crate::app::ui::run_gui_mode;
 use clap::Parser;
 
 /// The main entry point of the application.
 fn main() {
     let args = CliArgs::parse();
+    if let Err(e) = check_cli_profile(&args) {
+        eprintln!("Error: {}", e);
+        return;
+    }
 
-    if let Some(length) = args.n {
-        run_char_cli_mode(length);
+    if let Some(CliCommand::Check) = args.command {
+        run_check_cli_mode(&args);
+    } else if let Some(CliCommand::BuildBloom { hash_file, output, false_positive_rate }) = &args.command {
+        run_build_bloom_cli_mode(hash_file, output, *false_positive_rate);
+    } else if let Some(CliCommand::Wordlist { command: WordlistCommand::Info { name } }) = &args.command {
+        run_wordlist_info_cli_mode(name.as_deref(), &args);
+    } else if let Some(length) = args.n {
+        run_char_cli_mode(length, &args);
     } else if let Some(count) = args.w {
-        run_word_cli_mode(count);
+        if args.dice {
+            run_dice_cli_mode(count, &args);
+        } else {
+            run_word_cli_mode(count, &args);
+        }
+    } else if let Some(template) = &args.template {
+        run_template_cli_mode(template, &args);
+    } else if let Some(length) = args.pronounceable {
+        run_pronounceable_cli_mode(length, &args);
+    } else if let Some(grammar) = &args.sentence {
+        run_sentence_cli_mode(grammar, &args);
+    } else if let Some(length) = args.pin {
+        run_pin_cli_mode(length, &args);
+    } else if args.rules.is_some() || args.rules_file.is_some() {
+        run_rules_cli_mode(&args);
+    } else if let Some(site) = &args.derive {
+        run_derive_cli_mode(site, &args);
     } else {
         run_gui_mode();
     }